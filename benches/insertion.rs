@@ -1,26 +1,15 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 
-use rand::{
-    distributions::{Distribution, Uniform},
-    rngs::StdRng,
-    SeedableRng,
-};
 use voronoids::delaunay_tree::{DelaunayTree, TreeUpdate};
+use voronoids::points::{
+    gaussian_mixture, lattice, perturb, poisson_disk, uniform_ball, uniform_box, GaussianCluster,
+    Lattice,
+};
 use voronoids::scheduler::make_queue;
 
 fn benchmark_locate(c: &mut Criterion) {
     const N_POINTS: usize = 10;
-    let mut vertices = vec![];
-    let mut rng = StdRng::seed_from_u64(0);
-    let dist = Uniform::from(0.0..1.0);
-    for _ in 0..N_POINTS {
-        let point = [
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-        ];
-        vertices.push(point);
-    }
+    let vertices = uniform_box::<3>(N_POINTS, [0.0; 3], [1.0; 3], 0);
     let mut delaunay_tree = DelaunayTree::<3, 4>::new(vertices.clone());
     let n_points = delaunay_tree.vertices.len();
     for i in 0..N_POINTS {
@@ -29,15 +18,7 @@ fn benchmark_locate(c: &mut Criterion) {
     }
     let new_vertex = [0.5, 0.5, 0.5];
     const N_TEST_POINTS: usize = 100000;
-    let mut vertices2: Vec<[f64; 3]> = vec![];
-    for _ in 0..N_TEST_POINTS {
-        let point = [
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-        ];
-        vertices2.push(point);
-    }
+    let vertices2 = uniform_box::<3>(N_TEST_POINTS, [0.0; 3], [1.0; 3], 1);
     let queue = make_queue(vertices2.clone(), &delaunay_tree);
     let mut group = c.benchmark_group("insertion_group");
     group.significance_level(0.1).sample_size(10);
@@ -45,24 +26,14 @@ fn benchmark_locate(c: &mut Criterion) {
     // c.bench_function("locate 10000", |b| b.iter(|| delaunay_tree.locate(new_vertex)));
     // c.bench_function("update 10000", |b| b.iter(|| TreeUpdate::new(10001, new_vertex, &delaunay_tree)));
     // c.bench_function("make_queue 10000", |b| b.iter(|| make_queue(vertices2.clone(), &delaunay_tree)));
-    group.bench_function("find_placement 10000", |b| {
-        b.iter(|| voronoids::scheduler::find_placement(&queue))
+    group.bench_function("schedule_batches 10000", |b| {
+        b.iter(|| voronoids::scheduler::schedule_batches(&queue))
     });
 }
 
 fn benchmark_geometry(c: &mut Criterion) {
     const N_POINTS: usize = 10000;
-    let mut vertices = vec![];
-    let mut rng = StdRng::seed_from_u64(0);
-    let dist = Uniform::from(0.0..1.0);
-    for _ in 0..N_POINTS {
-        let point = [
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-        ];
-        vertices.push(point);
-    }
+    let vertices = uniform_box::<3>(N_POINTS, [0.0; 3], [1.0; 3], 0);
     let mut delaunay_tree = DelaunayTree::<3, 4>::new(vertices.clone());
     let n_points = delaunay_tree.vertices.len();
     for i in 0..N_POINTS {
@@ -71,15 +42,7 @@ fn benchmark_geometry(c: &mut Criterion) {
     }
     let new_vertex = [0.5, 0.5, 0.5];
     const N_TEST_POINTS: usize = 10000;
-    let mut vertices2: Vec<[f64; 3]> = vec![];
-    for _ in 0..N_TEST_POINTS {
-        let point = [
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-        ];
-        vertices2.push(point);
-    }
+    let vertices2 = uniform_box::<3>(N_TEST_POINTS, [0.0; 3], [1.0; 3], 1);
     let queue = make_queue(vertices2.clone(), &delaunay_tree);
     c.bench_function("locate 10000", |b| {
         b.iter(|| delaunay_tree.locate(new_vertex))
@@ -95,5 +58,51 @@ fn benchmark_geometry(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, benchmark_locate);
+fn benchmark_distributions(c: &mut Criterion) {
+    // Construction of 10000 point triangulations, uniform points being the easy case
+    let clusters: Vec<GaussianCluster<3>> = uniform_box::<3>(8, [0.0; 3], [1.0; 3], 0)
+        .into_iter()
+        .map(|center| GaussianCluster {
+            center,
+            sigma: 0.02,
+            weight: 1.0,
+        })
+        .collect();
+    let distributions = [
+        (
+            "uniform box",
+            uniform_box::<3>(10000, [0.0; 3], [1.0; 3], 0),
+        ),
+        ("uniform ball", uniform_ball::<3>(10000, [0.5; 3], 0.5, 0)),
+        (
+            "poisson disk",
+            poisson_disk::<3>([0.0; 3], [1.0; 3], 0.04, 0),
+        ),
+        (
+            "gaussian mixture",
+            gaussian_mixture::<3>(10000, &clusters, 0),
+        ),
+        (
+            "perturbed fcc lattice",
+            perturb(
+                &lattice::<3>(Lattice::FaceCentered, [14, 14, 13], 0.07, [0.0; 3]),
+                1e-7,
+                0,
+            ),
+        ),
+    ];
+    let mut group = c.benchmark_group("distribution_group");
+    group.significance_level(0.1).sample_size(10);
+    for (name, points) in distributions {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
+                delaunay_tree.insert_points(&points);
+                delaunay_tree
+            })
+        });
+    }
+}
+
+criterion_group!(benches, benchmark_locate, benchmark_distributions);
 criterion_main!(benches);
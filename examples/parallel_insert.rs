@@ -1,23 +1,12 @@
-use rand::distributions::{Distribution, Uniform};
-use rand::prelude::*;
 use std::time::Instant;
 use voronoids::delaunay_tree::{DelaunayTree, TreeUpdate};
+use voronoids::points::uniform_box;
 
 fn main() {
     const N_POINTS: usize = 100000;
     const N_TEST_POINTS: usize = 1000000;
     const BATCH_SIZE: usize = 1000000;
-    let mut vertices = vec![];
-    let mut rng = StdRng::seed_from_u64(0);
-    let dist = Uniform::from(0.0..1.0);
-    for _ in 0..N_POINTS {
-        let point = [
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-        ];
-        vertices.push(point);
-    }
+    let vertices = uniform_box::<3>(N_POINTS, [0.0; 3], [1.0; 3], 0);
     let mut delaunay_tree = DelaunayTree::<3, 4>::new(vertices.clone());
     let n_points = delaunay_tree.vertices.len();
     println!("Start constructing the initial tree");
@@ -32,15 +21,7 @@ fn main() {
         duration
     );
     println!("Generating test points");
-    let mut vertices2: Vec<[f64; 3]> = vec![];
-    for _ in 0..N_TEST_POINTS {
-        let point = [
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-        ];
-        vertices2.push(point);
-    }
+    let vertices2 = uniform_box::<3>(N_TEST_POINTS, [0.0; 3], [1.0; 3], 1);
     // println!("Benchmarking update speed");
     // let start = Instant::now();
     // let queue = make_queue(vertices2.clone(), &delaunay_tree);
@@ -1,22 +1,11 @@
 
-use rand::distributions::{Distribution, Uniform};
-use rand::prelude::*;
 use voronoids::delaunay_tree::{DelaunayTree, TreeUpdate};
+use voronoids::points::uniform_box;
 use std::time::Instant;
 
 fn main() {
     const N_POINTS: usize = 100000;
-    let mut vertices = vec![];
-    let mut rng = StdRng::seed_from_u64(0);
-    let dist = Uniform::from(0.0..1.0);
-    for _ in 0..N_POINTS {
-        let point = [
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-        ];
-        vertices.push(point);
-    }
+    let vertices = uniform_box::<3>(N_POINTS, [0.0; 3], [1.0; 3], 0);
     let mut delaunay_tree = DelaunayTree::<3, 4>::new(vertices.clone());
     let n_points = delaunay_tree.vertices.len();
     println!("Start constructing the initial tree");
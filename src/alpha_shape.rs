@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+use crate::geometry::{face_circumcenter, in_sphere};
+
+// Alpha complexes and the alpha filtration. Alpha values are radii, so that a simplex of
+// the triangulation enters the complex once alpha reaches its circumradius. A face of
+// lower dimension enters at the radius of its smallest circumsphere, unless that sphere
+// contains the opposite vertex of one of its cofaces (the face is attached), in which
+// case it enters together with its first coface. Only real simplices and their faces
+// take part. Faces are given by their vertex ids in increasing order.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiltrationFace {
+    pub vertices: Vec<usize>,
+    pub alpha: f64,
+    pub attached: bool,
+}
+
+impl FiltrationFace {
+    pub fn dimension(&self) -> usize {
+        self.vertices.len() - 1
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlphaComplex {
+    pub alpha: f64,
+    // All faces with an alpha value up to alpha, in filtration order
+    pub faces: Vec<FiltrationFace>,
+    // Ids of the full-dimensional simplices of the complex, sorted
+    pub simplices: Vec<usize>,
+}
+
+// Boundary of the alpha shape as a mesh of triangles in 3D or segments in 2D. Facets
+// index points and are oriented with their normal pointing out of the shape.
+#[derive(Debug, Clone, Default)]
+pub struct SurfaceMesh<const N: usize> {
+    pub points: Vec<[f64; N]>,
+    // Input point index of every mesh point
+    pub point_indices: Vec<usize>,
+    pub facets: Vec<[usize; N]>,
+}
+
+// Vertex ids of a face, sorted and padded with usize::MAX
+type FaceKey<const M: usize> = [usize; M];
+
+fn face_key<const M: usize>(vertices: &[usize]) -> FaceKey<M> {
+    let mut key = [usize::MAX; M];
+    key[..vertices.len()].copy_from_slice(vertices);
+    key[..vertices.len()].sort_unstable();
+    key
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    pub fn alpha_filtration(&self) -> Vec<FiltrationFace> {
+        // Faces of all dimensions sorted by alpha value, then dimension, then vertices,
+        // so that every face comes after its own faces
+        let simplices = self.real_simplices();
+        let mut faces: Vec<FiltrationFace> = simplices
+            .par_iter()
+            .map(|id| {
+                let simplex = self.simplices.get(id).unwrap();
+                let mut vertices = simplex.vertices.to_vec();
+                vertices.sort_unstable();
+                FiltrationFace {
+                    vertices,
+                    alpha: simplex.radius,
+                    attached: false,
+                }
+            })
+            .collect();
+        let mut cofaces: Vec<FiltrationFace> = faces.clone();
+
+        // Walk down the dimensions, every face gets its alpha value from its cofaces
+        for size in (1..M).rev() {
+            // Smallest alpha among the cofaces and whether a coface attaches the face
+            let mut found: HashMap<FaceKey<M>, (f64, bool)> = HashMap::new();
+            for coface in &cofaces {
+                for skip in 0..=size {
+                    let vertices: Vec<usize> = coface
+                        .vertices
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| *i != skip)
+                        .map(|(_, id)| *id)
+                        .collect();
+                    let key = face_key::<M>(&vertices);
+                    let entry = found.entry(key).or_insert((f64::INFINITY, false));
+                    entry.0 = entry.0.min(coface.alpha);
+                    if !entry.1 {
+                        let (center, radius) = self.smallest_sphere(&vertices);
+                        let opposite = self.vertices.get(&coface.vertices[skip]).unwrap();
+                        entry.1 = in_sphere(opposite.coordinates, center, radius);
+                    }
+                }
+            }
+            let mut keys: Vec<FaceKey<M>> = found.keys().copied().collect();
+            keys.sort_unstable();
+            cofaces = keys
+                .par_iter()
+                .map(|key| {
+                    let vertices = key[..size].to_vec();
+                    let (alpha, attached) = found[key];
+                    let alpha = if attached {
+                        alpha
+                    } else {
+                        self.smallest_sphere(&vertices).1
+                    };
+                    FiltrationFace {
+                        vertices,
+                        alpha,
+                        attached,
+                    }
+                })
+                .collect();
+            faces.extend(cofaces.iter().cloned());
+        }
+        faces.par_sort_unstable_by(|a, b| {
+            a.alpha
+                .total_cmp(&b.alpha)
+                .then_with(|| a.vertices.len().cmp(&b.vertices.len()))
+                .then_with(|| a.vertices.cmp(&b.vertices))
+        });
+        faces
+    }
+
+    fn smallest_sphere(&self, vertices: &[usize]) -> ([f64; N], f64) {
+        // Smallest sphere through the vertices of a face below full dimension
+        let points: Vec<[f64; N]> = vertices
+            .iter()
+            .map(|id| self.vertices.get(id).unwrap().coordinates)
+            .collect();
+        let center = face_circumcenter(&points);
+        let radius = points[0]
+            .iter()
+            .zip(center.iter())
+            .map(|(x, c)| (x - c) * (x - c))
+            .sum::<f64>()
+            .sqrt();
+        (center, radius)
+    }
+
+    pub fn alpha_complex(&self, alpha: f64) -> AlphaComplex {
+        self.alpha_complex_from(&self.alpha_filtration(), alpha)
+    }
+
+    pub fn alpha_complex_from(&self, filtration: &[FiltrationFace], alpha: f64) -> AlphaComplex {
+        // Cuts a filtration of this tree computed once with alpha_filtration, to take
+        // complexes at many alpha values without sorting the faces again
+        let end = filtration.partition_point(|face| face.alpha <= alpha);
+        AlphaComplex {
+            alpha,
+            faces: filtration[..end].to_vec(),
+            simplices: self.alpha_simplices(alpha),
+        }
+    }
+
+    pub fn alpha_simplices(&self, alpha: f64) -> Vec<usize> {
+        // Real simplices with a circumradius up to alpha, sorted by id
+        self.real_simplices()
+            .into_iter()
+            .filter(|id| self.simplices.get(id).unwrap().radius <= alpha)
+            .collect()
+    }
+
+    pub fn alpha_shape_boundary(&self, alpha: f64) -> Vec<[usize; N]> {
+        // Facets of the simplices of the alpha complex that are not shared with another
+        // one, as vertex ids oriented outwards. Faces of the complex that bound no
+        // full-dimensional simplex are left out.
+        let in_shape = |id: usize| {
+            let simplex = self.simplices.get(&id).unwrap();
+            Self::is_real_simplex(&simplex) && simplex.radius <= alpha
+        };
+        let mut facets = vec![];
+        for id in self.alpha_simplices(alpha) {
+            let vertices = self.oriented_vertices(id);
+            for k in 0..M {
+                let inside = self
+                    .opposite_neighbor(id, vertices[k])
+                    .is_some_and(in_shape);
+                if !inside {
+                    facets.push(Self::facet(vertices, k));
+                }
+            }
+        }
+        facets
+    }
+
+    pub fn alpha_shape_surface(&self, alpha: f64) -> SurfaceMesh<N> {
+        // The boundary with its points renumbered from 0 in order of first use
+        let mut mesh = SurfaceMesh::default();
+        let mut renumbered: HashMap<usize, usize> = HashMap::new();
+        for facet in self.alpha_shape_boundary(alpha) {
+            let mut indices = [0; N];
+            for (index, id) in indices.iter_mut().zip(facet.iter()) {
+                *index = *renumbered.entry(*id).or_insert_with(|| {
+                    mesh.points.push(self.vertices.get(id).unwrap().coordinates);
+                    mesh.point_indices.push(id - Self::N_SUPER_VERTICES);
+                    mesh.points.len() - 1
+                });
+            }
+            mesh.facets.push(indices);
+        }
+        mesh
+    }
+}
@@ -1,35 +1,211 @@
-use crate::geometry::{bounding_sphere, circumsphere, in_sphere};
-use crate::scheduler::{find_placement, make_queue};
+use crate::geometry::{bounding_sphere, circumsphere, in_sphere, signed_volume};
+use crate::query::QueryMethod;
+use crate::scheduler::{make_queue, schedule_batches};
 use dashmap::DashMap;
 use kiddo::{KdTree, SquaredEuclidean};
 use rayon::iter::{
-    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelExtend,
-    ParallelIterator,
+    IndexedParallelIterator, IntoParallelRefIterator, ParallelExtend, ParallelIterator,
 };
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Simplex<const N: usize, const M: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::array"))]
     pub vertices: [usize; M],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::array"))]
     pub center: [f64; N],
     pub radius: f64,
     pub neighbors: Vec<usize>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Vertex<const N: usize> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::array"))]
     pub coordinates: [f64; N],
     pub simplex: Vec<usize>,
 }
 
+// insert_points inserts this many points one by one before switching to batches
+const SEQUENTIAL_POINTS: usize = 100_001;
+// Serial insertion reports its progress every PROGRESS_INTERVAL points
+const PROGRESS_INTERVAL: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub points_inserted: usize,
+    pub total_points: usize,
+    // Current parallel batch, counting from 1, and the number of batches.
+    // Both are 0 while points are inserted one by one.
+    pub batch: usize,
+    pub n_batches: usize,
+    pub elapsed: Duration,
+}
+
+pub type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+// Where points are inserted. Only insertion follows this setting: validation, queries
+// and the other analyses run on the rayon pool they are called from, the global one
+// unless they are called inside ThreadPool::install.
+#[derive(Debug, Clone, Default)]
+pub enum Parallelism {
+    // Rayon's global thread pool
+    #[default]
+    Global,
+    Pool(Arc<ThreadPool>),
+    // Insert one point after the other without touching rayon
+    Serial,
+}
+
+impl Parallelism {
+    pub fn threads(n_threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        // A pool with this many threads, built once and shared by every insertion
+        let pool = ThreadPoolBuilder::new().num_threads(n_threads).build()?;
+        Ok(Parallelism::Pool(Arc::new(pool)))
+    }
+}
+
+type NewSimplices<const N: usize, const M: usize> = (
+    Vec<[usize; M]>,
+    Vec<[f64; N]>,
+    Vec<f64>,
+    Vec<(usize, usize)>,
+);
+
 pub struct DelaunayTree<const N: usize, const M: usize> {
     // Make sure M = N + 1
     pub kdtree: KdTree<f64, N>,
     pub vertices: DashMap<usize, Vertex<N>>,
     pub simplices: DashMap<usize, Simplex<N, M>>,
     pub max_simplex_id: usize,
+    // Give the same simplex ids, neighbor order and Vertex::simplex order for any
+    // number of threads, at the cost of a serial pass over every parallel batch
+    pub deterministic: bool,
+    pub parallelism: Parallelism,
+    pub progress: Option<ProgressCallback>,
+    pub query_method: QueryMethod,
 }
 
 impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    // The bounding simplex and its ghost vertices take the first 2 * M vertex ids,
+    // input points are numbered from there on in insertion order.
+    pub const N_SUPER_VERTICES: usize = 2 * M;
+
+    pub fn is_super_vertex(vertex_id: usize) -> bool {
+        vertex_id < Self::N_SUPER_VERTICES
+    }
+
+    pub fn is_ghost_vertex(vertex_id: usize) -> bool {
+        (M..Self::N_SUPER_VERTICES).contains(&vertex_id)
+    }
+
+    pub fn is_ghost_simplex(simplex: &Simplex<N, M>) -> bool {
+        // Ghost simplices cap the facets of the super-simplex, they are flat
+        // and only know their neighbor inside the super-simplex
+        simplex.vertices.iter().any(|&id| Self::is_ghost_vertex(id))
+    }
+
+    pub fn is_real_simplex(simplex: &Simplex<N, M>) -> bool {
+        simplex
+            .vertices
+            .iter()
+            .all(|&id| !Self::is_super_vertex(id))
+    }
+
+    pub fn real_simplices(&self) -> Vec<usize> {
+        let mut output: Vec<usize> = self
+            .simplices
+            .iter()
+            .filter(|simplex| Self::is_real_simplex(simplex.value()))
+            .map(|simplex| *simplex.key())
+            .collect();
+        output.sort_unstable();
+        output
+    }
+
+    pub fn simplex_coordinates(&self, simplex: &Simplex<N, M>) -> [[f64; N]; M] {
+        let mut coordinates = [[0.0; N]; M];
+        for (i, id) in simplex.vertices.iter().enumerate() {
+            coordinates[i] = self.vertices.get(id).unwrap().coordinates;
+        }
+        coordinates
+    }
+
+    pub fn oriented_vertices(&self, simplex_id: usize) -> [usize; M] {
+        // Vertices of the simplex with the first two swapped if it is negatively oriented
+        let simplex = self.simplices.get(&simplex_id).unwrap();
+        let mut vertices = simplex.vertices;
+        if signed_volume(self.simplex_coordinates(&simplex)) < 0.0 {
+            vertices.swap(0, 1);
+        }
+        vertices
+    }
+
+    pub fn opposite_neighbor(&self, simplex_id: usize, vertex_id: usize) -> Option<usize> {
+        // The neighbor sharing the facet that does not contain vertex_id
+        self.simplices
+            .get(&simplex_id)
+            .unwrap()
+            .neighbors
+            .iter()
+            .find(|neighbor| {
+                !self
+                    .simplices
+                    .get(neighbor)
+                    .unwrap()
+                    .vertices
+                    .contains(&vertex_id)
+            })
+            .copied()
+    }
+
+    pub fn hull_facets(&self) -> Vec<[usize; N]> {
+        // Facets of real simplices whose opposite neighbor touches the super-simplex,
+        // oriented with their normal pointing out of the hull
+        let mut facets = vec![];
+        for id in self.real_simplices() {
+            let vertices = self.oriented_vertices(id);
+            for k in 0..M {
+                let on_hull = match self.opposite_neighbor(id, vertices[k]) {
+                    Some(neighbor) => {
+                        !Self::is_real_simplex(&self.simplices.get(&neighbor).unwrap())
+                    }
+                    None => true,
+                };
+                if on_hull {
+                    facets.push(Self::facet(vertices, k));
+                }
+            }
+        }
+        facets
+    }
+
+    pub fn facet(vertices: [usize; M], k: usize) -> [usize; N] {
+        // Facet opposite to the k-th vertex with the orientation induced by the simplex
+        let mut facet = [0; N];
+        for (i, &vertex) in vertices.iter().filter(|&&v| v != vertices[k]).enumerate() {
+            facet[i] = vertex;
+        }
+        if k % 2 == 1 && N > 1 {
+            facet.swap(0, 1);
+        }
+        facet
+    }
+
+    pub fn build_kdtree(vertices: &DashMap<usize, Vertex<N>>) -> KdTree<f64, N> {
+        // Items are vertex ids, added in id order so the tree does not depend on DashMap order
+        let mut kdtree = KdTree::new();
+        for id in 0..vertices.len() {
+            kdtree.add(&vertices.get(&id).unwrap().coordinates, id as u64);
+        }
+        kdtree
+    }
+
     pub fn locate(&self, vertex: [f64; N]) -> Vec<usize> {
         let mut output: Vec<usize> = vec![];
         let simplex_id = &self
@@ -38,17 +214,18 @@ impl<const N: usize, const M: usize> DelaunayTree<N, M> {
             .unwrap()
             .simplex;
         for id in simplex_id {
-            let _simplex = self.simplices.get(&id).unwrap();
+            let _simplex = self.simplices.get(id).unwrap();
             if in_sphere(vertex, _simplex.center, _simplex.radius) {
                 output.push(*id);
                 output = self.find_all_neighbors(&mut output, *id, vertex);
             }
         }
-        if output.len() == 0 {
-            println!("Simplex_id {:?}", simplex_id);
-            println!(
-                "{:?}",
-                self.kdtree.nearest_one::<SquaredEuclidean>(&vertex).item as usize
+        if output.is_empty() {
+            log::error!(
+                "No simplex around vertex {} contains {:?}, its simplices are {:?}",
+                self.kdtree.nearest_one::<SquaredEuclidean>(&vertex).item,
+                vertex,
+                simplex_id
             );
             panic!("No simplex found for vertex {:?}", vertex);
         }
@@ -65,8 +242,8 @@ impl<const N: usize, const M: usize> DelaunayTree<N, M> {
     ) -> Vec<usize> {
         let neighbors = &self.simplices.get(&node_id).unwrap().neighbors;
         for neighbor in neighbors {
-            let _simplex = self.simplices.get(&neighbor).unwrap();
-            if !output.contains(&neighbor) && in_sphere(vertex, _simplex.center, _simplex.radius) {
+            let _simplex = self.simplices.get(neighbor).unwrap();
+            if !output.contains(neighbor) && in_sphere(vertex, _simplex.center, _simplex.radius) {
                 output.push(*neighbor);
                 self.find_all_neighbors(output, *neighbor, vertex);
             }
@@ -79,12 +256,7 @@ impl<const N: usize, const M: usize> DelaunayTree<N, M> {
         killed_site_id: usize,
         vertex: [f64; N],
         vertex_id: usize,
-    ) -> (
-        Vec<[usize; M]>,
-        Vec<[f64; N]>,
-        Vec<f64>,
-        Vec<(usize, usize)>,
-    ) {
+    ) -> NewSimplices<N, M> {
         let mut simplices: Vec<[usize; M]> = vec![];
         let mut simplices_id: Vec<usize> = vec![];
         let mut centers: Vec<[f64; N]> = vec![];
@@ -107,10 +279,15 @@ impl<const N: usize, const M: usize> DelaunayTree<N, M> {
                     }
                 }
                 let mut new_simplex_vertex: [[f64; N]; M] = [[0.0; N]; M];
-                new_simplex_vertex[0] = vertex.clone();
+                new_simplex_vertex[0] = vertex;
                 for i in 1..M {
                     new_simplex_vertex[i] = self.vertices.get(&new_simplex[i]).unwrap().coordinates;
                 }
+                // Keep every simplex positively oriented
+                if signed_volume(new_simplex_vertex) < 0.0 {
+                    new_simplex.swap(1, 2);
+                    new_simplex_vertex.swap(1, 2);
+                }
                 let (center, radius) = circumsphere(new_simplex_vertex);
                 simplices.push(new_simplex);
                 simplices_id.push(self.max_simplex_id + simplices.len());
@@ -127,7 +304,7 @@ impl<const N: usize, const M: usize> DelaunayTree<N, M> {
         // This does not parallelize the insert so we don't have to pay for overhead.
         // Works well for small number of points
         let killed_sites = &update.killed_sites;
-        self.kdtree.add(&update.vertex, self.vertices.len() as u64);
+        self.kdtree.add(&update.vertex, update.id as u64);
 
         // Update simplices
         self.simplices
@@ -171,7 +348,7 @@ impl<const N: usize, const M: usize> DelaunayTree<N, M> {
         // Update vertices_simplex
 
         self.vertices.insert(
-            self.vertices.len(),
+            update.id,
             Vertex {
                 coordinates: update.vertex,
                 simplex: vec![],
@@ -223,13 +400,26 @@ impl<const N: usize, const M: usize> DelaunayTree<N, M> {
             *x
         });
         simplices_length.insert(0, 0);
-        let length = self.vertices.len();
-
-        updates.iter().enumerate().for_each(|(i, update)| {
-            self.kdtree
-                .add(&update.vertex, (self.vertices.len() + i) as u64);
+        updates.iter().for_each(|update| {
+            self.kdtree.add(&update.vertex, update.id as u64);
         });
 
+        let add_to_stars = |update_index: usize, update: &TreeUpdate<N, M>| {
+            update
+                .simplices
+                .iter()
+                .enumerate()
+                .for_each(|(i, simplex)| {
+                    for j in 0..M {
+                        self.vertices.get_mut(&(*simplex)[j]).unwrap().simplex.push(
+                            self.max_simplex_id
+                                + update.simplices_id[i]
+                                + simplices_length[update_index],
+                        );
+                    }
+                });
+        };
+
         updates
             .par_iter()
             .enumerate()
@@ -293,26 +483,18 @@ impl<const N: usize, const M: usize> DelaunayTree<N, M> {
                 // Update vertices_simplex
 
                 self.vertices.insert(
-                    length + update_index,
+                    update.id,
                     Vertex {
                         coordinates: update.vertex,
                         simplex: vec![],
                     },
                 );
 
-                update
-                    .simplices
-                    .iter()
-                    .enumerate()
-                    .for_each(|(i, simplex)| {
-                        for j in 0..M {
-                            self.vertices
-                                .get_mut(&(*simplex)[j])
-                                .unwrap()
-                                .simplex
-                                .push(self.max_simplex_id + update.simplices_id[i] + simplices_length[update_index]);
-                        }
-                    });
+                // Updates of one batch can share vertices on the border of their cavities,
+                // the order of the pushes then depends on thread timing
+                if !self.deterministic {
+                    add_to_stars(update_index, update);
+                }
 
                 killed_sites.iter().for_each(|killed_sites_id| {
                     for i in 0..M {
@@ -330,60 +512,168 @@ impl<const N: usize, const M: usize> DelaunayTree<N, M> {
                 });
             });
 
+        if self.deterministic {
+            updates
+                .iter()
+                .enumerate()
+                .for_each(|(update_index, update)| add_to_stars(update_index, update));
+        }
+
         self.max_simplex_id += simplices_length.last().unwrap();
     }
 
+    pub fn with_parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    fn report(&self, progress: Progress) {
+        if let Some(callback) = &self.progress {
+            callback(&progress);
+        }
+    }
+
     pub fn add_points_to_tree(&mut self, vertices: Vec<[f64; N]>) {
-        println!("Making queue and finding placement");
-        let start = std::time::Instant::now();
-        let queue = make_queue(vertices, self);
-        let placement = find_placement(&queue);
-        let mut batches = vec![];
-        batches.par_extend(
-            (1..placement.iter().max().unwrap() + 1)
-                .into_par_iter()
-                .map(|i| {
-                    queue
-                        .iter()
-                        .enumerate()
-                        .filter(|(id, _)| placement[*id] == i)
-                        .collect::<Vec<(usize, &(usize, [f64; N], Vec<usize>))>>()
-                }),
-        );
-        println!("Queue and placement finished in {:?}", start.elapsed());
-        #[cfg(debug_assertions)]
-        {
-            let time = std::time::Instant::now();
-            for batch in batches {
-                let n_points = self.vertices.len();
-                println!("Valid batch {:?}", batch.len());
-                let updates = batch
-                    .par_iter()
-                    .enumerate()
-                    // .with_min_len(8)
-                    .map(|(id, vertex)| TreeUpdate::new(n_points + id, vertex.1 .1, self))
-                    .collect::<Vec<TreeUpdate<N, M>>>();
-                self.insert_points_parallel(&updates);
+        let total_points = vertices.len();
+        let first_id = self.vertices.len();
+        self.add_points(vertices, first_id, 0, total_points, Instant::now());
+    }
+
+    pub(crate) fn add_points(
+        &mut self,
+        vertices: Vec<[f64; N]>,
+        first_id: usize,
+        points_inserted: usize,
+        total_points: usize,
+        start: Instant,
+    ) {
+        match self.parallelism.clone() {
+            Parallelism::Global => {
+                self.add_points_in_batches(vertices, first_id, points_inserted, total_points, start)
+            }
+            Parallelism::Pool(pool) => pool.install(|| {
+                self.add_points_in_batches(vertices, first_id, points_inserted, total_points, start)
+            }),
+            Parallelism::Serial => {
+                self.add_points_serially(&vertices, first_id, points_inserted, total_points, start)
             }
-            println!("Insertion finished in {:?}", time.elapsed());
         }
-        #[cfg(not(debug_assertions))]
-        {
-            for batch in batches {
-                let n_points = self.vertices.len();
-                let updates = batch
-                    .par_iter()
-                    .enumerate()
-                    // .with_min_len(16)
-                    .map(|(id, vertex)| TreeUpdate::new(n_points + id, vertex.1 .1, self))
-                    .collect::<Vec<TreeUpdate<N, M>>>();
-                // for update in updates {
-                //     self.insert_point(&update);
-                // }
-                self.insert_points_parallel(&updates);
+    }
+
+    fn add_points_serially(
+        &mut self,
+        vertices: &[[f64; N]],
+        first_id: usize,
+        points_inserted: usize,
+        total_points: usize,
+        start: Instant,
+    ) {
+        for (i, &point) in vertices.iter().enumerate() {
+            let update = TreeUpdate::new(first_id + i, point, self);
+            self.insert_point(&update);
+            if (i + 1) % PROGRESS_INTERVAL == 0 || i + 1 == vertices.len() {
+                self.report(Progress {
+                    points_inserted: points_inserted + i + 1,
+                    total_points,
+                    batch: 0,
+                    n_batches: 0,
+                    elapsed: start.elapsed(),
+                });
             }
         }
     }
+
+    fn add_points_in_batches(
+        &mut self,
+        vertices: Vec<[f64; N]>,
+        first_id: usize,
+        mut points_inserted: usize,
+        total_points: usize,
+        start: Instant,
+    ) {
+        log::debug!("Making queue and scheduling batches");
+        let queue = make_queue(vertices, self);
+        let batches = schedule_batches(&queue);
+        log::debug!(
+            "Scheduled {} points in {} batches in {:?}",
+            queue.len(),
+            batches.len(),
+            start.elapsed()
+        );
+        // Vertex ids follow the order of the input rather than the order of insertion
+        let n_batches = batches.len();
+        for (batch_index, batch) in batches.iter().enumerate() {
+            log::trace!(
+                "Inserting batch {} of {} points",
+                batch_index + 1,
+                batch.len()
+            );
+            let updates = batch
+                .par_iter()
+                .map(|i| TreeUpdate::new(first_id + queue[*i].0, queue[*i].1, self))
+                .collect::<Vec<TreeUpdate<N, M>>>();
+            self.insert_points_parallel(&updates);
+            points_inserted += batch.len();
+            self.report(Progress {
+                points_inserted,
+                total_points,
+                batch: batch_index + 1,
+                n_batches,
+                elapsed: start.elapsed(),
+            });
+        }
+        log::debug!("Parallel insertion finished in {:?}", start.elapsed());
+    }
+
+    pub fn insert_points(&mut self, points: &[[f64; N]]) {
+        // Insert sequentially while the tree is small, then switch to parallel insertion
+        let start = Instant::now();
+        let n_sequential = points.len().min(SEQUENTIAL_POINTS);
+        let first_id = self.vertices.len();
+        self.add_points_serially(&points[..n_sequential], first_id, 0, points.len(), start);
+        if n_sequential < points.len() {
+            log::info!(
+                "More than {} points, switching to parallel insertion",
+                SEQUENTIAL_POINTS
+            );
+            self.add_points(
+                points[n_sequential..].to_vec(),
+                first_id + n_sequential,
+                n_sequential,
+                points.len(),
+                start,
+            );
+        } else {
+            log::info!(
+                "Inserted {} points sequentially in {:?}",
+                points.len(),
+                start.elapsed()
+            );
+        }
+    }
+
+    fn with_settings_of(mut self, other: &Self) -> Self {
+        self.parallelism = other.parallelism.clone();
+        self.query_method = other.query_method;
+        self.deterministic = other.deterministic;
+        self
+    }
+
+    pub(crate) fn replace_triangulation(&mut self, tree: Self) {
+        // Take the vertices and simplices of tree, keeping the settings of self
+        self.kdtree = tree.kdtree;
+        self.vertices = tree.vertices;
+        self.simplices = tree.simplices;
+        self.max_simplex_id = tree.max_simplex_id;
+    }
 }
 
 impl DelaunayTree<3, 4> {
@@ -423,7 +713,7 @@ impl DelaunayTree<3, 4> {
 
         let vertex = DashMap::new();
 
-        let vertices = vec![
+        let vertices = [
             first_vertex,
             second_vertex,
             third_vertex,
@@ -458,7 +748,8 @@ impl DelaunayTree<3, 4> {
         simplices.insert(
             0,
             Simplex {
-                vertices: [0, 1, 2, 3],
+                // Positively oriented
+                vertices: [0, 2, 1, 3],
                 center,
                 radius,
                 neighbors: vec![1, 2, 3, 4],
@@ -500,44 +791,24 @@ impl DelaunayTree<3, 4> {
                 neighbors: vec![0],
             },
         );
-        let delaunay_tree = DelaunayTree {
+
+        DelaunayTree {
             kdtree,
             vertices: vertex,
             simplices,
             max_simplex_id: 4,
-        };
-        delaunay_tree
-    }
-
-    pub fn check_delaunay(&self) -> bool {
-        let mut result = true;
-        for simplex in self.simplices.iter() {
-            for vertex in self.vertices.iter() {
-                let local_simplex = self.simplices.get(simplex.key()).unwrap();
-                if in_sphere(
-                    vertex.coordinates,
-                    local_simplex.center,
-                    local_simplex.radius,
-                ) && !local_simplex.vertices.contains(&vertex.key())
-                    && local_simplex.vertices.iter().all(|&x| x > 7)
-                // TODO fix this
-                {
-                    result = false;
-                    println!(
-                        "Vertex {:?} is in sphere of simplex {:?}",
-                        vertex.key(),
-                        simplex.key()
-                    );
-                    println!("Vertices coordinates {:?}", vertex.coordinates);
-                    for i in 0..4 {
-                        println!("Simplex vertices {:?}", local_simplex.vertices[i]);
-                    }
-                    println!("Center of simplex {:?}", local_simplex.center);
-                    println!("Radius of simplex {:?}", local_simplex.radius);
-                }
-            }
+            deterministic: false,
+            parallelism: Parallelism::Global,
+            progress: None,
+            query_method: QueryMethod::KdTree,
         }
-        result
+    }
+
+    pub(crate) fn retriangulate(&self, points: &[[f64; 3]]) -> Self {
+        // The same input, with the same ids and settings, triangulated from scratch
+        let mut tree = DelaunayTree::<3, 4>::new(points.to_vec()).with_settings_of(self);
+        tree.insert_points(points);
+        tree
     }
 }
 
@@ -560,16 +831,16 @@ impl DelaunayTree<2, 3> {
             first_vertex,
             second_vertex,
             third_vertex,
-            first_vertex.clone(),
-            second_vertex.clone(),
-            third_vertex.clone(),
+            first_vertex,
+            second_vertex,
+            third_vertex,
         ];
 
-        println!("{:?}", vertices);
+        log::trace!("Super-simplex vertices {:?}", vertices);
         let mut kdtree = KdTree::new();
 
-        for i in 0..6 {
-            kdtree.add(&vertices[i], i as u64);
+        for (i, vertex) in vertices.iter().enumerate() {
+            kdtree.add(vertex, i as u64);
         }
 
         let vertices_simplex = [
@@ -630,50 +901,30 @@ impl DelaunayTree<2, 3> {
                 neighbors: vec![0],
             },
         );
-        let delaunay_tree = DelaunayTree::<2, 3> {
+
+        DelaunayTree::<2, 3> {
             kdtree,
             vertices: vertex,
             simplices,
             max_simplex_id: 3,
-        };
-        delaunay_tree
-    }
-
-    pub fn check_delaunay(&self) -> bool {
-        let mut result = true;
-        for simplex in self.simplices.iter() {
-            for vertex in self.vertices.iter() {
-                let local_simplex = self.simplices.get(simplex.key()).unwrap();
-                if in_sphere(
-                    vertex.coordinates,
-                    local_simplex.center,
-                    local_simplex.radius,
-                ) && !local_simplex.vertices.contains(&vertex.key())
-                    && local_simplex.vertices.iter().all(|&x| x > 5)
-                // TODO fix this
-                {
-                    result = false;
-                    println!(
-                        "Vertex {:?} is in sphere of simplex {:?}",
-                        vertex.key(),
-                        simplex.key()
-                    );
-                    println!("Vertices coordinates {:?}", vertex.coordinates);
-                    for i in 0..4 {
-                        println!("Simplex vertices {:?}", local_simplex.vertices[i]);
-                    }
-                    println!("Center of simplex {:?}", local_simplex.center);
-                    println!("Radius of simplex {:?}", local_simplex.radius);
-                }
-            }
+            deterministic: false,
+            parallelism: Parallelism::Global,
+            progress: None,
+            query_method: QueryMethod::KdTree,
         }
-        result
+    }
+
+    pub(crate) fn retriangulate(&self, points: &[[f64; 2]]) -> Self {
+        // The same input, with the same ids and settings, triangulated from scratch
+        let mut tree = DelaunayTree::<2, 3>::new(points.to_vec()).with_settings_of(self);
+        tree.insert_points(points);
+        tree
     }
 }
 
 fn pair_simplices<const N: usize, const M: usize>(
-    simplices: &Vec<[usize; M]>,
-    simplices_id: &Vec<usize>,
+    simplices: &[[usize; M]],
+    simplices_id: &[usize],
 ) -> Vec<(usize, usize)> {
     let mut new_neighbors: Vec<(usize, usize)> = vec![];
     let n_simplices = simplices.len();
@@ -696,12 +947,14 @@ fn pair_simplices<const N: usize, const M: usize>(
 
 #[derive(Debug, Clone)]
 pub struct TreeUpdate<const N: usize, const M: usize> {
+    id: usize,
     vertex: [f64; N],
-    killed_sites: Vec<usize>,
-    simplices: Vec<[usize; M]>,
+    pub(crate) killed_sites: Vec<usize>,
+    // Vertices, circumcenters and circumradii of the simplices around the new point
+    pub(crate) simplices: Vec<[usize; M]>,
     simplices_id: Vec<usize>,
-    centers: Vec<[f64; N]>,
-    radii: Vec<f64>,
+    pub(crate) centers: Vec<[f64; N]>,
+    pub(crate) radii: Vec<f64>,
     neighbors: Vec<(usize, usize)>,
     new_neighbors: Vec<(usize, usize)>,
 }
@@ -714,9 +967,9 @@ impl<const N: usize, const M: usize> TreeUpdate<N, M> {
         let mut radii: Vec<f64> = vec![];
         let mut neighbors: Vec<(usize, usize)> = vec![];
 
-        for i in 0..killed_sites.len() {
+        for &killed_site in killed_sites.iter() {
             let (simplices_, centers_, radii_, neighbors_) =
-                tree.get_new_simplices(killed_sites[i], vertex, id);
+                tree.get_new_simplices(killed_site, vertex, id);
             simplices.extend(simplices_);
             centers.extend(centers_);
             radii.extend(radii_);
@@ -727,6 +980,7 @@ impl<const N: usize, const M: usize> TreeUpdate<N, M> {
         let new_neighbors: Vec<(usize, usize)> = pair_simplices::<N, M>(&simplices, &simplices_id);
 
         TreeUpdate {
+            id,
             vertex,
             killed_sites,
             simplices,
@@ -0,0 +1,100 @@
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+use crate::geometry::simplex_volume;
+use crate::interpolation::{Grid, InterpolationMethod, Interpolator};
+
+// Delaunay Tessellation Field Estimator. The density at an input point is (N + 1) times
+// its mass over the total volume of the real simplices around it, and the density field
+// is the linear interpolation of these values in every simplex. The field integrates to
+// the total mass over the convex hull and vanishes outside of it.
+
+pub struct Dtfe<'a, const N: usize, const M: usize> {
+    tree: &'a DelaunayTree<N, M>,
+    // One density per input point, in input order
+    densities: Vec<f64>,
+    interpolator: Interpolator<'a, N, M, 1>,
+}
+
+impl<'a, const N: usize, const M: usize> Dtfe<'a, N, M> {
+    pub fn new(tree: &'a DelaunayTree<N, M>, masses: Option<&[f64]>) -> Self {
+        // Unit masses if none are given
+        let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+        let n_points = tree.vertices.len() - first;
+        if let Some(masses) = masses {
+            assert_eq!(
+                masses.len(),
+                n_points,
+                "DTFE needs one mass per input point"
+            );
+        }
+        let densities: Vec<f64> = (0..n_points)
+            .into_par_iter()
+            .map(|index| {
+                let mass = masses.map_or(1.0, |masses| masses[index]);
+                let volume = tree.star_volume(index + first);
+                if volume > 0.0 {
+                    M as f64 * mass / volume
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let interpolator =
+            Interpolator::from_scalars(tree, &densities).with_method(InterpolationMethod::Linear);
+        Dtfe {
+            tree,
+            densities,
+            interpolator,
+        }
+    }
+
+    pub fn densities(&self) -> &[f64] {
+        &self.densities
+    }
+
+    pub fn density(&self, point: [f64; N]) -> f64 {
+        self.interpolator
+            .interpolate(point)
+            .map_or(0.0, |value| value[0])
+    }
+
+    pub fn densities_at(&self, points: &[[f64; N]]) -> Vec<f64> {
+        points
+            .par_iter()
+            .map(|point| self.density(*point))
+            .collect()
+    }
+
+    pub fn sample_grid(&self, grid: &Grid<N>) -> Vec<f64> {
+        // Densities at the grid points in the order of Grid::point
+        (0..grid.len())
+            .into_par_iter()
+            .map(|index| self.density(grid.point(index)))
+            .collect()
+    }
+
+    pub fn simplex_gradients(&self) -> Vec<(usize, [f64; N])> {
+        // Constant density gradient in every real simplex, sorted by simplex id
+        self.tree
+            .real_simplices()
+            .into_par_iter()
+            .map(|id| (id, self.interpolator.simplex_gradient(id)[0]))
+            .collect()
+    }
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    pub fn star_volume(&self, vertex_id: usize) -> f64 {
+        // Total volume of the real simplices containing the vertex
+        self.vertices
+            .get(&vertex_id)
+            .unwrap()
+            .simplex
+            .iter()
+            .map(|id| self.simplices.get(id).unwrap())
+            .filter(|simplex| Self::is_real_simplex(simplex))
+            .map(|simplex| simplex_volume(self.simplex_coordinates(&simplex)))
+            .sum()
+    }
+}
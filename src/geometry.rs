@@ -1,24 +1,19 @@
-use nalgebra::Matrix3;
+use nalgebra::{DMatrix, Matrix3};
 fn circumsphere_2d(vertices: [[f64; 2]; 3]) -> ([f64; 2], f64) {
+    // Solved relative to the first vertex, which also handles edges parallel to an axis
     let [x1, y1] = vertices[0];
-    let [x2, y2] = vertices[1];
-    let [x3, y3] = vertices[2];
+    let (bx, by) = (vertices[1][0] - x1, vertices[1][1] - y1);
+    let (cx, cy) = (vertices[2][0] - x1, vertices[2][1] - y1);
 
-    let d = [(x1 + x2) / 2.0, (y1 + y2) / 2.0];
-    let e = [(x2 + x3) / 2.0, (y2 + y3) / 2.0];
+    let d = 2.0 * (bx * cy - by * cx);
+    let b2 = bx * bx + by * by;
+    let c2 = cx * cx + cy * cy;
+    let ux = (cy * b2 - by * c2) / d;
+    let uy = (bx * c2 - cx * b2) / d;
 
-    let m_ab = (y2 - y1) / (x2 - x1);
-    let m_bc = (y3 - y2) / (x3 - x2);
+    let r = (ux * ux + uy * uy).sqrt();
 
-    let m_d = -1. / m_ab;
-    let m_e = -1. / m_bc;
-
-    let x = (m_d * d[0] - m_e * e[0] + e[1] - d[1]) / (m_d - m_e);
-    let y = m_d * (x - d[0]) + d[1];
-
-    let r = ((x - x1) * (x - x1) + (y - y1) * (y - y1)).sqrt();
-
-    return ([x, y], r);
+    ([x1 + ux, y1 + uy], r)
 }
 
 fn circumsphere_3d(vertices: [[f64; 3]; 4]) -> ([f64; 3], f64) {
@@ -86,7 +81,70 @@ pub fn circumsphere<const N: usize, const M: usize>(vertices: [[f64; N]; M]) ->
     (center, radius)
 }
 
+pub fn face_circumcenter<const N: usize>(points: &[[f64; N]]) -> [f64; N] {
+    // Center of the smallest sphere through a vertex, an edge or a triangle
+    let mut center = points[0];
+    match points.len() {
+        1 => {}
+        2 => {
+            for k in 0..N {
+                center[k] = (points[0][k] + points[1][k]) / 2.0;
+            }
+        }
+        3 => {
+            // Intersection of the triangle plane with the bisectors of its edges
+            let mut u = [0.0; 3];
+            let mut w = [0.0; 3];
+            for k in 0..N {
+                u[k] = points[1][k] - points[0][k];
+                w[k] = points[2][k] - points[0][k];
+            }
+            let cross = |a: [f64; 3], b: [f64; 3]| {
+                [
+                    a[1] * b[2] - a[2] * b[1],
+                    a[2] * b[0] - a[0] * b[2],
+                    a[0] * b[1] - a[1] * b[0],
+                ]
+            };
+            let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+            let normal = cross(u, w);
+            let mut combination = [0.0; 3];
+            for k in 0..3 {
+                combination[k] = dot(u, u) * w[k] - dot(w, w) * u[k];
+            }
+            let offset = cross(combination, normal);
+            let scale = 2.0 * dot(normal, normal);
+            for k in 0..N {
+                center[k] += offset[k] / scale;
+            }
+        }
+        _ => panic!("No face circumcenter for {} points", points.len()),
+    }
+    center
+}
+
+pub fn signed_volume<const N: usize, const M: usize>(vertices: [[f64; N]; M]) -> f64 {
+    // Determinant of the edge vectors from the first vertex, divided by N!
+    let edge = |i: usize, j: usize| vertices[i + 1][j] - vertices[0][j];
+    match N {
+        2 => (edge(0, 0) * edge(1, 1) - edge(0, 1) * edge(1, 0)) / 2.0,
+        3 => {
+            (edge(0, 0) * (edge(1, 1) * edge(2, 2) - edge(1, 2) * edge(2, 1))
+                - edge(0, 1) * (edge(1, 0) * edge(2, 2) - edge(1, 2) * edge(2, 0))
+                + edge(0, 2) * (edge(1, 0) * edge(2, 1) - edge(1, 1) * edge(2, 0)))
+                / 6.0
+        }
+        _ => {
+            let edges = DMatrix::from_fn(N, N, edge);
+            let factorial = (1..=N).product::<usize>() as f64;
+            edges.determinant() / factorial
+        }
+    }
+}
 
+pub fn simplex_volume<const N: usize, const M: usize>(vertices: [[f64; N]; M]) -> f64 {
+    signed_volume(vertices).abs()
+}
 
 pub fn in_sphere<const N: usize>(vertex: [f64; N], center: [f64; N], radius: f64) -> bool {
     let mut distance: f64 = 0.0;
@@ -0,0 +1,214 @@
+use std::io::{self, Write};
+
+use crate::delaunay_tree::DelaunayTree;
+
+// Gmsh MSH 4.1 writer. The mesh has one entity of dimension N holding the simplices
+// (physical group 2, "interior") and one of dimension N - 1 holding the convex hull
+// facets (physical group 1, "hull boundary"). Node tags are the input point indices
+// plus one.
+
+pub const HULL_BOUNDARY: i32 = 1;
+pub const INTERIOR: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MshFormat {
+    Ascii,
+    Binary,
+}
+
+struct MshWriter<W: Write> {
+    writer: W,
+    binary: bool,
+    // Pending ASCII line
+    line: Vec<String>,
+}
+
+impl<W: Write> MshWriter<W> {
+    fn int(&mut self, value: i32) -> io::Result<()> {
+        if self.binary {
+            self.writer.write_all(&value.to_le_bytes())
+        } else {
+            self.line.push(value.to_string());
+            Ok(())
+        }
+    }
+
+    fn size(&mut self, value: usize) -> io::Result<()> {
+        if self.binary {
+            self.writer.write_all(&(value as u64).to_le_bytes())
+        } else {
+            self.line.push(value.to_string());
+            Ok(())
+        }
+    }
+
+    fn double(&mut self, value: f64) -> io::Result<()> {
+        if self.binary {
+            self.writer.write_all(&value.to_le_bytes())
+        } else {
+            self.line.push(format!("{:e}", value));
+            Ok(())
+        }
+    }
+
+    fn end_line(&mut self) -> io::Result<()> {
+        if !self.binary {
+            writeln!(self.writer, "{}", self.line.join(" "))?;
+            self.line.clear();
+        }
+        Ok(())
+    }
+
+    fn section(&mut self, name: &str) -> io::Result<()> {
+        writeln!(self.writer, "${}", name)
+    }
+
+    fn end_section(&mut self, name: &str) -> io::Result<()> {
+        if self.binary {
+            writeln!(self.writer)?;
+        }
+        writeln!(self.writer, "$End{}", name)
+    }
+}
+
+pub fn write_msh<const N: usize, const M: usize, W: Write>(
+    tree: &DelaunayTree<N, M>,
+    writer: W,
+    format: MshFormat,
+) -> io::Result<()> {
+    let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+    let n_nodes = tree.vertices.len() - first;
+    let simplices: Vec<[usize; M]> = tree
+        .real_simplices()
+        .into_iter()
+        .map(|id| tree.oriented_vertices(id))
+        .collect();
+    let facets = tree.hull_facets();
+    // Gmsh element types: 1 line, 2 triangle, 4 tetrahedron
+    let (facet_type, simplex_type) = if N == 2 { (1, 2) } else { (2, 4) };
+
+    let mut lower = [f64::INFINITY; 3];
+    let mut upper = [f64::NEG_INFINITY; 3];
+    let mut coordinates = Vec::with_capacity(n_nodes);
+    for id in first..tree.vertices.len() {
+        let mut point = [0.0; 3];
+        point[..N].copy_from_slice(&tree.vertices.get(&id).unwrap().coordinates);
+        for i in 0..3 {
+            lower[i] = lower[i].min(point[i]);
+            upper[i] = upper[i].max(point[i]);
+        }
+        coordinates.push(point);
+    }
+    if n_nodes == 0 {
+        lower = [0.0; 3];
+        upper = [0.0; 3];
+    }
+
+    let binary = format == MshFormat::Binary;
+    let mut msh = MshWriter {
+        writer,
+        binary,
+        line: vec![],
+    };
+
+    msh.section("MeshFormat")?;
+    if binary {
+        writeln!(msh.writer, "4.1 1 8")?;
+        msh.writer.write_all(&1i32.to_le_bytes())?;
+        msh.end_section("MeshFormat")?;
+    } else {
+        writeln!(msh.writer, "4.1 0 8")?;
+        msh.end_section("MeshFormat")?;
+    }
+
+    // Physical names are always ASCII
+    writeln!(msh.writer, "$PhysicalNames")?;
+    writeln!(msh.writer, "2")?;
+    writeln!(msh.writer, "{} {} \"hull boundary\"", N - 1, HULL_BOUNDARY)?;
+    writeln!(msh.writer, "{} {} \"interior\"", N, INTERIOR)?;
+    writeln!(msh.writer, "$EndPhysicalNames")?;
+
+    msh.section("Entities")?;
+    let mut counts = [0; 4];
+    counts[N - 1] = 1;
+    counts[N] = 1;
+    for count in counts {
+        msh.size(count)?;
+    }
+    msh.end_line()?;
+    for (dimension, physical) in [(N - 1, HULL_BOUNDARY), (N, INTERIOR)] {
+        msh.int(1)?;
+        for value in lower.iter().chain(upper.iter()) {
+            msh.double(*value)?;
+        }
+        msh.size(1)?;
+        msh.int(physical)?;
+        if dimension == N {
+            // The interior is bounded by the hull entity
+            msh.size(1)?;
+            msh.int(1)?;
+        } else {
+            msh.size(0)?;
+        }
+        msh.end_line()?;
+    }
+    msh.end_section("Entities")?;
+
+    msh.section("Nodes")?;
+    for value in [1, n_nodes, n_nodes.min(1), n_nodes] {
+        msh.size(value)?;
+    }
+    msh.end_line()?;
+    msh.int(N as i32)?;
+    msh.int(1)?;
+    msh.int(0)?;
+    msh.size(n_nodes)?;
+    msh.end_line()?;
+    for tag in 1..=n_nodes {
+        msh.size(tag)?;
+        msh.end_line()?;
+    }
+    for point in &coordinates {
+        for value in point {
+            msh.double(*value)?;
+        }
+        msh.end_line()?;
+    }
+    msh.end_section("Nodes")?;
+
+    msh.section("Elements")?;
+    let n_elements = facets.len() + simplices.len();
+    for value in [2, n_elements, n_elements.min(1), n_elements] {
+        msh.size(value)?;
+    }
+    msh.end_line()?;
+    let mut tag = 0;
+    msh.int(N as i32 - 1)?;
+    msh.int(1)?;
+    msh.int(facet_type)?;
+    msh.size(facets.len())?;
+    msh.end_line()?;
+    for facet in &facets {
+        tag += 1;
+        msh.size(tag)?;
+        for vertex in facet {
+            msh.size(vertex - first + 1)?;
+        }
+        msh.end_line()?;
+    }
+    msh.int(N as i32)?;
+    msh.int(1)?;
+    msh.int(simplex_type)?;
+    msh.size(simplices.len())?;
+    msh.end_line()?;
+    for simplex in &simplices {
+        tag += 1;
+        msh.size(tag)?;
+        for vertex in simplex {
+            msh.size(vertex - first + 1)?;
+        }
+        msh.end_line()?;
+    }
+    msh.end_section("Elements")?;
+    msh.writer.flush()
+}
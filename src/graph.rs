@@ -0,0 +1,218 @@
+use kiddo::SquaredEuclidean;
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+
+// Proximity graphs between the input points, given by their input index. The Delaunay
+// graph holds the edges of the real simplices, and the Gabriel graph, the relative
+// neighborhood graph and the Euclidean minimum spanning tree are subgraphs of it, each
+// one contained in the previous.
+
+// Undirected graph in compressed sparse row form: the neighbors of vertex i are
+// neighbors[offsets[i]..offsets[i + 1]], sorted, with the edge lengths in weights
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Graph {
+    pub offsets: Vec<usize>,
+    pub neighbors: Vec<usize>,
+    pub weights: Vec<f64>,
+}
+
+impl Graph {
+    pub fn from_edges(n_vertices: usize, edges: &[(usize, usize, f64)]) -> Self {
+        // Every edge is stored in the rows of both of its ends
+        let mut degrees = vec![0; n_vertices];
+        for (a, b, _) in edges {
+            degrees[*a] += 1;
+            degrees[*b] += 1;
+        }
+        let mut offsets = vec![0; n_vertices + 1];
+        for i in 0..n_vertices {
+            offsets[i + 1] = offsets[i] + degrees[i];
+        }
+        let mut rows: Vec<Vec<(usize, f64)>> =
+            degrees.iter().map(|d| Vec::with_capacity(*d)).collect();
+        for (a, b, weight) in edges {
+            rows[*a].push((*b, *weight));
+            rows[*b].push((*a, *weight));
+        }
+        let mut neighbors = Vec::with_capacity(offsets[n_vertices]);
+        let mut weights = Vec::with_capacity(offsets[n_vertices]);
+        for mut row in rows {
+            row.sort_unstable_by_key(|(neighbor, _)| *neighbor);
+            for (neighbor, weight) in row {
+                neighbors.push(neighbor);
+                weights.push(weight);
+            }
+        }
+        Graph {
+            offsets,
+            neighbors,
+            weights,
+        }
+    }
+
+    pub fn n_vertices(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    pub fn n_edges(&self) -> usize {
+        self.neighbors.len() / 2
+    }
+
+    pub fn neighbors_of(&self, vertex: usize) -> &[usize] {
+        &self.neighbors[self.offsets[vertex]..self.offsets[vertex + 1]]
+    }
+
+    pub fn edges(&self) -> Vec<(usize, usize, f64)> {
+        // Each edge once, with the smaller index first, sorted
+        let mut edges = vec![];
+        for a in 0..self.n_vertices() {
+            for k in self.offsets[a]..self.offsets[a + 1] {
+                if a < self.neighbors[k] {
+                    edges.push((a, self.neighbors[k], self.weights[k]));
+                }
+            }
+        }
+        edges
+    }
+}
+
+fn distance<const N: usize>(a: &[f64; N], b: &[f64; N]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f64>()
+        .sqrt()
+}
+
+// Union-find with path halving and union by size
+struct DisjointSets {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        DisjointSets {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        true
+    }
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    fn n_input_points(&self) -> usize {
+        self.vertices.len() - Self::N_SUPER_VERTICES
+    }
+
+    fn input_coordinates(&self, index: usize) -> [f64; N] {
+        self.vertices
+            .get(&(index + Self::N_SUPER_VERTICES))
+            .unwrap()
+            .coordinates
+    }
+
+    fn delaunay_edges(&self) -> Vec<(usize, usize, f64)> {
+        // Edges of the real simplices as input indices, smaller first, sorted
+        self.unique_edges(true)
+            .map(|(a, b)| {
+                let (a, b) = (a - Self::N_SUPER_VERTICES, b - Self::N_SUPER_VERTICES);
+                let length = distance(&self.input_coordinates(a), &self.input_coordinates(b));
+                (a, b, length)
+            })
+            .collect()
+    }
+
+    fn empty_region(&self, a: usize, b: usize, length: f64, gabriel: bool) -> bool {
+        // Whether no other input point lies strictly inside the diametral ball of the
+        // edge (Gabriel) or inside the lune of the two balls of radius length (RNG)
+        let (p, q) = (self.input_coordinates(a), self.input_coordinates(b));
+        let (center, radius) = if gabriel {
+            let mut midpoint = [0.0; N];
+            for k in 0..N {
+                midpoint[k] = (p[k] + q[k]) / 2.0;
+            }
+            (midpoint, length / 2.0)
+        } else {
+            (p, length)
+        };
+        self.kdtree
+            .within_unsorted::<SquaredEuclidean>(&center, radius * radius)
+            .into_iter()
+            .map(|neighbour| neighbour.item as usize)
+            .filter(|&id| !Self::is_super_vertex(id))
+            .map(|id| id - Self::N_SUPER_VERTICES)
+            .filter(|&other| other != a && other != b)
+            .all(|other| {
+                let coordinates = self.input_coordinates(other);
+                if gabriel {
+                    distance(&coordinates, &center) >= radius
+                } else {
+                    distance(&coordinates, &p).max(distance(&coordinates, &q)) >= length
+                }
+            })
+    }
+
+    pub fn delaunay_graph(&self) -> Graph {
+        Graph::from_edges(self.n_input_points(), &self.delaunay_edges())
+    }
+
+    pub fn gabriel_graph(&self) -> Graph {
+        let edges: Vec<(usize, usize, f64)> = self
+            .delaunay_edges()
+            .into_par_iter()
+            .filter(|(a, b, length)| self.empty_region(*a, *b, *length, true))
+            .collect();
+        Graph::from_edges(self.n_input_points(), &edges)
+    }
+
+    pub fn relative_neighborhood_graph(&self) -> Graph {
+        // The lune contains the diametral ball, so only Gabriel edges are candidates
+        let edges: Vec<(usize, usize, f64)> = self
+            .delaunay_edges()
+            .into_par_iter()
+            .filter(|(a, b, length)| {
+                self.empty_region(*a, *b, *length, true)
+                    && self.empty_region(*a, *b, *length, false)
+            })
+            .collect();
+        Graph::from_edges(self.n_input_points(), &edges)
+    }
+
+    pub fn euclidean_minimum_spanning_tree(&self) -> Graph {
+        // Kruskal on the Delaunay edges. A forest if the triangulation is not connected,
+        // e.g. with fewer than N + 1 input points.
+        let mut edges = self.delaunay_edges();
+        edges.par_sort_by(|x, y| {
+            x.2.total_cmp(&y.2)
+                .then_with(|| (x.0, x.1).cmp(&(y.0, y.1)))
+        });
+        let mut sets = DisjointSets::new(self.n_input_points());
+        let tree: Vec<(usize, usize, f64)> = edges
+            .into_iter()
+            .filter(|(a, b, _)| sets.union(*a, *b))
+            .collect();
+        Graph::from_edges(self.n_input_points(), &tree)
+    }
+}
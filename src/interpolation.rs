@@ -0,0 +1,434 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::OnceLock;
+
+use nalgebra::DMatrix;
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+use crate::geometry::{circumsphere, face_circumcenter, in_sphere, signed_volume};
+use crate::query::Location;
+
+// Interpolation of fields given at the input points. Values have K components (K = 1 for
+// scalar fields) and gradients are K rows of N partial derivatives. Linear interpolation
+// is only defined inside the convex hull of the input points and returns None outside.
+// Natural neighbor interpolation also returns None close to the hull, where the Voronoi
+// cell of the query point reaches that of a super-simplex vertex, see
+// natural_neighbor_coordinates. Nearest-neighbor interpolation is defined everywhere.
+
+// Stolen volumes down to -STOLEN_VOLUME_TOLERANCE times their sum are rounding errors
+const STOLEN_VOLUME_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InterpolationMethod {
+    // Barycentric interpolation in the containing simplex
+    #[default]
+    Linear,
+    // Sibson coordinates, i.e. the volumes the Voronoi cell of the query point steals
+    // from the cells of its natural neighbors
+    NaturalNeighbor,
+    // Value of the closest input point
+    Nearest,
+}
+
+// Regular grid of points from lower to upper (both included) with shape[k] points along
+// axis k, flattened in row-major order so the last axis varies fastest
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid<const N: usize> {
+    pub lower: [f64; N],
+    pub upper: [f64; N],
+    pub shape: [usize; N],
+}
+
+impl<const N: usize> Grid<N> {
+    pub fn new(lower: [f64; N], upper: [f64; N], shape: [usize; N]) -> Self {
+        Grid {
+            lower,
+            upper,
+            shape,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn spacing(&self) -> [f64; N] {
+        // Zero along axes with a single point
+        let mut spacing = [0.0; N];
+        for (k, step) in spacing.iter_mut().enumerate() {
+            if self.shape[k] > 1 {
+                *step = (self.upper[k] - self.lower[k]) / (self.shape[k] - 1) as f64;
+            }
+        }
+        spacing
+    }
+
+    pub fn point(&self, index: usize) -> [f64; N] {
+        let spacing = self.spacing();
+        let mut point = [0.0; N];
+        let mut rest = index;
+        for k in (0..N).rev() {
+            point[k] = self.lower[k] + (rest % self.shape[k]) as f64 * spacing[k];
+            rest /= self.shape[k];
+        }
+        point
+    }
+
+    pub fn points(&self) -> Vec<[f64; N]> {
+        (0..self.len()).map(|index| self.point(index)).collect()
+    }
+}
+
+fn permutations(items: &[usize]) -> Vec<Vec<usize>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+    let mut output = vec![];
+    for (i, &first) in items.iter().enumerate() {
+        let mut rest = items.to_vec();
+        rest.remove(i);
+        for mut tail in permutations(&rest) {
+            tail.insert(0, first);
+            output.push(tail);
+        }
+    }
+    output
+}
+
+fn dual_share<const N: usize, const M: usize>(
+    coordinates: &[[f64; N]; M],
+    center: [f64; N],
+    k: usize,
+) -> f64 {
+    // Part of the Voronoi cell of vertex k that is dual to the simplex: the signed sum,
+    // over the chains vertex < edge < ... < simplex through vertex k, of the simplices
+    // spanned by the circumcenters of the chain. The signs make it exact for simplices
+    // whose circumcenter lies outside, and the shares of a star add up to the cell.
+    let others: Vec<usize> = (0..M).filter(|&i| i != k).collect();
+    let mut share = 0.0;
+    for order in permutations(&others) {
+        let mut ordered = [coordinates[k]; M];
+        for (j, &i) in order.iter().enumerate() {
+            ordered[j + 1] = coordinates[i];
+        }
+        let mut piece = [center; M];
+        for j in 0..N {
+            piece[j] = face_circumcenter(&ordered[..=j]);
+        }
+        share += signed_volume(ordered).signum() * signed_volume(piece);
+    }
+    share
+}
+
+fn solve(matrix: DMatrix<f64>, rhs: DMatrix<f64>) -> Option<DMatrix<f64>> {
+    matrix.lu().solve(&rhs)
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    pub fn natural_neighbor_coordinates(&self, point: [f64; N]) -> Option<Vec<(usize, f64)>> {
+        // Sibson coordinates of point as (input index, weight) pairs sorted by index. None
+        // outside the convex hull, and near the hull where a super-simplex vertex is a
+        // natural neighbor: its Voronoi cell depends on where the super-simplex is and would
+        // lose volume to point, so the input points alone do not give Sibson coordinates.
+        let Location::Inside {
+            simplex,
+            barycentric,
+        } = self.find_containing_simplex(point)
+        else {
+            return None;
+        };
+        let simplex_vertices = self.simplices.get(&simplex).unwrap().vertices;
+        let barycentric_weights = || {
+            let mut weights: Vec<(usize, f64)> = simplex_vertices
+                .iter()
+                .zip(barycentric.iter())
+                .map(|(id, weight)| (id - Self::N_SUPER_VERTICES, *weight))
+                .collect();
+            weights.sort_unstable_by_key(|(index, _)| *index);
+            weights
+        };
+
+        // Simplices whose circumsphere contains point, i.e. those its insertion would remove
+        let mut cavity = vec![simplex];
+        let mut in_cavity = HashSet::from([simplex]);
+        let mut rejected = HashSet::new();
+        let mut i = 0;
+        while i < cavity.len() {
+            let neighbors = self.simplices.get(&cavity[i]).unwrap().neighbors.clone();
+            for neighbor in neighbors {
+                if in_cavity.contains(&neighbor) || rejected.contains(&neighbor) {
+                    continue;
+                }
+                let other = self.simplices.get(&neighbor).unwrap();
+                if !Self::is_ghost_simplex(&other) && in_sphere(point, other.center, other.radius) {
+                    in_cavity.insert(neighbor);
+                    cavity.push(neighbor);
+                } else {
+                    rejected.insert(neighbor);
+                }
+            }
+            i += 1;
+        }
+        if cavity.iter().any(|id| {
+            let current = self.simplices.get(id).unwrap();
+            current
+                .vertices
+                .iter()
+                .any(|&vertex| Self::is_super_vertex(vertex))
+        }) {
+            return None;
+        }
+
+        // Stolen volume = dual shares of the removed simplices minus those of the simplices
+        // joining point to the cavity boundary, which replace them. Ordered by vertex id so
+        // that the weights come out sorted and the sums do not depend on hashing.
+        let mut stolen: BTreeMap<usize, f64> = BTreeMap::new();
+        for id in &cavity {
+            let current = self.simplices.get(id).unwrap();
+            let coordinates = self.simplex_coordinates(&current);
+            for (k, vertex) in current.vertices.iter().enumerate() {
+                *stolen.entry(*vertex).or_default() += dual_share(&coordinates, current.center, k);
+            }
+            for k in 0..M {
+                let outside = match self.opposite_neighbor(*id, current.vertices[k]) {
+                    Some(neighbor) => !in_cavity.contains(&neighbor),
+                    None => true,
+                };
+                if !outside {
+                    continue;
+                }
+                let mut replaced = coordinates;
+                replaced[k] = point;
+                let (center, _) = circumsphere(replaced);
+                for (j, vertex) in current.vertices.iter().enumerate() {
+                    if j != k {
+                        *stolen.entry(*vertex).or_default() -= dual_share(&replaced, center, j);
+                    }
+                }
+            }
+        }
+
+        // Every natural neighbor loses a non-negative volume, anything below 0 is rounding
+        let total: f64 = stolen.values().filter(|volume| **volume > 0.0).sum();
+        debug_assert!(
+            stolen
+                .values()
+                .all(|volume| *volume >= -STOLEN_VOLUME_TOLERANCE * total),
+            "negative stolen volume {:?} around {:?}",
+            stolen,
+            point
+        );
+        let weights: Vec<(usize, f64)> = stolen
+            .into_iter()
+            .filter(|(_, volume)| *volume > 0.0)
+            .map(|(id, volume)| (id - Self::N_SUPER_VERTICES, volume / total))
+            .collect();
+        if weights.is_empty() {
+            // The point coincides with an input point or the cavity is degenerate
+            return Some(barycentric_weights());
+        }
+        Some(weights)
+    }
+}
+
+pub struct Interpolator<'a, const N: usize, const M: usize, const K: usize> {
+    tree: &'a DelaunayTree<N, M>,
+    // One value per input point, in insertion order
+    values: Vec<[f64; K]>,
+    method: InterpolationMethod,
+    // Least-squares gradients at the input points, computed on first use
+    vertex_gradients: OnceLock<Vec<[[f64; N]; K]>>,
+}
+
+impl<'a, const N: usize, const M: usize, const K: usize> Interpolator<'a, N, M, K> {
+    pub fn new(tree: &'a DelaunayTree<N, M>, values: Vec<[f64; K]>) -> Self {
+        assert_eq!(
+            values.len(),
+            tree.vertices.len() - DelaunayTree::<N, M>::N_SUPER_VERTICES,
+            "Interpolation needs one value per input point"
+        );
+        Interpolator {
+            tree,
+            values,
+            method: InterpolationMethod::default(),
+            vertex_gradients: OnceLock::new(),
+        }
+    }
+
+    pub fn with_method(mut self, method: InterpolationMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn method(&self) -> InterpolationMethod {
+        self.method
+    }
+
+    fn weights(&self, point: [f64; N]) -> Option<Vec<(usize, f64)>> {
+        // Input indices and weights of the points the value is a combination of
+        match self.method {
+            InterpolationMethod::Linear => match self.tree.find_containing_simplex(point) {
+                Location::Inside {
+                    simplex,
+                    barycentric,
+                } => {
+                    let vertices = self.tree.simplices.get(&simplex).unwrap().vertices;
+                    Some(
+                        vertices
+                            .iter()
+                            .zip(barycentric.iter())
+                            .map(|(id, weight)| {
+                                (id - DelaunayTree::<N, M>::N_SUPER_VERTICES, *weight)
+                            })
+                            .collect(),
+                    )
+                }
+                Location::OutsideHull => None,
+            },
+            InterpolationMethod::NaturalNeighbor => self.tree.natural_neighbor_coordinates(point),
+            InterpolationMethod::Nearest => self
+                .tree
+                .nearest_vertex(point)
+                .map(|(index, _)| vec![(index, 1.0)]),
+        }
+    }
+
+    pub fn interpolate(&self, point: [f64; N]) -> Option<[f64; K]> {
+        let weights = self.weights(point)?;
+        let mut value = [0.0; K];
+        for (index, weight) in weights {
+            for (component, x) in value.iter_mut().zip(self.values[index].iter()) {
+                *component += weight * x;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn gradient(&self, point: [f64; N]) -> Option<[[f64; N]; K]> {
+        // Linear interpolation uses the gradient of the containing simplex, the other
+        // methods combine the vertex gradients with their interpolation weights
+        if self.method == InterpolationMethod::Linear {
+            let Location::Inside { simplex, .. } = self.tree.find_containing_simplex(point) else {
+                return None;
+            };
+            return Some(self.simplex_gradient(simplex));
+        }
+        let weights = self.weights(point)?;
+        let vertex_gradients = self.vertex_gradients();
+        let mut gradient = [[0.0; N]; K];
+        for (index, weight) in weights {
+            for c in 0..K {
+                for k in 0..N {
+                    gradient[c][k] += weight * vertex_gradients[index][c][k];
+                }
+            }
+        }
+        Some(gradient)
+    }
+
+    pub fn interpolate_points(&self, points: &[[f64; N]]) -> Vec<Option<[f64; K]>> {
+        points
+            .par_iter()
+            .map(|point| self.interpolate(*point))
+            .collect()
+    }
+
+    pub fn gradient_points(&self, points: &[[f64; N]]) -> Vec<Option<[[f64; N]; K]>> {
+        points
+            .par_iter()
+            .map(|point| self.gradient(*point))
+            .collect()
+    }
+
+    pub fn interpolate_grid(&self, grid: &Grid<N>) -> Vec<Option<[f64; K]>> {
+        (0..grid.len())
+            .into_par_iter()
+            .map(|index| self.interpolate(grid.point(index)))
+            .collect()
+    }
+
+    pub fn gradient_grid(&self, grid: &Grid<N>) -> Vec<Option<[[f64; N]; K]>> {
+        (0..grid.len())
+            .into_par_iter()
+            .map(|index| self.gradient(grid.point(index)))
+            .collect()
+    }
+
+    pub fn vertex_gradients(&self) -> &[[[f64; N]; K]] {
+        self.vertex_gradients.get_or_init(|| {
+            (0..self.values.len())
+                .into_par_iter()
+                .map(|index| self.vertex_gradient(index))
+                .collect()
+        })
+    }
+
+    pub fn simplex_gradient(&self, simplex: usize) -> [[f64; N]; K] {
+        // Solves (p_i - p_0) . gradient = f_i - f_0 for the vertices of the simplex
+        let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+        let vertices = self.tree.simplices.get(&simplex).unwrap().vertices;
+        let coordinates: Vec<[f64; N]> = vertices
+            .iter()
+            .map(|id| self.tree.vertices.get(id).unwrap().coordinates)
+            .collect();
+        let values: Vec<[f64; K]> = vertices.iter().map(|id| self.values[id - first]).collect();
+        let matrix = DMatrix::from_fn(N, N, |i, k| coordinates[i + 1][k] - coordinates[0][k]);
+        let rhs = DMatrix::from_fn(N, K, |i, c| values[i + 1][c] - values[0][c]);
+        let mut gradient = [[0.0; N]; K];
+        if let Some(solution) = solve(matrix, rhs) {
+            for c in 0..K {
+                for k in 0..N {
+                    gradient[c][k] = solution[(k, c)];
+                }
+            }
+        }
+        gradient
+    }
+
+    fn vertex_gradient(&self, index: usize) -> [[f64; N]; K] {
+        // Least-squares fit of a linear function through the Delaunay neighbors, weighted
+        // by inverse squared distance. Exact for linear fields.
+        let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+        let id = index + first;
+        let center = self.tree.vertices.get(&id).unwrap().coordinates;
+        let mut normal = DMatrix::<f64>::zeros(N, N);
+        let mut rhs = DMatrix::<f64>::zeros(N, K);
+        for neighbor in self.tree.vertex_link(id, true) {
+            let coordinates = self.tree.vertices.get(&neighbor).unwrap().coordinates;
+            let mut offset = [0.0; N];
+            for k in 0..N {
+                offset[k] = coordinates[k] - center[k];
+            }
+            let weight = 1.0 / offset.iter().map(|x| x * x).sum::<f64>();
+            for i in 0..N {
+                for j in 0..N {
+                    normal[(i, j)] += weight * offset[i] * offset[j];
+                }
+                for c in 0..K {
+                    rhs[(i, c)] += weight
+                        * offset[i]
+                        * (self.values[neighbor - first][c] - self.values[index][c]);
+                }
+            }
+        }
+        let mut gradient = [[0.0; N]; K];
+        if let Some(solution) = solve(normal, rhs) {
+            for c in 0..K {
+                for k in 0..N {
+                    gradient[c][k] = solution[(k, c)];
+                }
+            }
+        }
+        gradient
+    }
+}
+
+impl<'a, const N: usize, const M: usize> Interpolator<'a, N, M, 1> {
+    pub fn from_scalars(tree: &'a DelaunayTree<N, M>, values: &[f64]) -> Self {
+        Self::new(tree, values.iter().map(|value| [*value]).collect())
+    }
+}
@@ -0,0 +1,106 @@
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+
+// Parallel iterators over the edges, facets and vertex links of the triangulation, each
+// element visited once without a global de-duplication pass. Items use vertex and simplex
+// ids and come in a deterministic order. Ghost vertices and simplices never show up. With
+// exclude_super, only real simplices, i.e. those between input points, are considered.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Facet<const N: usize> {
+    // Vertex ids with the orientation induced by simplex
+    pub vertices: [usize; N],
+    pub simplex: usize,
+    // The simplex on the other side, None on the boundary of the considered simplices
+    pub neighbor: Option<usize>,
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    fn considered(&self, simplex_id: usize, exclude_super: bool) -> bool {
+        let simplex = self.simplices.get(&simplex_id).unwrap();
+        if exclude_super {
+            Self::is_real_simplex(&simplex)
+        } else {
+            !Self::is_ghost_simplex(&simplex)
+        }
+    }
+
+    fn considered_vertex(vertex_id: usize, exclude_super: bool) -> bool {
+        if exclude_super {
+            !Self::is_super_vertex(vertex_id)
+        } else {
+            !Self::is_ghost_vertex(vertex_id)
+        }
+    }
+
+    pub fn vertex_link(&self, vertex_id: usize, exclude_super: bool) -> Vec<usize> {
+        // Vertices sharing a considered simplex with vertex_id, sorted
+        let star = self.vertices.get(&vertex_id).unwrap().simplex.clone();
+        let mut link: Vec<usize> = star
+            .into_iter()
+            .filter(|id| self.considered(*id, exclude_super))
+            .flat_map(|id| self.simplices.get(&id).unwrap().vertices)
+            .filter(|&other| other != vertex_id)
+            .collect();
+        link.sort_unstable();
+        link.dedup();
+        link
+    }
+
+    pub fn vertex_links(
+        &self,
+        exclude_super: bool,
+    ) -> impl IndexedParallelIterator<Item = (usize, Vec<usize>)> + '_ {
+        // (vertex id, link) for every considered vertex, in vertex id order
+        let ids: Vec<usize> = (0..self.vertices.len())
+            .filter(|&id| Self::considered_vertex(id, exclude_super))
+            .collect();
+        ids.into_par_iter()
+            .map(move |id| (id, self.vertex_link(id, exclude_super)))
+    }
+
+    pub fn unique_edges(
+        &self,
+        exclude_super: bool,
+    ) -> impl ParallelIterator<Item = (usize, usize)> + '_ {
+        // Every edge as (a, b) with a < b, emitted by its smaller end, sorted
+        self.vertex_links(exclude_super)
+            .flat_map_iter(|(id, link)| {
+                link.into_iter()
+                    .filter(move |&other| other > id)
+                    .map(move |other| (id, other))
+            })
+    }
+
+    pub fn unique_facets(
+        &self,
+        exclude_super: bool,
+    ) -> impl ParallelIterator<Item = Facet<N>> + '_ {
+        // Every facet once, emitted by the incident simplex with the smaller id, or by the
+        // only considered one on the boundary. Ordered by that simplex id.
+        let mut ids: Vec<usize> = self
+            .simplices
+            .iter()
+            .map(|simplex| *simplex.key())
+            .filter(|id| self.considered(*id, exclude_super))
+            .collect();
+        ids.sort_unstable();
+        ids.into_par_iter().flat_map_iter(move |id| {
+            let vertices = self.oriented_vertices(id);
+            (0..M).filter_map(move |k| {
+                let neighbor = self
+                    .opposite_neighbor(id, vertices[k])
+                    .filter(|neighbor| self.considered(*neighbor, exclude_super));
+                if neighbor.is_some_and(|neighbor| neighbor < id) {
+                    return None;
+                }
+                Some(Facet {
+                    vertices: Self::facet(vertices, k),
+                    simplex: id,
+                    neighbor,
+                })
+            })
+        })
+    }
+}
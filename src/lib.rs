@@ -1,12 +1,35 @@
 #![crate_name = "voronoids"]
 
+pub mod alpha_shape;
 pub mod delaunay_tree;
+pub mod dtfe;
 pub mod geometry;
+pub mod gmsh;
+pub mod graph;
+pub mod interpolation;
+pub mod iterators;
+pub mod lloyd;
+pub mod npy;
+pub mod points;
+pub mod quality;
+pub mod query;
+pub mod refinement;
+pub mod render;
 pub mod scheduler;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod sliver;
+pub mod streaming;
+pub mod tetgen;
+pub mod validation;
+pub mod voronoi;
+pub mod vtk;
 
 use std::collections::HashMap;
 
-use delaunay_tree::{DelaunayTree, TreeUpdate};
+use delaunay_tree::{DelaunayTree, Parallelism, Progress};
+use graph::Graph;
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
 
 #[pyclass]
@@ -59,6 +82,35 @@ impl PySimplex {
     }
 }
 
+#[pyclass]
+struct PyGraph {
+    graph: Graph,
+}
+
+#[pymethods]
+impl PyGraph {
+    // Compressed sparse row arrays: the neighbors of point i are
+    // neighbors[offsets[i]:offsets[i + 1]], with the edge lengths in weights
+    #[getter]
+    fn offsets(&self) -> Vec<usize> {
+        self.graph.offsets.clone()
+    }
+
+    #[getter]
+    fn neighbors(&self) -> Vec<usize> {
+        self.graph.neighbors.clone()
+    }
+
+    #[getter]
+    fn weights(&self) -> Vec<f64> {
+        self.graph.weights.clone()
+    }
+
+    fn edges(&self) -> Vec<(usize, usize, f64)> {
+        self.graph.edges()
+    }
+}
+
 #[pyclass]
 struct PyDelauanyTree {
     tree: DelaunayTree<3, 4>,
@@ -75,7 +127,7 @@ impl PyDelauanyTree {
         HashMap::from_iter(self.tree.vertices.iter().map(|id| {
             let vertex = &self.tree.vertices.get(id.key()).unwrap();
             (
-                id.key().clone(),
+                *id.key(),
                 PyVertex {
                     point: vertex.coordinates,
                     simplex: vertex.simplex.clone(),
@@ -99,37 +151,79 @@ impl PyDelauanyTree {
             )
         }))
     }
-}
 
-#[pyfunction]
-fn delaunay(points: Vec<[f64; 3]>) -> PyDelauanyTree {
-    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
-    let n_points = delaunay_tree.vertices.len();
-    println!("Starting point insertion");
-
-    for (i, &point) in points.iter().enumerate() {
-        let update = TreeUpdate::new(n_points + i, point, &delaunay_tree);
-        delaunay_tree.insert_point(&update);
-
-        if i == 1e5 as usize {
-            println!("More than 1e5 points, switching to parallel insert");
-            delaunay_tree.add_points_to_tree(points[i + 1..].to_vec());
-            break;
+    fn delaunay_graph(&self) -> PyGraph {
+        PyGraph {
+            graph: self.tree.delaunay_graph(),
         }
     }
 
-    if points.len() <= 1e5 as usize {
-        println!("Less than 1e5 points, completed sequential insert");
+    fn gabriel_graph(&self) -> PyGraph {
+        PyGraph {
+            graph: self.tree.gabriel_graph(),
+        }
     }
-    PyDelauanyTree {
-        tree: delaunay_tree,
+
+    fn relative_neighborhood_graph(&self) -> PyGraph {
+        PyGraph {
+            graph: self.tree.relative_neighborhood_graph(),
+        }
     }
+
+    fn minimum_spanning_tree(&self) -> PyGraph {
+        PyGraph {
+            graph: self.tree.euclidean_minimum_spanning_tree(),
+        }
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (points, n_threads=None, progress=None))]
+fn delaunay(
+    py: Python<'_>,
+    points: Vec<[f64; 3]>,
+    n_threads: Option<usize>,
+    progress: Option<PyObject>,
+) -> PyResult<PyDelauanyTree> {
+    // n_threads=None uses all cores, n_threads=1 inserts serially.
+    // progress is called as progress(points_inserted, total_points, batch, n_batches, elapsed)
+    // with the elapsed time in seconds, e.g. to update a tqdm bar.
+    let parallelism = match n_threads {
+        None => Parallelism::Global,
+        Some(1) => Parallelism::Serial,
+        Some(n_threads) => Parallelism::threads(n_threads)
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))?,
+    };
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone()).with_parallelism(parallelism);
+    if let Some(callback) = progress {
+        delaunay_tree = delaunay_tree.with_progress(move |progress: &Progress| {
+            Python::with_gil(|py| {
+                let arguments = (
+                    progress.points_inserted,
+                    progress.total_points,
+                    progress.batch,
+                    progress.n_batches,
+                    progress.elapsed.as_secs_f64(),
+                );
+                if let Err(err) = callback.call1(py, arguments) {
+                    err.print(py);
+                }
+            })
+        });
+    }
+    log::info!("Starting point insertion");
+    // Release the GIL so the callback can take it from rayon's worker threads
+    py.allow_threads(|| delaunay_tree.insert_points(&points));
+    Ok(PyDelauanyTree {
+        tree: delaunay_tree,
+    })
 }
 
 #[pymodule]
 fn voronoids(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyVertex>()?;
     m.add_class::<PySimplex>()?;
+    m.add_class::<PyGraph>()?;
     m.add_class::<PyDelauanyTree>()?;
     m.add_function(wrap_pyfunction!(delaunay, m)?)?;
     Ok(())
@@ -0,0 +1,442 @@
+use std::fmt;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+use crate::geometry::simplex_volume;
+
+// Lloyd relaxation towards a centroidal Voronoi tessellation (CVT). The Voronoi cell of
+// every input point is clipped to a convex domain by cutting the domain with the
+// bisectors between the point and its Delaunay neighbors. Cells are split into simplices
+// and the density is integrated with the degree 2 rule that puts one point per vertex at
+// barycentric coordinates (a, b, ..., b), so that masses, centroids and energies are
+// exact for a constant density. Every iteration moves the points to the centroids of
+// their cells and triangulates them again, keeping vertex ids. The energy is the sum over
+// the cells of the integral of the density times the squared distance to the point.
+
+// Density at a point, it should be positive over the domain
+pub type DensityFunction = Arc<dyn Fn(&[f64]) -> f64 + Send + Sync>;
+
+// A box cut by half-spaces normal . x <= offset
+#[derive(Debug, Clone, PartialEq)]
+pub struct Domain<const N: usize> {
+    pub lower: [f64; N],
+    pub upper: [f64; N],
+    pub halfspaces: Vec<([f64; N], f64)>,
+}
+
+impl<const N: usize> Domain<N> {
+    pub fn new(lower: [f64; N], upper: [f64; N]) -> Self {
+        Domain {
+            lower,
+            upper,
+            halfspaces: vec![],
+        }
+    }
+
+    pub fn with_halfspace(mut self, normal: [f64; N], offset: f64) -> Self {
+        self.halfspaces.push((normal, offset));
+        self
+    }
+
+    pub fn contains(&self, point: [f64; N]) -> bool {
+        (0..N).all(|k| self.lower[k] <= point[k] && point[k] <= self.upper[k])
+            && self
+                .halfspaces
+                .iter()
+                .all(|(normal, offset)| dot(normal, &point) <= *offset)
+    }
+
+    pub fn diameter(&self) -> f64 {
+        // Diagonal of the box
+        self.lower
+            .iter()
+            .zip(self.upper.iter())
+            .map(|(l, u)| (u - l) * (u - l))
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellMoments<const N: usize> {
+    // Integral of the density over the clipped cell, its volume for a constant density
+    pub mass: f64,
+    // Center of mass, the point itself if its cell misses the domain
+    pub centroid: [f64; N],
+    // Integral of the density times the squared distance to the point
+    pub energy: f64,
+}
+
+#[derive(Clone)]
+pub struct LloydOptions {
+    pub max_iterations: usize,
+    // Converged once no point moves by more than tolerance times the domain diameter
+    pub tolerance: f64,
+    // Converged once an iteration lowers the energy by less than this fraction, 0 to
+    // rely on tolerance only
+    pub energy_tolerance: f64,
+    // Constant density if None
+    pub density: Option<DensityFunction>,
+}
+
+impl Default for LloydOptions {
+    fn default() -> Self {
+        LloydOptions {
+            max_iterations: 100,
+            tolerance: 1e-6,
+            energy_tolerance: 0.0,
+            density: None,
+        }
+    }
+}
+
+impl fmt::Debug for LloydOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LloydOptions")
+            .field("max_iterations", &self.max_iterations)
+            .field("tolerance", &self.tolerance)
+            .field("energy_tolerance", &self.energy_tolerance)
+            .field("density", &self.density.as_ref().map(|_| "Fn"))
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LloydReport {
+    pub iterations: usize,
+    pub converged: bool,
+    // Energy before every iteration and after the last one
+    pub energy: Vec<f64>,
+    // Largest move of every iteration
+    pub max_displacement: Vec<f64>,
+}
+
+fn dot<const N: usize>(a: &[f64; N], b: &[f64; N]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+fn squared_distance<const N: usize>(a: &[f64; N], b: &[f64; N]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+fn clip_polygon<const N: usize>(
+    polygon: &[[f64; N]],
+    normal: &[f64; N],
+    offset: f64,
+    cut: &mut Vec<[f64; N]>,
+) -> Vec<[f64; N]> {
+    // Sutherland-Hodgman against normal . x <= offset. Points on the plane are added to
+    // cut.
+    let mut output = vec![];
+    for (i, current) in polygon.iter().enumerate() {
+        let next = &polygon[(i + 1) % polygon.len()];
+        let (a, b) = (dot(normal, current) - offset, dot(normal, next) - offset);
+        if a <= 0.0 {
+            output.push(*current);
+            if a == 0.0 {
+                cut.push(*current);
+            }
+        }
+        if (a < 0.0 && b > 0.0) || (a > 0.0 && b < 0.0) {
+            let t = a / (a - b);
+            let mut point = [0.0; N];
+            for k in 0..N {
+                point[k] = current[k] + t * (next[k] - current[k]);
+            }
+            output.push(point);
+            cut.push(point);
+        }
+    }
+    output
+}
+
+// Convex polygon in 2D, convex polyhedron given by its face polygons in 3D
+struct Polytope<const N: usize> {
+    faces: Vec<Vec<[f64; N]>>,
+}
+
+impl<const N: usize> Polytope<N> {
+    fn cuboid(lower: &[f64; N], upper: &[f64; N]) -> Self {
+        let corner = |bits: usize| {
+            let mut point = [0.0; N];
+            for k in 0..N {
+                point[k] = if (bits >> k) & 1 == 1 {
+                    upper[k]
+                } else {
+                    lower[k]
+                };
+            }
+            point
+        };
+        let faces: Vec<Vec<usize>> = if N == 2 {
+            vec![vec![0, 1, 3, 2]]
+        } else {
+            vec![
+                vec![0, 2, 3, 1],
+                vec![4, 5, 7, 6],
+                vec![0, 1, 5, 4],
+                vec![2, 6, 7, 3],
+                vec![0, 4, 6, 2],
+                vec![1, 3, 7, 5],
+            ]
+        };
+        Polytope {
+            faces: faces
+                .into_iter()
+                .map(|face| face.into_iter().map(corner).collect())
+                .collect(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+
+    fn clip(&mut self, normal: &[f64; N], offset: f64) {
+        // Keep the part with normal . x <= offset
+        let mut cut = vec![];
+        self.faces = self
+            .faces
+            .iter()
+            .map(|face| clip_polygon(face, normal, offset, &mut cut))
+            .filter(|face| face.len() >= 3)
+            .collect();
+        if N == 2 || self.faces.is_empty() {
+            return;
+        }
+        // Close the polyhedron with the cut points ordered around their center
+        let scale = cut.iter().map(|p| dot(p, p)).fold(0.0, f64::max);
+        let mut cap: Vec<[f64; N]> = vec![];
+        for point in cut {
+            if cap
+                .iter()
+                .all(|other| squared_distance(other, &point) > 1e-24 * scale)
+            {
+                cap.push(point);
+            }
+        }
+        if cap.len() < 3 {
+            return;
+        }
+        let mut center = [0.0; N];
+        for point in &cap {
+            for k in 0..N {
+                center[k] += point[k] / cap.len() as f64;
+            }
+        }
+        // Orthonormal basis (u, v) of the plane
+        let length = dot(normal, normal).sqrt();
+        let n = normal.map(|x| x / length);
+        let axis = (0..N)
+            .min_by(|&i, &j| n[i].abs().total_cmp(&n[j].abs()))
+            .unwrap();
+        let mut u = [0.0; N];
+        u[axis] = 1.0;
+        let projection = dot(&u, &n);
+        for k in 0..N {
+            u[k] -= projection * n[k];
+        }
+        let length = dot(&u, &u).sqrt();
+        u = u.map(|x| x / length);
+        let mut v = [0.0; N];
+        for k in 0..N {
+            v[k] = n[(k + 1) % 3] * u[(k + 2) % 3] - n[(k + 2) % 3] * u[(k + 1) % 3];
+        }
+        let angle = |point: &[f64; N]| {
+            let mut relative = [0.0; N];
+            for k in 0..N {
+                relative[k] = point[k] - center[k];
+            }
+            dot(&relative, &v).atan2(dot(&relative, &u))
+        };
+        cap.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        self.faces.push(cap);
+    }
+
+    fn simplices<const M: usize>(&self) -> Vec<[[f64; N]; M]> {
+        // Fan of the polygon in 2D, fans of the faces joined to the vertex average in 3D
+        let mut simplices = vec![];
+        let apex = if N == 2 {
+            None
+        } else {
+            let n_points: usize = self.faces.iter().map(|face| face.len()).sum();
+            let mut apex = [0.0; N];
+            for point in self.faces.iter().flatten() {
+                for k in 0..N {
+                    apex[k] += point[k] / n_points as f64;
+                }
+            }
+            Some(apex)
+        };
+        for face in &self.faces {
+            for i in 1..face.len() - 1 {
+                let mut points = vec![face[0], face[i], face[i + 1]];
+                points.extend(apex);
+                let mut simplex = [[0.0; N]; M];
+                for (vertex, point) in simplex.iter_mut().zip(points) {
+                    *vertex = point;
+                }
+                simplices.push(simplex);
+            }
+        }
+        simplices
+    }
+}
+
+fn integrate<const N: usize, const M: usize>(
+    cell: &Polytope<N>,
+    site: [f64; N],
+    density: Option<&DensityFunction>,
+) -> CellMoments<N> {
+    let b = ((N + 2) as f64 - ((N + 2) as f64).sqrt()) / ((N + 1) * (N + 2)) as f64;
+    let a = 1.0 - N as f64 * b;
+    let mut mass = 0.0;
+    let mut moment = [0.0; N];
+    let mut energy = 0.0;
+    for simplex in cell.simplices::<M>() {
+        let weight = simplex_volume(simplex) / M as f64;
+        for i in 0..M {
+            let mut point = [0.0; N];
+            for (j, vertex) in simplex.iter().enumerate() {
+                let coefficient = if i == j { a } else { b };
+                for k in 0..N {
+                    point[k] += coefficient * vertex[k];
+                }
+            }
+            let w = weight * density.map_or(1.0, |density| density(&point));
+            mass += w;
+            for k in 0..N {
+                moment[k] += w * point[k];
+            }
+            energy += w * squared_distance(&point, &site);
+        }
+    }
+    let centroid = if mass > 0.0 {
+        moment.map(|x| x / mass)
+    } else {
+        site
+    };
+    CellMoments {
+        mass,
+        centroid,
+        energy,
+    }
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    fn clipped_cell(&self, vertex_id: usize, domain: &Domain<N>) -> Polytope<N> {
+        let site = self.vertices.get(&vertex_id).unwrap().coordinates;
+        let mut cell = Polytope::cuboid(&domain.lower, &domain.upper);
+        for (normal, offset) in &domain.halfspaces {
+            cell.clip(normal, *offset);
+        }
+        // Bisectors with every input point sharing a simplex, including those only
+        // connected through the super-simplex
+        for other in self.vertex_link(vertex_id, false) {
+            if cell.is_empty() {
+                break;
+            }
+            if Self::is_super_vertex(other) {
+                continue;
+            }
+            let point = self.vertices.get(&other).unwrap().coordinates;
+            let mut normal = [0.0; N];
+            for k in 0..N {
+                normal[k] = point[k] - site[k];
+            }
+            cell.clip(&normal, (dot(&point, &point) - dot(&site, &site)) / 2.0);
+        }
+        cell
+    }
+
+    pub fn cell_moments(
+        &self,
+        domain: &Domain<N>,
+        density: Option<&DensityFunction>,
+    ) -> Vec<CellMoments<N>> {
+        // Moments of the clipped Voronoi cells of all input points, in input order
+        (Self::N_SUPER_VERTICES..self.vertices.len())
+            .into_par_iter()
+            .map(|id| {
+                let site = self.vertices.get(&id).unwrap().coordinates;
+                integrate::<N, M>(&self.clipped_cell(id, domain), site, density)
+            })
+            .collect()
+    }
+
+    pub fn cvt_energy(&self, domain: &Domain<N>, density: Option<&DensityFunction>) -> f64 {
+        self.cell_moments(domain, density)
+            .iter()
+            .map(|moments| moments.energy)
+            .sum()
+    }
+
+    fn relax(
+        &mut self,
+        domain: &Domain<N>,
+        options: &LloydOptions,
+        retriangulate: fn(&Self, &[[f64; N]]) -> Self,
+    ) -> LloydReport {
+        let mut report = LloydReport::default();
+        let density = options.density.as_ref();
+        for _ in 0..options.max_iterations {
+            let moments = self.cell_moments(domain, density);
+            let energy: f64 = moments.iter().map(|moments| moments.energy).sum();
+            let stalled = report
+                .energy
+                .last()
+                .is_some_and(|previous| previous - energy <= options.energy_tolerance * previous);
+            report.energy.push(energy);
+            if options.energy_tolerance > 0.0 && stalled {
+                report.converged = true;
+                break;
+            }
+
+            let points: Vec<[f64; N]> = moments.iter().map(|moments| moments.centroid).collect();
+            let displacement = points
+                .iter()
+                .enumerate()
+                .map(|(i, centroid)| {
+                    let site = self
+                        .vertices
+                        .get(&(i + Self::N_SUPER_VERTICES))
+                        .unwrap()
+                        .coordinates;
+                    squared_distance(centroid, &site).sqrt()
+                })
+                .fold(0.0, f64::max);
+            let tree = retriangulate(self, &points);
+            self.replace_triangulation(tree);
+            report.iterations += 1;
+            report.max_displacement.push(displacement);
+            log::debug!(
+                "Lloyd iteration {}: energy {:e}, largest move {:e}",
+                report.iterations,
+                energy,
+                displacement
+            );
+            if displacement <= options.tolerance * domain.diameter() {
+                report.converged = true;
+                break;
+            }
+        }
+        if report.energy.len() == report.iterations {
+            report.energy.push(self.cvt_energy(domain, density));
+        }
+        report
+    }
+}
+
+impl DelaunayTree<2, 3> {
+    pub fn lloyd(&mut self, domain: &Domain<2>, options: &LloydOptions) -> LloydReport {
+        self.relax(domain, options, Self::retriangulate)
+    }
+}
+
+impl DelaunayTree<3, 4> {
+    pub fn lloyd(&mut self, domain: &Domain<3>, options: &LloydOptions) -> LloydReport {
+        self.relax(domain, options, Self::retriangulate)
+    }
+}
@@ -1,3 +1,416 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+use std::process::exit;
+use std::time::Instant;
+
+use voronoids::delaunay_tree::{DelaunayTree, Parallelism};
+use voronoids::npy::{self, SimplexArrays};
+use voronoids::vtk::{VtkFormat, VtkMesh};
+
+const USAGE: &str = "Build the Delaunay triangulation of a point set.
+
+Usage: voronoids [OPTIONS] <INPUT>
+
+Arguments:
+  <INPUT>  Points as CSV/whitespace separated text, .npy, .npz (first array) or
+           raw little endian float64 binary, '-' reads text from stdin
+
+Options:
+  -o, --output <PATH>         Output file, '-' for stdout [default: -]
+  -f, --format <FORMAT>       Output format [default: simplices]
+                                simplices    one line of point indices per simplex
+                                neighbors    one line of neighbor indices per point
+                                voronoi      index, volume and vertices of bounded Voronoi cells
+                                vtk          legacy binary VTK unstructured grid
+                                vtu          XML VTK unstructured grid
+                                voronoi-vtu  bounded Voronoi cells as XML VTK
+                                npz          simplices, neighbors, centers and radii arrays
+  -d, --dim <2|3>             Dimension, inferred from text and NumPy input [default: 3]
+  -i, --input-format <FORMAT> text, npy, npz or bin, inferred from the extension
+  -j, --threads <N>           Number of worker threads [default: all cores]
+  -q, --quiet                 Do not print statistics to stderr
+  -h, --help                  Print this help";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputFormat {
+    Text,
+    Npy,
+    Npz,
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Simplices,
+    Neighbors,
+    Voronoi,
+    Vtk,
+    Vtu,
+    VoronoiVtu,
+    Npz,
+}
+
+#[derive(Debug)]
+struct Options {
+    input: String,
+    output: String,
+    format: OutputFormat,
+    dimension: Option<usize>,
+    input_format: Option<InputFormat>,
+    threads: Option<usize>,
+    quiet: bool,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    exit(2);
+}
+
+fn parse_options(args: Vec<String>) -> Options {
+    let mut options = Options {
+        input: String::new(),
+        output: "-".to_string(),
+        format: OutputFormat::Simplices,
+        dimension: None,
+        input_format: None,
+        threads: None,
+        quiet: false,
+    };
+    let mut input = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .unwrap_or_else(|| fail(&format!("{} needs a value", name)))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            "-q" | "--quiet" => options.quiet = true,
+            "-o" | "--output" => options.output = value(&arg),
+            "-f" | "--format" => {
+                options.format = match value(&arg).as_str() {
+                    "simplices" => OutputFormat::Simplices,
+                    "neighbors" => OutputFormat::Neighbors,
+                    "voronoi" => OutputFormat::Voronoi,
+                    "vtk" => OutputFormat::Vtk,
+                    "vtu" => OutputFormat::Vtu,
+                    "voronoi-vtu" => OutputFormat::VoronoiVtu,
+                    "npz" => OutputFormat::Npz,
+                    other => fail(&format!("unknown output format {:?}", other)),
+                }
+            }
+            "-d" | "--dim" => match value(&arg).as_str() {
+                "2" => options.dimension = Some(2),
+                "3" => options.dimension = Some(3),
+                other => fail(&format!("dimension must be 2 or 3, not {:?}", other)),
+            },
+            "-i" | "--input-format" => {
+                options.input_format = match value(&arg).as_str() {
+                    "text" | "csv" => Some(InputFormat::Text),
+                    "npy" => Some(InputFormat::Npy),
+                    "npz" => Some(InputFormat::Npz),
+                    "bin" => Some(InputFormat::Binary),
+                    other => fail(&format!("unknown input format {:?}", other)),
+                }
+            }
+            "-j" | "--threads" => {
+                let threads = value(&arg);
+                options.threads = match threads.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => fail(&format!("invalid thread count {:?}", threads)),
+                }
+            }
+            _ if arg.starts_with('-') && arg != "-" => fail(&format!("unknown option {:?}", arg)),
+            _ if input.is_none() => input = Some(arg),
+            _ => fail(&format!("unexpected argument {:?}", arg)),
+        }
+    }
+    options.input = input.unwrap_or_else(|| fail("no input given"));
+    options
+}
+
+fn read_text<R: BufRead>(reader: R) -> io::Result<(usize, Vec<f64>)> {
+    // Rows of comma or whitespace separated numbers. Blank lines, '#' comments
+    // and a header line that does not parse as numbers are skipped.
+    let mut columns = 0;
+    let mut data = vec![];
+    let mut first_row = true;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let content = line.split('#').next().unwrap_or("").trim();
+        if content.is_empty() {
+            continue;
+        }
+        let row: Result<Vec<f64>, _> = content
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+            .map(str::parse::<f64>)
+            .collect();
+        let header = first_row;
+        first_row = false;
+        let row = match row {
+            Ok(row) => row,
+            Err(_) if header => continue,
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: cannot parse {:?}", i + 1, content),
+                ))
+            }
+        };
+        if columns == 0 {
+            columns = row.len();
+        } else if row.len() != columns {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "line {}: expected {} columns, found {}",
+                    i + 1,
+                    columns,
+                    row.len()
+                ),
+            ));
+        }
+        data.extend(row);
+    }
+    Ok((columns, data))
+}
+
+fn read_input(options: &Options) -> io::Result<(usize, Vec<f64>)> {
+    // Number of columns and row-major coordinates
+    let input_format = options.input_format.unwrap_or_else(|| {
+        match Path::new(&options.input)
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("npy") => InputFormat::Npy,
+            Some("npz") => InputFormat::Npz,
+            Some("bin") | Some("raw") | Some("dat") => InputFormat::Binary,
+            _ => InputFormat::Text,
+        }
+    });
+    let reader: Box<dyn Read> = if options.input == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(&options.input)?)
+    };
+    let reader = BufReader::new(reader);
+    match input_format {
+        InputFormat::Text => read_text(reader),
+        InputFormat::Npy | InputFormat::Npz => {
+            let (shape, data) = if input_format == InputFormat::Npy {
+                npy::read_array(reader)?
+            } else {
+                // Zip archives are read from the end, so buffer the whole file
+                let mut bytes = vec![];
+                let mut reader = reader;
+                reader.read_to_end(&mut bytes)?;
+                npy::read_npz_array(Cursor::new(bytes), None)?
+            };
+            if shape.len() != 2 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected a two dimensional array, found shape {:?}", shape),
+                ));
+            }
+            Ok((shape[1], data))
+        }
+        InputFormat::Binary => {
+            let mut bytes = vec![];
+            let mut reader = reader;
+            reader.read_to_end(&mut bytes)?;
+            if bytes.len() % 8 != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} bytes is not a whole number of float64", bytes.len()),
+                ));
+            }
+            let data = bytes
+                .chunks_exact(8)
+                .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()))
+                .collect();
+            Ok((options.dimension.unwrap_or(3), data))
+        }
+    }
+}
+
+fn to_points<const N: usize>(data: &[f64]) -> Vec<[f64; N]> {
+    data.chunks_exact(N)
+        .map(|chunk| chunk.try_into().unwrap())
+        .collect()
+}
+
+fn write_output<const N: usize, const M: usize, W: Write>(
+    tree: &DelaunayTree<N, M>,
+    format: OutputFormat,
+    mut writer: W,
+) -> io::Result<()> {
+    let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+    match format {
+        OutputFormat::Simplices => {
+            for id in tree.real_simplices() {
+                let vertices: Vec<String> = tree
+                    .oriented_vertices(id)
+                    .iter()
+                    .map(|vertex| (vertex - first).to_string())
+                    .collect();
+                writeln!(writer, "{}", vertices.join(" "))?;
+            }
+        }
+        OutputFormat::Neighbors => {
+            for id in first..tree.vertices.len() {
+                let mut neighbors: Vec<usize> = tree
+                    .vertices
+                    .get(&id)
+                    .unwrap()
+                    .simplex
+                    .iter()
+                    .flat_map(|simplex| tree.simplices.get(simplex).unwrap().vertices)
+                    .filter(|&vertex| vertex != id && vertex >= first)
+                    .collect();
+                neighbors.sort_unstable();
+                neighbors.dedup();
+                let neighbors: Vec<String> = neighbors
+                    .iter()
+                    .map(|vertex| (vertex - first).to_string())
+                    .collect();
+                writeln!(writer, "{}", neighbors.join(" "))?;
+            }
+        }
+        OutputFormat::Voronoi => {
+            for cell in tree.voronoi_cells().iter().filter(|cell| cell.bounded) {
+                let vertices: Vec<String> = cell
+                    .vertices
+                    .iter()
+                    .flatten()
+                    .map(|x| x.to_string())
+                    .collect();
+                writeln!(
+                    writer,
+                    "{} {} {}",
+                    cell.generator - first,
+                    cell.volume(),
+                    vertices.join(" ")
+                )?;
+            }
+        }
+        OutputFormat::Vtk => VtkMesh::delaunay(tree)
+            .with_volume(tree)
+            .write(&mut writer, VtkFormat::LegacyBinary)?,
+        OutputFormat::Vtu => VtkMesh::delaunay(tree)
+            .with_volume(tree)
+            .write(&mut writer, VtkFormat::XmlBinary)?,
+        OutputFormat::VoronoiVtu => VtkMesh::voronoi(tree)
+            .with_volume(tree)
+            .write(&mut writer, VtkFormat::XmlBinary)?,
+        OutputFormat::Npz => {
+            let archive = SimplexArrays::new(tree).write_npz(Cursor::new(vec![]), true)?;
+            writer.write_all(archive.get_ref())?
+        }
+    }
+    writer.flush()
+}
+
+fn run<const N: usize, const M: usize>(
+    tree: &DelaunayTree<N, M>,
+    options: &Options,
+    start: Instant,
+) -> io::Result<()> {
+    if !options.quiet {
+        eprintln!(
+            "Triangulated {} points into {} simplices ({} including the super-simplex) in {:?}",
+            tree.vertices.len() - DelaunayTree::<N, M>::N_SUPER_VERTICES,
+            tree.real_simplices().len(),
+            tree.simplices.len(),
+            start.elapsed()
+        );
+    }
+    let start = Instant::now();
+    if options.output == "-" {
+        write_output(tree, options.format, BufWriter::new(io::stdout().lock()))?;
+    } else {
+        write_output(
+            tree,
+            options.format,
+            BufWriter::new(File::create(&options.output)?),
+        )?;
+    }
+    if !options.quiet {
+        eprintln!("Wrote output in {:?}", start.elapsed());
+    }
+    Ok(())
+}
+
 fn main() {
-    println!("Hello, world!");
+    let options = parse_options(std::env::args().skip(1).collect());
+    // The thread count only applies to insertion
+    let parallelism = match options.threads {
+        Some(threads) => Parallelism::threads(threads).unwrap_or_else(|err| {
+            eprintln!("error: cannot start {} threads: {}", threads, err);
+            exit(1);
+        }),
+        None => Parallelism::Global,
+    };
+
+    let start = Instant::now();
+    let (columns, data) = read_input(&options).unwrap_or_else(|err| {
+        eprintln!("error: cannot read {}: {}", options.input, err);
+        exit(1);
+    });
+    let dimension = options.dimension.unwrap_or(columns);
+    if dimension != columns && options.input_format != Some(InputFormat::Binary) {
+        eprintln!(
+            "error: input has {} columns but --dim is {}",
+            columns, dimension
+        );
+        exit(1);
+    }
+    if data.is_empty() || !data.len().is_multiple_of(dimension) {
+        eprintln!("error: input does not hold a list of {}D points", dimension);
+        exit(1);
+    }
+    if !options.quiet {
+        eprintln!(
+            "Read {} {}D points in {:?}",
+            data.len() / dimension,
+            dimension,
+            start.elapsed()
+        );
+    }
+
+    let start = Instant::now();
+    let result = match dimension {
+        2 => {
+            let points = to_points::<2>(&data);
+            let mut tree = DelaunayTree::<2, 3>::new(points.clone()).with_parallelism(parallelism);
+            tree.insert_points(&points);
+            run(&tree, &options, start)
+        }
+        3 => {
+            let points = to_points::<3>(&data);
+            let mut tree = DelaunayTree::<3, 4>::new(points.clone()).with_parallelism(parallelism);
+            tree.insert_points(&points);
+            run(&tree, &options, start)
+        }
+        _ => {
+            eprintln!(
+                "error: only 2D and 3D points are supported, found {} columns",
+                dimension
+            );
+            exit(1);
+        }
+    };
+    match result {
+        // Output piped into e.g. head
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(err) => {
+            eprintln!("error: cannot write {}: {}", options.output, err);
+            exit(1);
+        }
+        Ok(()) => {}
+    }
 }
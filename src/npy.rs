@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::delaunay_tree::DelaunayTree;
+
+// Readers and writers for NumPy .npy files and .npz archives. Points are read from
+// float32 or float64 arrays of shape (n, N). The triangulation is written as int64
+// simplex and neighbor arrays and float64 center and radius arrays, numbered like
+// scipy.spatial.Delaunay: point indices start at 0 and -1 marks a missing neighbor.
+
+pub const MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NpyHeader {
+    pub descr: String,
+    pub fortran_order: bool,
+    pub shape: Vec<usize>,
+}
+
+fn header_value<'a>(header: &'a str, key: &str) -> io::Result<&'a str> {
+    // The header is a Python dict literal, e.g.
+    // {'descr': '<f8', 'fortran_order': False, 'shape': (100, 3), }
+    let start = header
+        .find(&format!("'{}'", key))
+        .ok_or_else(|| invalid_data(format!("npy header has no {:?} entry", key)))?
+        + key.len()
+        + 2;
+    let rest = header[start..]
+        .trim_start()
+        .trim_start_matches(':')
+        .trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')').map(|i| i + 1)
+    } else {
+        rest.find([',', '}'])
+    }
+    .ok_or_else(|| invalid_data(format!("cannot parse npy header {:?}", header)))?;
+    Ok(rest[..end].trim())
+}
+
+pub fn read_header<R: Read>(reader: &mut R) -> io::Result<NpyHeader> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic[..6] != MAGIC {
+        return Err(invalid_data("not a .npy file".to_string()));
+    }
+    let header_length = match magic[6] {
+        1 => {
+            let mut length = [0u8; 2];
+            reader.read_exact(&mut length)?;
+            u16::from_le_bytes(length) as usize
+        }
+        2 | 3 => {
+            let mut length = [0u8; 4];
+            reader.read_exact(&mut length)?;
+            u32::from_le_bytes(length) as usize
+        }
+        version => {
+            return Err(invalid_data(format!(
+                "unsupported .npy version {}",
+                version
+            )))
+        }
+    };
+    let mut header = vec![0u8; header_length];
+    reader.read_exact(&mut header)?;
+    let header = String::from_utf8_lossy(&header);
+
+    let descr = header_value(&header, "descr")?
+        .trim_matches('\'')
+        .to_string();
+    let fortran_order = header_value(&header, "fortran_order")? == "True";
+    let shape = header_value(&header, "shape")?
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(str::trim)
+        .filter(|dimension| !dimension.is_empty())
+        .map(|dimension| {
+            dimension
+                .parse()
+                .map_err(|_| invalid_data(format!("invalid npy shape {:?}", dimension)))
+        })
+        .collect::<io::Result<Vec<usize>>>()?;
+    Ok(NpyHeader {
+        descr,
+        fortran_order,
+        shape,
+    })
+}
+
+pub fn read_array<R: Read>(mut reader: R) -> io::Result<(Vec<usize>, Vec<f64>)> {
+    // Shape and row-major data of a float array, converted to f64
+    let header = read_header(&mut reader)?;
+    // The shape comes from the file, so a corrupt one must not overflow
+    let length = header
+        .shape
+        .iter()
+        .try_fold(1usize, |length, &dimension| length.checked_mul(dimension))
+        .ok_or_else(|| invalid_data(format!("npy shape {:?} is too large", header.shape)))?;
+    let (size, big_endian) = match header.descr.as_str() {
+        "<f8" | "=f8" => (8, false),
+        ">f8" => (8, true),
+        "<f4" | "=f4" => (4, false),
+        ">f4" => (4, true),
+        descr => {
+            return Err(invalid_data(format!(
+                "unsupported npy dtype {:?}, expected float32 or float64",
+                descr
+            )))
+        }
+    };
+    let n_bytes = length
+        .checked_mul(size)
+        .ok_or_else(|| invalid_data(format!("npy shape {:?} is too large", header.shape)))?;
+    // Only take memory for the bytes actually there
+    let mut bytes = vec![];
+    reader.take(n_bytes as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != n_bytes {
+        return Err(invalid_data(format!(
+            "npy data has {} bytes, shape {:?} needs {}",
+            bytes.len(),
+            header.shape,
+            n_bytes
+        )));
+    }
+    let mut data: Vec<f64> = bytes
+        .chunks_exact(size)
+        .map(|chunk| match (size, big_endian) {
+            (8, false) => f64::from_le_bytes(chunk.try_into().unwrap()),
+            (8, true) => f64::from_be_bytes(chunk.try_into().unwrap()),
+            (_, false) => f32::from_le_bytes(chunk.try_into().unwrap()) as f64,
+            (_, true) => f32::from_be_bytes(chunk.try_into().unwrap()) as f64,
+        })
+        .collect();
+
+    if header.fortran_order && header.shape.len() == 2 {
+        let (rows, columns) = (header.shape[0], header.shape[1]);
+        let column_major = data;
+        data = (0..rows * columns)
+            .map(|i| column_major[(i % columns) * rows + i / columns])
+            .collect();
+    } else if header.fortran_order && header.shape.len() > 2 {
+        return Err(invalid_data(
+            "Fortran ordered arrays with more than two dimensions are not supported".to_string(),
+        ));
+    }
+    Ok((header.shape, data))
+}
+
+pub fn read_points<const N: usize, R: Read>(reader: R) -> io::Result<Vec<[f64; N]>> {
+    // An (n, N) array as a list of points
+    let (shape, data) = read_array(reader)?;
+    if shape.len() != 2 || shape[1] != N {
+        return Err(invalid_data(format!(
+            "expected an array of shape (n, {}), found {:?}",
+            N, shape
+        )));
+    }
+    Ok(data
+        .chunks_exact(N)
+        .map(|chunk| chunk.try_into().unwrap())
+        .collect())
+}
+
+pub fn read_points_file<const N: usize, P: AsRef<Path>>(path: P) -> io::Result<Vec<[f64; N]>> {
+    read_points(BufReader::new(File::open(path)?))
+}
+
+impl DelaunayTree<3, 4> {
+    pub fn from_npy<R: Read>(reader: R) -> io::Result<Self> {
+        let points = read_points::<3, R>(reader)?;
+        let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
+        delaunay_tree.insert_points(&points);
+        Ok(delaunay_tree)
+    }
+}
+
+impl DelaunayTree<2, 3> {
+    pub fn from_npy<R: Read>(reader: R) -> io::Result<Self> {
+        let points = read_points::<2, R>(reader)?;
+        let mut delaunay_tree = DelaunayTree::<2, 3>::new(points.clone());
+        delaunay_tree.insert_points(&points);
+        Ok(delaunay_tree)
+    }
+}
+
+pub fn read_npz_array<R: Read + Seek>(
+    reader: R,
+    name: Option<&str>,
+) -> io::Result<(Vec<usize>, Vec<f64>)> {
+    // The array stored as name.npy, or the first array in the archive
+    let mut archive = ZipArchive::new(reader)?;
+    if archive.is_empty() {
+        return Err(invalid_data("empty .npz archive".to_string()));
+    }
+    let file = match name {
+        Some(name) => archive
+            .by_name(&format!("{}.npy", name))
+            .map_err(|_| invalid_data(format!(".npz archive has no array named {:?}", name)))?,
+        None => archive.by_index(0)?,
+    };
+    read_array(BufReader::new(file))
+}
+
+pub fn read_npz_points<const N: usize, R: Read + Seek>(
+    reader: R,
+    name: Option<&str>,
+) -> io::Result<Vec<[f64; N]>> {
+    let (shape, data) = read_npz_array(reader, name)?;
+    if shape.len() != 2 || shape[1] != N {
+        return Err(invalid_data(format!(
+            "expected an array of shape (n, {}), found {:?}",
+            N, shape
+        )));
+    }
+    Ok(data
+        .chunks_exact(N)
+        .map(|chunk| chunk.try_into().unwrap())
+        .collect())
+}
+
+pub trait NpyElement: Copy {
+    const DESCR: &'static str;
+    fn write_le<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+}
+
+impl NpyElement for f64 {
+    const DESCR: &'static str = "<f8";
+    fn write_le<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+impl NpyElement for f32 {
+    const DESCR: &'static str = "<f4";
+    fn write_le<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+impl NpyElement for i64 {
+    const DESCR: &'static str = "<i8";
+    fn write_le<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+pub fn write_array<T: NpyElement, W: Write>(
+    mut writer: W,
+    shape: &[usize],
+    data: &[T],
+) -> io::Result<()> {
+    assert_eq!(
+        shape.iter().product::<usize>(),
+        data.len(),
+        "shape {:?} does not match {} elements",
+        shape,
+        data.len()
+    );
+    let shape = match shape {
+        [length] => format!("({},)", length),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|dimension| dimension.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        T::DESCR,
+        shape
+    );
+    // Version 1 stores the header length in two bytes, the data starts on a 64 byte boundary
+    let version: u8 = if header.len() + 11 > u16::MAX as usize {
+        2
+    } else {
+        1
+    };
+    let prefix = if version == 1 { 10 } else { 12 };
+    while (prefix + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[version, 0])?;
+    if version == 1 {
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    } else {
+        writer.write_all(&(header.len() as u32).to_le_bytes())?;
+    }
+    writer.write_all(header.as_bytes())?;
+    for value in data {
+        value.write_le(&mut writer)?;
+    }
+    writer.flush()
+}
+
+#[derive(Debug, Clone)]
+pub struct SimplexArrays {
+    // Real simplices in increasing id order, as returned by DelaunayTree::real_simplices
+    pub n_simplices: usize,
+    pub dimension: usize,
+    // (n_simplices, N + 1) positively oriented point indices
+    pub simplices: Vec<i64>,
+    // (n_simplices, N + 1), the k-th neighbor is opposite to the k-th vertex, -1 on the hull
+    pub neighbors: Vec<i64>,
+    // (n_simplices, N) circumcenters
+    pub centers: Vec<f64>,
+    // (n_simplices,) circumradii
+    pub radii: Vec<f64>,
+}
+
+impl SimplexArrays {
+    pub fn new<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>) -> Self {
+        let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+        let ids = tree.real_simplices();
+        let index: HashMap<usize, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let mut output = SimplexArrays {
+            n_simplices: ids.len(),
+            dimension: N,
+            simplices: Vec::with_capacity(ids.len() * M),
+            neighbors: Vec::with_capacity(ids.len() * M),
+            centers: Vec::with_capacity(ids.len() * N),
+            radii: Vec::with_capacity(ids.len()),
+        };
+        for id in &ids {
+            for vertex in tree.oriented_vertices(*id) {
+                output.simplices.push((vertex - first) as i64);
+                output.neighbors.push(
+                    tree.opposite_neighbor(*id, vertex)
+                        .and_then(|neighbor| index.get(&neighbor))
+                        .map_or(-1, |k| *k as i64),
+                );
+            }
+            let simplex = tree.simplices.get(id).unwrap();
+            output.centers.extend(simplex.center);
+            output.radii.push(simplex.radius);
+        }
+        output
+    }
+
+    pub fn write_simplices<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_array(
+            writer,
+            &[self.n_simplices, self.dimension + 1],
+            &self.simplices,
+        )
+    }
+
+    pub fn write_neighbors<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_array(
+            writer,
+            &[self.n_simplices, self.dimension + 1],
+            &self.neighbors,
+        )
+    }
+
+    pub fn write_centers<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_array(writer, &[self.n_simplices, self.dimension], &self.centers)
+    }
+
+    pub fn write_radii<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_array(writer, &[self.n_simplices], &self.radii)
+    }
+
+    pub fn write_npz<W: Write + Seek>(&self, writer: W, compressed: bool) -> io::Result<W> {
+        // Archive with simplices.npy, neighbors.npy, centers.npy and radii.npy,
+        // like numpy.savez or numpy.savez_compressed
+        let method = if compressed {
+            CompressionMethod::Deflated
+        } else {
+            CompressionMethod::Stored
+        };
+        let options = FileOptions::default()
+            .compression_method(method)
+            .large_file(self.simplices.len() * 8 > u32::MAX as usize);
+        let mut archive = ZipWriter::new(writer);
+        archive.start_file("simplices.npy", options)?;
+        self.write_simplices(&mut archive)?;
+        archive.start_file("neighbors.npy", options)?;
+        self.write_neighbors(&mut archive)?;
+        archive.start_file("centers.npy", options)?;
+        self.write_centers(&mut archive)?;
+        archive.start_file("radii.npy", options)?;
+        self.write_radii(&mut archive)?;
+        Ok(archive.finish()?)
+    }
+}
+
+pub fn write_npz_file<const N: usize, const M: usize, P: AsRef<Path>>(
+    tree: &DelaunayTree<N, M>,
+    path: P,
+    compressed: bool,
+) -> io::Result<()> {
+    SimplexArrays::new(tree)
+        .write_npz(BufWriter::new(File::create(path)?), compressed)?
+        .flush()
+}
@@ -0,0 +1,333 @@
+use std::f64::consts::PI;
+
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+
+// Point sets for tests, benchmarks and examples. Every random generator takes a seed and
+// returns the same points for the same seed, and all of them return Vec<[f64; N]> that
+// goes straight into DelaunayTree::new and insert_points.
+
+// Candidates tried around every active point in Bridson's algorithm
+const BRIDSON_ATTEMPTS: usize = 30;
+
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    // Box-Muller
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+fn random_direction<const N: usize>(rng: &mut StdRng) -> [f64; N] {
+    // Normalized Gaussian vector, uniform on the sphere
+    loop {
+        let mut direction = [0.0; N];
+        for x in direction.iter_mut() {
+            *x = standard_normal(rng);
+        }
+        let norm = direction.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm > 1e-12 {
+            return direction.map(|x| x / norm);
+        }
+    }
+}
+
+pub fn uniform_box<const N: usize>(
+    n: usize,
+    lower: [f64; N],
+    upper: [f64; N],
+    seed: u64,
+) -> Vec<[f64; N]> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let dist = Uniform::from(0.0..1.0);
+    (0..n)
+        .map(|_| {
+            let mut point = [0.0; N];
+            for k in 0..N {
+                point[k] = lower[k] + (upper[k] - lower[k]) * dist.sample(&mut rng);
+            }
+            point
+        })
+        .collect()
+}
+
+pub fn uniform_ball<const N: usize>(
+    n: usize,
+    center: [f64; N],
+    radius: f64,
+    seed: u64,
+) -> Vec<[f64; N]> {
+    // Uniform direction, radius distributed as u^(1/N)
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n)
+        .map(|_| {
+            let direction = random_direction::<N>(&mut rng);
+            let r = radius * rng.gen::<f64>().powf(1.0 / N as f64);
+            let mut point = center;
+            for k in 0..N {
+                point[k] += r * direction[k];
+            }
+            point
+        })
+        .collect()
+}
+
+pub fn poisson_disk<const N: usize>(
+    lower: [f64; N],
+    upper: [f64; N],
+    radius: f64,
+    seed: u64,
+) -> Vec<[f64; N]> {
+    // Bridson's algorithm: points at least radius apart that fill the box, so that no
+    // point can be added. A background grid with cells of diagonal radius holds at most
+    // one point per cell.
+    assert!(
+        radius > 0.0,
+        "poisson_disk needs a positive radius, got {}",
+        radius
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let cell = radius / (N as f64).sqrt();
+    let mut shape = [0usize; N];
+    for k in 0..N {
+        shape[k] = (((upper[k] - lower[k]) / cell).ceil() as usize).max(1);
+    }
+    let mut grid: Vec<Option<usize>> = vec![None; shape.iter().product()];
+    let index = |point: &[f64; N]| {
+        let mut cell_index = [0usize; N];
+        for k in 0..N {
+            cell_index[k] = (((point[k] - lower[k]) / cell) as usize).min(shape[k] - 1);
+        }
+        cell_index
+    };
+    let flatten =
+        |cell_index: &[usize; N]| (0..N).fold(0, |flat, k| flat * shape[k] + cell_index[k]);
+    let fits = |point: &[f64; N], points: &[[f64; N]], grid: &[Option<usize>]| {
+        // Neighbors within radius are at most 2 cells away along every axis
+        let center = index(point);
+        let mut offset = [0usize; N];
+        loop {
+            let mut neighbor = [0usize; N];
+            let mut inside = true;
+            for k in 0..N {
+                let value = center[k] as i64 + offset[k] as i64 - 2;
+                if value < 0 || value >= shape[k] as i64 {
+                    inside = false;
+                    break;
+                }
+                neighbor[k] = value as usize;
+            }
+            if inside {
+                if let Some(other) = grid[flatten(&neighbor)] {
+                    let squared: f64 = (0..N).map(|k| (points[other][k] - point[k]).powi(2)).sum();
+                    if squared < radius * radius {
+                        return false;
+                    }
+                }
+            }
+            // Next offset in 0..5 along every axis
+            let mut k = 0;
+            while k < N && offset[k] == 4 {
+                offset[k] = 0;
+                k += 1;
+            }
+            if k == N {
+                return true;
+            }
+            offset[k] += 1;
+        }
+    };
+
+    let mut first = [0.0; N];
+    for k in 0..N {
+        first[k] = rng.gen_range(lower[k]..upper[k]);
+    }
+    let mut points = vec![first];
+    grid[flatten(&index(&first))] = Some(0);
+    let mut active = vec![0];
+    while !active.is_empty() {
+        let slot = rng.gen_range(0..active.len());
+        let origin = points[active[slot]];
+        let mut found = false;
+        for _ in 0..BRIDSON_ATTEMPTS {
+            // Uniform in the shell between radius and twice the radius
+            let direction = random_direction::<N>(&mut rng);
+            let u: f64 = rng.gen();
+            let r = radius * (1.0 + u * ((1 << N) as f64 - 1.0)).powf(1.0 / N as f64);
+            let mut candidate = origin;
+            for k in 0..N {
+                candidate[k] += r * direction[k];
+            }
+            if (0..N).any(|k| candidate[k] < lower[k] || candidate[k] >= upper[k]) {
+                continue;
+            }
+            if fits(&candidate, &points, &grid) {
+                grid[flatten(&index(&candidate))] = Some(points.len());
+                active.push(points.len());
+                points.push(candidate);
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            active.swap_remove(slot);
+        }
+    }
+    points
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaussianCluster<const N: usize> {
+    pub center: [f64; N],
+    // Standard deviation along every axis
+    pub sigma: f64,
+    // Relative number of points, the weights need not sum to 1
+    pub weight: f64,
+}
+
+pub fn gaussian_mixture<const N: usize>(
+    n: usize,
+    clusters: &[GaussianCluster<N>],
+    seed: u64,
+) -> Vec<[f64; N]> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let total: f64 = clusters.iter().map(|cluster| cluster.weight).sum();
+    assert!(
+        !clusters.is_empty() && total > 0.0,
+        "gaussian_mixture needs at least one cluster and a positive total weight"
+    );
+    (0..n)
+        .map(|_| {
+            let mut u = rng.gen::<f64>() * total;
+            let cluster = clusters
+                .iter()
+                .find(|cluster| {
+                    u -= cluster.weight;
+                    u < 0.0
+                })
+                .unwrap_or(&clusters[clusters.len() - 1]);
+            let mut point = cluster.center;
+            for x in point.iter_mut() {
+                *x += cluster.sigma * standard_normal(&mut rng);
+            }
+            point
+        })
+        .collect()
+}
+
+fn grid_indices<const N: usize>(counts: [usize; N]) -> Vec<[usize; N]> {
+    // All multi-indices below counts, the last axis varying fastest
+    let mut indices = vec![[0usize; N]];
+    for k in 0..N {
+        indices = indices
+            .into_iter()
+            .flat_map(|index| {
+                (0..counts[k]).map(move |i| {
+                    let mut index = index;
+                    index[k] = i;
+                    index
+                })
+            })
+            .collect();
+    }
+    indices
+}
+
+pub fn jittered_grid<const N: usize>(
+    counts: [usize; N],
+    lower: [f64; N],
+    upper: [f64; N],
+    jitter: f64,
+    seed: u64,
+) -> Vec<[f64; N]> {
+    // One point per grid cell, moved from the cell center by up to jitter / 2 cell sizes
+    // along every axis. A jitter of 1 is stratified sampling, 0 the cell centers.
+    let mut rng = StdRng::seed_from_u64(seed);
+    let dist = Uniform::from(-0.5..0.5);
+    grid_indices(counts)
+        .into_iter()
+        .map(|index| {
+            let mut point = [0.0; N];
+            for k in 0..N {
+                let size = (upper[k] - lower[k]) / counts[k] as f64;
+                point[k] =
+                    lower[k] + size * (index[k] as f64 + 0.5 + jitter * dist.sample(&mut rng));
+            }
+            point
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lattice {
+    Cubic,
+    // Face-centered cubic, the cube corners and the centers of its 2D faces
+    FaceCentered,
+    // Body-centered cubic, the cube corners and the cube center
+    BodyCentered,
+    // Triangular in 2D, triangular layers stacked on top of each other in 3D
+    Hexagonal,
+}
+
+impl Lattice {
+    fn basis<const N: usize>(self) -> (Vec<[f64; N]>, [f64; N]) {
+        // Points of the unit cell and its size
+        let mut basis = vec![[0.0; N]];
+        let mut size = [1.0; N];
+        match self {
+            Lattice::Cubic => {}
+            Lattice::BodyCentered => basis.push([0.5; N]),
+            Lattice::FaceCentered => {
+                for i in 0..N {
+                    for j in i + 1..N {
+                        let mut point = [0.0; N];
+                        point[i] = 0.5;
+                        point[j] = 0.5;
+                        basis.push(point);
+                    }
+                }
+            }
+            Lattice::Hexagonal => {
+                let mut point = [0.0; N];
+                point[0] = 0.5;
+                point[1] = 0.5;
+                basis.push(point);
+                size[1] = 3f64.sqrt();
+            }
+        }
+        (basis, size)
+    }
+}
+
+pub fn lattice<const N: usize>(
+    kind: Lattice,
+    cells: [usize; N],
+    spacing: f64,
+    origin: [f64; N],
+) -> Vec<[f64; N]> {
+    // Unit cells repeated cells[k] times along every axis, spacing being the distance
+    // between neighboring cube corners or the side of the triangles. These points are
+    // deliberately degenerate, with many on the same circle or sphere; see perturb.
+    let (basis, size) = kind.basis::<N>();
+    grid_indices(cells)
+        .into_iter()
+        .flat_map(|index| {
+            basis.iter().map(move |offset| {
+                let mut point = origin;
+                for k in 0..N {
+                    point[k] += spacing * size[k] * (index[k] as f64 + offset[k]);
+                }
+                point
+            })
+        })
+        .collect()
+}
+
+pub fn perturb<const N: usize>(points: &[[f64; N]], amplitude: f64, seed: u64) -> Vec<[f64; N]> {
+    // Moves every coordinate by up to amplitude, to break the symmetries of a lattice
+    let mut rng = StdRng::seed_from_u64(seed);
+    let dist = Uniform::from(-amplitude..=amplitude);
+    points
+        .iter()
+        .map(|point| point.map(|x| x + dist.sample(&mut rng)))
+        .collect()
+}
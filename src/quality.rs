@@ -0,0 +1,321 @@
+use std::fmt;
+
+use nalgebra::DMatrix;
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+use crate::geometry::simplex_volume;
+
+// Quality measures of the real simplices. All of them derive from the gradients of the
+// barycentric coordinates: the height over the facet opposite to vertex k is
+// 1 / |grad l_k|, the inradius is 1 / sum_k |grad l_k|, and the dihedral angle between the
+// facets opposite to k and l has cosine -grad l_k . grad l_l / (|grad l_k| |grad l_l|).
+// In 2D the dihedral angles are the angles of the triangle.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimplexQuality {
+    pub simplex: usize,
+    // Area in 2D
+    pub volume: f64,
+    // Circumradius over shortest edge, sqrt(6) / 4 for the regular tetrahedron and
+    // 1 / sqrt(3) for the equilateral triangle
+    pub radius_edge_ratio: f64,
+    // N times the inradius over the circumradius, 1 for regular simplices, 0 when flat
+    pub radius_ratio: f64,
+    // In degrees, 70.53 for the regular tetrahedron and 60 for the equilateral triangle
+    pub min_dihedral_angle: f64,
+    // Longest edge over smallest height, scaled to 1 for regular simplices
+    pub aspect_ratio: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityMeasure {
+    Volume,
+    RadiusEdgeRatio,
+    RadiusRatio,
+    MinDihedralAngle,
+    AspectRatio,
+}
+
+impl QualityMeasure {
+    pub const ALL: [QualityMeasure; 5] = [
+        QualityMeasure::Volume,
+        QualityMeasure::RadiusEdgeRatio,
+        QualityMeasure::RadiusRatio,
+        QualityMeasure::MinDihedralAngle,
+        QualityMeasure::AspectRatio,
+    ];
+
+    pub fn value(&self, quality: &SimplexQuality) -> f64 {
+        match self {
+            QualityMeasure::Volume => quality.volume,
+            QualityMeasure::RadiusEdgeRatio => quality.radius_edge_ratio,
+            QualityMeasure::RadiusRatio => quality.radius_ratio,
+            QualityMeasure::MinDihedralAngle => quality.min_dihedral_angle,
+            QualityMeasure::AspectRatio => quality.aspect_ratio,
+        }
+    }
+
+    pub fn higher_is_better(&self) -> bool {
+        matches!(
+            self,
+            QualityMeasure::Volume | QualityMeasure::RadiusRatio | QualityMeasure::MinDihedralAngle
+        )
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QualityMeasure::Volume => "volume",
+            QualityMeasure::RadiusEdgeRatio => "radius-edge ratio",
+            QualityMeasure::RadiusRatio => "radius ratio",
+            QualityMeasure::MinDihedralAngle => "minimum dihedral angle",
+            QualityMeasure::AspectRatio => "aspect ratio",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    // bins + 1 increasing bin edges, the last bin includes its upper edge
+    pub edges: Vec<f64>,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn new(values: &[f64], bins: usize, range: Option<(f64, f64)>) -> Self {
+        // Values outside the range are not counted. The default range spans the values.
+        let (lower, upper) = range.unwrap_or_else(|| {
+            values.iter().fold(
+                (f64::INFINITY, f64::NEG_INFINITY),
+                |(lower, upper), value| (lower.min(*value), upper.max(*value)),
+            )
+        });
+        let (lower, upper) = if lower <= upper {
+            (lower, upper)
+        } else {
+            (0.0, 0.0)
+        };
+        let width = (upper - lower) / bins as f64;
+        let edges: Vec<f64> = (0..=bins).map(|i| lower + i as f64 * width).collect();
+        let mut counts = vec![0; bins];
+        for value in values {
+            if bins == 0 || !(lower..=upper).contains(value) {
+                continue;
+            }
+            let bin = if width > 0.0 {
+                (((value - lower) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            counts[bin] += 1;
+        }
+        Histogram { edges, counts }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualitySummary {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std: f64,
+    pub median: f64,
+}
+
+impl QualitySummary {
+    pub fn new(values: &[f64]) -> Self {
+        let count = values.len();
+        if count == 0 {
+            return QualitySummary {
+                count,
+                min: f64::NAN,
+                max: f64::NAN,
+                mean: f64::NAN,
+                std: f64::NAN,
+                median: f64::NAN,
+            };
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable_by(f64::total_cmp);
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let variance = values.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / count as f64;
+        let median = if count % 2 == 1 {
+            sorted[count / 2]
+        } else {
+            (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+        };
+        QualitySummary {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean,
+            std: variance.sqrt(),
+            median,
+        }
+    }
+}
+
+// Summary of every measure over a set of simplices
+#[derive(Debug, Clone)]
+pub struct QualityReport {
+    pub summaries: Vec<(QualityMeasure, QualitySummary)>,
+}
+
+impl QualityReport {
+    pub fn new(qualities: &[SimplexQuality]) -> Self {
+        let summaries = QualityMeasure::ALL
+            .iter()
+            .map(|measure| (*measure, QualitySummary::new(&values(qualities, *measure))))
+            .collect();
+        QualityReport { summaries }
+    }
+}
+
+impl fmt::Display for QualityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>12} {:>12} {:>12} {:>12}",
+            "measure", "min", "median", "mean", "std", "max"
+        )?;
+        for (measure, summary) in &self.summaries {
+            writeln!(
+                f,
+                "{:<24} {:>12.6e} {:>12.6e} {:>12.6e} {:>12.6e} {:>12.6e}",
+                measure.name(),
+                summary.min,
+                summary.median,
+                summary.mean,
+                summary.std,
+                summary.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn values(qualities: &[SimplexQuality], measure: QualityMeasure) -> Vec<f64> {
+    qualities
+        .iter()
+        .map(|quality| measure.value(quality))
+        .collect()
+}
+
+pub fn histogram(
+    qualities: &[SimplexQuality],
+    measure: QualityMeasure,
+    bins: usize,
+    range: Option<(f64, f64)>,
+) -> Histogram {
+    Histogram::new(&values(qualities, measure), bins, range)
+}
+
+fn norm<const N: usize>(vector: &[f64; N]) -> f64 {
+    vector.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+pub fn simplex_quality<const N: usize, const M: usize>(
+    coordinates: [[f64; N]; M],
+    radius: f64,
+) -> SimplexQuality {
+    // Measures of a simplex given its vertices and circumradius, with simplex set to 0
+    let mut shortest = f64::INFINITY;
+    let mut longest: f64 = 0.0;
+    for i in 0..M {
+        for j in i + 1..M {
+            let mut edge = [0.0; N];
+            for k in 0..N {
+                edge[k] = coordinates[j][k] - coordinates[i][k];
+            }
+            shortest = shortest.min(norm(&edge));
+            longest = longest.max(norm(&edge));
+        }
+    }
+    let volume = simplex_volume(coordinates);
+
+    // Gradient of barycentric coordinate i > 0 is column i - 1 of the inverse edge matrix
+    let edges = DMatrix::from_fn(N, N, |i, k| coordinates[i + 1][k] - coordinates[0][k]);
+    let Some(inverse) = edges.try_inverse().filter(|_| volume > 0.0) else {
+        return SimplexQuality {
+            simplex: 0,
+            volume,
+            radius_edge_ratio: radius / shortest,
+            radius_ratio: 0.0,
+            min_dihedral_angle: 0.0,
+            aspect_ratio: f64::INFINITY,
+        };
+    };
+    let mut gradients = [[0.0; N]; M];
+    for i in 1..M {
+        for k in 0..N {
+            gradients[i][k] = inverse[(k, i - 1)];
+            gradients[0][k] -= inverse[(k, i - 1)];
+        }
+    }
+    let lengths: Vec<f64> = gradients.iter().map(norm).collect();
+
+    let mut min_angle = f64::INFINITY;
+    for i in 0..M {
+        for j in i + 1..M {
+            let dot: f64 = (0..N).map(|k| gradients[i][k] * gradients[j][k]).sum();
+            let cosine = (-dot / (lengths[i] * lengths[j])).clamp(-1.0, 1.0);
+            min_angle = min_angle.min(cosine.acos().to_degrees());
+        }
+    }
+    let inradius = 1.0 / lengths.iter().sum::<f64>();
+    let min_height = 1.0 / lengths.iter().cloned().fold(0.0, f64::max);
+    // Height over edge length of the regular simplex, sqrt((N + 1) / (2 N))
+    let regular = ((N + 1) as f64 / (2 * N) as f64).sqrt();
+    SimplexQuality {
+        simplex: 0,
+        volume,
+        radius_edge_ratio: radius / shortest,
+        radius_ratio: N as f64 * inradius / radius,
+        min_dihedral_angle: min_angle,
+        aspect_ratio: regular * longest / min_height,
+    }
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    pub fn simplex_quality(&self, simplex_id: usize) -> SimplexQuality {
+        let simplex = self.simplices.get(&simplex_id).unwrap();
+        SimplexQuality {
+            simplex: simplex_id,
+            ..simplex_quality(self.simplex_coordinates(&simplex), simplex.radius)
+        }
+    }
+
+    pub fn quality(&self) -> Vec<SimplexQuality> {
+        // Measures of all real simplices, sorted by simplex id
+        self.real_simplices()
+            .into_par_iter()
+            .map(|id| self.simplex_quality(id))
+            .collect()
+    }
+
+    pub fn worst_simplices(&self, measure: QualityMeasure, count: usize) -> Vec<SimplexQuality> {
+        // The count real simplices with the worst value of measure, worst first
+        let mut qualities = self.quality();
+        qualities.par_sort_by(|a, b| {
+            let order = measure.value(a).total_cmp(&measure.value(b));
+            let order = if measure.higher_is_better() {
+                order
+            } else {
+                order.reverse()
+            };
+            order.then_with(|| a.simplex.cmp(&b.simplex))
+        });
+        qualities.truncate(count);
+        qualities
+    }
+
+    pub fn slivers(&self, min_dihedral_angle: f64) -> Vec<usize> {
+        // Ids of the real simplices with a dihedral angle below min_dihedral_angle degrees
+        self.quality()
+            .into_iter()
+            .filter(|quality| quality.min_dihedral_angle < min_dihedral_angle)
+            .map(|quality| quality.simplex)
+            .collect()
+    }
+}
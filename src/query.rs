@@ -0,0 +1,324 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+use kiddo::SquaredEuclidean;
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+use crate::geometry::signed_volume;
+
+// Nearest-vertex queries and point location. Nearest-vertex results only contain input
+// points, given by their index in insertion order (vertex id minus N_SUPER_VERTICES),
+// with their Euclidean distance to the query point and sorted by increasing distance.
+
+// Barycentric coordinates above -BARYCENTRIC_TOLERANCE count as inside, so that points
+// on a shared facet are located in one of the simplices
+const BARYCENTRIC_TOLERANCE: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryMethod {
+    // Range and nearest-neighbor queries on the kd-tree
+    #[default]
+    KdTree,
+    // Greedy walk on the Delaunay graph to the nearest vertex, followed by a best-first
+    // expansion. Points inside any ball are connected in the Delaunay graph, so the
+    // expansion visits vertices in order of distance.
+    Walk,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Location<const M: usize> {
+    // Barycentric coordinates are given in the order of Simplex::vertices and sum to 1
+    Inside {
+        simplex: usize,
+        barycentric: [f64; M],
+    },
+    // The point is not covered by any simplex between input points
+    OutsideHull,
+}
+
+// Vertex id ordered by decreasing distance, so that BinaryHeap pops the closest one
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    distance: f64,
+    id: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+fn squared_distance<const N: usize>(a: &[f64; N], b: &[f64; N]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    pub fn with_query_method(mut self, query_method: QueryMethod) -> Self {
+        self.query_method = query_method;
+        self
+    }
+
+    pub fn nearest_vertex(&self, point: [f64; N]) -> Option<(usize, f64)> {
+        self.k_nearest(point, 1).into_iter().next()
+    }
+
+    pub fn k_nearest(&self, point: [f64; N], k: usize) -> Vec<(usize, f64)> {
+        match self.query_method {
+            QueryMethod::KdTree => {
+                // The super-simplex vertices can be among the nearest ones. kiddo allocates
+                // room for all the results up front, so k cannot exceed the input points.
+                let k = k.min(self.vertices.len() - Self::N_SUPER_VERTICES);
+                let found = self
+                    .kdtree
+                    .nearest_n::<SquaredEuclidean>(&point, k + Self::N_SUPER_VERTICES);
+                self.real_results(
+                    found
+                        .into_iter()
+                        .map(|neighbour| (neighbour.item as usize, neighbour.distance)),
+                    k,
+                )
+            }
+            QueryMethod::Walk => self.expand(point, k, f64::INFINITY),
+        }
+    }
+
+    pub fn within_radius(&self, point: [f64; N], radius: f64) -> Vec<(usize, f64)> {
+        match self.query_method {
+            QueryMethod::KdTree => {
+                let found = self
+                    .kdtree
+                    .within::<SquaredEuclidean>(&point, radius * radius);
+                self.real_results(
+                    found
+                        .into_iter()
+                        .map(|neighbour| (neighbour.item as usize, neighbour.distance)),
+                    usize::MAX,
+                )
+            }
+            QueryMethod::Walk => self.expand(point, usize::MAX, radius * radius),
+        }
+    }
+
+    fn real_results(
+        &self,
+        found: impl Iterator<Item = (usize, f64)>,
+        k: usize,
+    ) -> Vec<(usize, f64)> {
+        // Found vertex ids and squared distances, sorted, to input indices and distances
+        found
+            .filter(|(id, _)| !Self::is_super_vertex(*id))
+            .take(k)
+            .map(|(id, distance)| (id - Self::N_SUPER_VERTICES, distance.sqrt()))
+            .collect()
+    }
+
+    fn walk(&self, point: [f64; N]) -> usize {
+        // Greedy walk from the first input point, or the super-simplex if there is none,
+        // to the vertex closest to point. In a Delaunay triangulation a vertex that is
+        // not the closest one always has a closer neighbor.
+        let mut current = if self.vertices.len() > Self::N_SUPER_VERTICES {
+            Self::N_SUPER_VERTICES
+        } else {
+            0
+        };
+        let mut distance =
+            squared_distance(&point, &self.vertices.get(&current).unwrap().coordinates);
+        loop {
+            let closer = self
+                .vertex_link(current, false)
+                .into_iter()
+                .map(|id| {
+                    (
+                        squared_distance(&point, &self.vertices.get(&id).unwrap().coordinates),
+                        id,
+                    )
+                })
+                .filter(|(other, _)| *other < distance)
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            match closer {
+                Some((other, id)) => {
+                    distance = other;
+                    current = id;
+                }
+                None => return current,
+            }
+        }
+    }
+
+    fn expand(&self, point: [f64; N], k: usize, squared_radius: f64) -> Vec<(usize, f64)> {
+        // Best-first search from the closest vertex, stopping after k input points or
+        // once the next vertex is further than the radius
+        let mut output = vec![];
+        if k == 0 {
+            return output;
+        }
+        let start = self.walk(point);
+        let mut visited = HashSet::from([start]);
+        let mut heap = BinaryHeap::from([Candidate {
+            distance: squared_distance(&point, &self.vertices.get(&start).unwrap().coordinates),
+            id: start,
+        }]);
+        while let Some(Candidate { distance, id }) = heap.pop() {
+            if distance > squared_radius {
+                break;
+            }
+            if !Self::is_super_vertex(id) {
+                output.push((id - Self::N_SUPER_VERTICES, distance.sqrt()));
+                if output.len() == k {
+                    break;
+                }
+            }
+            for neighbor in self.vertex_link(id, false) {
+                if visited.insert(neighbor) {
+                    heap.push(Candidate {
+                        distance: squared_distance(
+                            &point,
+                            &self.vertices.get(&neighbor).unwrap().coordinates,
+                        ),
+                        id: neighbor,
+                    });
+                }
+            }
+        }
+        output
+    }
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    pub fn barycentric_coordinates(&self, simplex_id: usize, point: [f64; N]) -> [f64; M] {
+        // Ratios of the volumes with one vertex replaced by point to the simplex volume
+        let simplex = self.simplices.get(&simplex_id).unwrap();
+        let coordinates = self.simplex_coordinates(&simplex);
+        let volume = signed_volume(coordinates);
+        let mut barycentric = [0.0; M];
+        for (k, value) in barycentric.iter_mut().enumerate() {
+            let mut replaced = coordinates;
+            replaced[k] = point;
+            *value = signed_volume(replaced) / volume;
+        }
+        barycentric
+    }
+
+    pub fn find_containing_simplex(&self, point: [f64; N]) -> Location<M> {
+        // Visibility walk from a simplex around the nearest vertex, stepping through the
+        // facet opposite to the most negative barycentric coordinate. The walk cannot
+        // cycle in a Delaunay triangulation.
+        let nearest = self.kdtree.nearest_one::<SquaredEuclidean>(&point).item as usize;
+        let start = self
+            .vertices
+            .get(&nearest)
+            .unwrap()
+            .simplex
+            .iter()
+            .copied()
+            .find(|id| !Self::is_ghost_simplex(&self.simplices.get(id).unwrap()));
+        let Some(mut current) = start else {
+            return Location::OutsideHull;
+        };
+        for _ in 0..self.simplices.len() {
+            let barycentric = self.barycentric_coordinates(current, point);
+            let (k, smallest) = barycentric
+                .iter()
+                .copied()
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            if smallest >= -BARYCENTRIC_TOLERANCE {
+                let simplex = self.simplices.get(&current).unwrap();
+                return if Self::is_real_simplex(&simplex) {
+                    Location::Inside {
+                        simplex: current,
+                        barycentric,
+                    }
+                } else {
+                    self.locate_on_hull(&simplex.vertices, &barycentric, point)
+                };
+            }
+            let vertex = self.simplices.get(&current).unwrap().vertices[k];
+            match self.opposite_neighbor(current, vertex) {
+                // Ghost simplices lie outside the super-simplex
+                Some(next) if !Self::is_ghost_simplex(&self.simplices.get(&next).unwrap()) => {
+                    current = next
+                }
+                _ => return Location::OutsideHull,
+            }
+        }
+        // Rounding can make the walk cycle on nearly degenerate input, scan instead
+        log::warn!("Point location did not terminate for {:?}", point);
+        self.locate_by_scan(point)
+    }
+
+    fn locate_by_scan(&self, point: [f64; N]) -> Location<M> {
+        // Checks every real simplex in id order
+        self.real_simplices()
+            .into_iter()
+            .find_map(|simplex| {
+                let barycentric = self.barycentric_coordinates(simplex, point);
+                barycentric
+                    .iter()
+                    .all(|&value| value >= -BARYCENTRIC_TOLERANCE)
+                    .then_some(Location::Inside {
+                        simplex,
+                        barycentric,
+                    })
+            })
+            .unwrap_or(Location::OutsideHull)
+    }
+
+    fn locate_on_hull(
+        &self,
+        vertices: &[usize; M],
+        barycentric: &[f64; M],
+        point: [f64; N],
+    ) -> Location<M> {
+        // A point in a simplex touching the super-simplex is still inside the hull if it
+        // lies on the face spanned by the input points, then a real simplex around one of
+        // these points contains it too
+        let on_face = vertices
+            .iter()
+            .zip(barycentric.iter())
+            .all(|(&id, &value)| !Self::is_super_vertex(id) || value <= BARYCENTRIC_TOLERANCE);
+        if !on_face {
+            return Location::OutsideHull;
+        }
+        for &id in vertices.iter().filter(|&&id| !Self::is_super_vertex(id)) {
+            let star = self.vertices.get(&id).unwrap().simplex.clone();
+            for simplex in star {
+                if !Self::is_real_simplex(&self.simplices.get(&simplex).unwrap()) {
+                    continue;
+                }
+                let barycentric = self.barycentric_coordinates(simplex, point);
+                if barycentric
+                    .iter()
+                    .all(|&value| value >= -BARYCENTRIC_TOLERANCE)
+                {
+                    return Location::Inside {
+                        simplex,
+                        barycentric,
+                    };
+                }
+            }
+        }
+        Location::OutsideHull
+    }
+
+    pub fn find_containing_simplices(&self, points: &[[f64; N]]) -> Vec<Location<M>> {
+        points
+            .par_iter()
+            .map(|point| self.find_containing_simplex(*point))
+            .collect()
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use kiddo::{KdTree, SquaredEuclidean};
+use rayon::prelude::*;
+
+use crate::delaunay_tree::{DelaunayTree, TreeUpdate};
+use crate::geometry::{face_circumcenter, in_sphere};
+use crate::query::Location;
+use crate::scheduler::{make_queue, schedule_batches};
+
+// Delaunay refinement of the convex hull of the input points: Ruppert/Chew in 2D and
+// Shewchuk in 3D. The segments are the hull edges, in 3D only those on a ridge of the
+// hull, and in 3D the hull triangles are the subfacets. Every round first splits the
+// boundary elements encroached by a vertex, i.e. with a vertex strictly inside their
+// diametral sphere. Once there are none, bad simplices get their circumcenter inserted,
+// unless it would encroach on the boundary, in which case the encroached element is
+// split instead. Segments are split at their midpoint, subfacets at their circumcenter,
+// or at the midpoint of a segment that circumcenter encroaches on. Steiner points are
+// appended after the input points. Termination relies on the input having no points
+// much closer to the hull than to each other and no nearly coplanar hull faces.
+
+// One minus the cosine of the largest angle between the normals of two hull triangles
+// that are considered coplanar
+const COPLANAR_TOLERANCE: f64 = 1e-10;
+
+// Largest allowed circumradius at a point
+pub type SizeFunction = Arc<dyn Fn(&[f64]) -> f64 + Send + Sync>;
+
+#[derive(Clone)]
+pub struct RefinementOptions {
+    // Simplices with a larger circumradius over shortest edge are refined. Termination
+    // is guaranteed above sqrt(2) in 2D and above 2 in 3D.
+    pub radius_edge_bound: f64,
+    // Simplices with a circumradius above the size at their centroid are refined
+    pub size: Option<SizeFunction>,
+    // Stop after inserting this many points
+    pub max_points: usize,
+    // Insert a conflict-free batch of points in parallel every round, found with the
+    // insertion scheduler, rather than all points of the round one by one
+    pub batch: bool,
+}
+
+impl Default for RefinementOptions {
+    fn default() -> Self {
+        RefinementOptions {
+            radius_edge_bound: 2.0,
+            size: None,
+            max_points: 1_000_000,
+            batch: false,
+        }
+    }
+}
+
+impl fmt::Debug for RefinementOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RefinementOptions")
+            .field("radius_edge_bound", &self.radius_edge_bound)
+            .field("size", &self.size.as_ref().map(|_| "Fn"))
+            .field("max_points", &self.max_points)
+            .field("batch", &self.batch)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RefinementReport {
+    pub points_inserted: usize,
+    pub rounds: usize,
+    // Bad simplices left, non-zero if max_points was reached, if a circumcenter could not
+    // be placed inside the hull or if the input has features close to the rounding error
+    pub bad_simplices: usize,
+}
+
+// A segment or subfacet on the hull with its diametral sphere
+#[derive(Debug, Clone)]
+struct BoundaryElement<const N: usize> {
+    vertices: Vec<usize>,
+    center: [f64; N],
+    radius: f64,
+}
+
+// Boundary elements indexed by the centers of their diametral spheres
+struct Boundary<const N: usize> {
+    elements: Vec<BoundaryElement<N>>,
+    centers: KdTree<f64, N>,
+    max_radius: f64,
+}
+
+impl<const N: usize> Boundary<N> {
+    fn encroached_by(&self, point: [f64; N]) -> Vec<usize> {
+        // Elements whose diametral sphere strictly contains point, segments first
+        let mut found: Vec<usize> = self
+            .centers
+            .within_unsorted::<SquaredEuclidean>(&point, self.max_radius * self.max_radius)
+            .into_iter()
+            .map(|neighbour| neighbour.item as usize)
+            .filter(|&i| in_sphere(point, self.elements[i].center, self.elements[i].radius))
+            .collect();
+        found.sort_unstable_by_key(|&i| (self.elements[i].vertices.len(), i));
+        found
+    }
+}
+
+// A point to insert and the simplex that asked for it, which must still exist when
+// points are inserted one by one
+type Candidate<const N: usize> = ([f64; N], usize);
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    fn diametral_element(&self, vertices: Vec<usize>) -> BoundaryElement<N> {
+        let points: Vec<[f64; N]> = vertices
+            .iter()
+            .map(|id| self.vertices.get(id).unwrap().coordinates)
+            .collect();
+        let center = face_circumcenter(&points);
+        let radius = points[0]
+            .iter()
+            .zip(center.iter())
+            .map(|(x, c)| (x - c) * (x - c))
+            .sum::<f64>()
+            .sqrt();
+        BoundaryElement {
+            vertices,
+            center,
+            radius,
+        }
+    }
+
+    fn hull_normal(&self, facet: &[usize; N]) -> [f64; N] {
+        // Unit normal of a hull triangle in 3D
+        let points = facet.map(|id| self.vertices.get(&id).unwrap().coordinates);
+        let u: Vec<f64> = (0..N).map(|k| points[1][k] - points[0][k]).collect();
+        let v: Vec<f64> = (0..N).map(|k| points[2][k] - points[0][k]).collect();
+        let mut normal = [0.0; N];
+        for k in 0..N {
+            normal[k] = u[(k + 1) % 3] * v[(k + 2) % 3] - u[(k + 2) % 3] * v[(k + 1) % 3];
+        }
+        let length = normal.iter().map(|x| x * x).sum::<f64>().sqrt();
+        normal.map(|x| x / length)
+    }
+
+    fn boundary(&self) -> Boundary<N> {
+        // Hull edges in 2D. In 3D the hull triangles, and the hull edges between two of
+        // them that are not coplanar, so that splits inside a planar face of the hull do
+        // not create segments.
+        let facets = self.hull_facets();
+        let mut keys: Vec<Vec<usize>> = vec![];
+        if N == 2 {
+            keys = facets.iter().map(|facet| facet.to_vec()).collect();
+        } else {
+            let mut normals: HashMap<(usize, usize), Vec<[f64; N]>> = HashMap::new();
+            for facet in &facets {
+                let normal = self.hull_normal(facet);
+                for i in 0..N {
+                    let (a, b) = (facet[i], facet[(i + 1) % N]);
+                    normals
+                        .entry((a.min(b), a.max(b)))
+                        .or_default()
+                        .push(normal);
+                }
+                let mut key = facet.to_vec();
+                key.sort_unstable();
+                keys.push(key);
+            }
+            for ((a, b), normals) in normals {
+                let coplanar = normals.len() == 2 && {
+                    let cosine: f64 = (0..N).map(|k| normals[0][k] * normals[1][k]).sum();
+                    cosine > 1.0 - COPLANAR_TOLERANCE
+                };
+                if !coplanar {
+                    keys.push(vec![a, b]);
+                }
+            }
+        }
+        for key in keys.iter_mut() {
+            key.sort_unstable();
+        }
+        keys.sort_unstable();
+        let elements: Vec<BoundaryElement<N>> = keys
+            .into_par_iter()
+            .map(|vertices| self.diametral_element(vertices))
+            .collect();
+        let mut centers = KdTree::new();
+        for (i, element) in elements.iter().enumerate() {
+            centers.add(&element.center, i as u64);
+        }
+        let max_radius = elements
+            .iter()
+            .map(|element| element.radius)
+            .fold(0.0, f64::max);
+        Boundary {
+            elements,
+            centers,
+            max_radius,
+        }
+    }
+
+    fn incident_simplices(&self, vertices: &[usize]) -> Vec<usize> {
+        // Real simplices containing all the given vertices
+        self.vertices
+            .get(&vertices[0])
+            .unwrap()
+            .simplex
+            .iter()
+            .copied()
+            .filter(|id| {
+                let simplex = self.simplices.get(id).unwrap();
+                Self::is_real_simplex(&simplex)
+                    && vertices.iter().all(|v| simplex.vertices.contains(v))
+            })
+            .collect()
+    }
+
+    fn is_encroached(&self, element: &BoundaryElement<N>) -> Option<usize> {
+        // A vertex of an incident simplex inside the diametral sphere, as in a Delaunay
+        // triangulation any encroaching vertex implies one of these does. Returns that
+        // simplex.
+        self.incident_simplices(&element.vertices)
+            .into_iter()
+            .find(|id| {
+                self.simplices
+                    .get(id)
+                    .unwrap()
+                    .vertices
+                    .iter()
+                    .filter(|v| !element.vertices.contains(v))
+                    .any(|v| {
+                        let coordinates = self.vertices.get(v).unwrap().coordinates;
+                        in_sphere(coordinates, element.center, element.radius)
+                    })
+            })
+    }
+
+    fn split_point(&self, boundary: &Boundary<N>, element: usize) -> [f64; N] {
+        // Midpoint of a segment. For a subfacet its circumcenter, unless that encroaches on
+        // a segment, which is split instead.
+        let element = &boundary.elements[element];
+        if element.vertices.len() == 2 {
+            return element.center;
+        }
+        boundary
+            .encroached_by(element.center)
+            .into_iter()
+            .find(|&i| boundary.elements[i].vertices.len() == 2)
+            .map_or(element.center, |i| boundary.elements[i].center)
+    }
+
+    fn is_bad(&self, simplex_id: usize, options: &RefinementOptions) -> bool {
+        let simplex = self.simplices.get(&simplex_id).unwrap();
+        let coordinates = self.simplex_coordinates(&simplex);
+        let mut shortest = f64::INFINITY;
+        for i in 0..M {
+            for j in i + 1..M {
+                let length = coordinates[i]
+                    .iter()
+                    .zip(coordinates[j].iter())
+                    .map(|(x, y)| (x - y) * (x - y))
+                    .sum::<f64>()
+                    .sqrt();
+                shortest = shortest.min(length);
+            }
+        }
+        if simplex.radius > options.radius_edge_bound * shortest {
+            return true;
+        }
+        match &options.size {
+            Some(size) => {
+                let mut centroid = [0.0; N];
+                for point in &coordinates {
+                    for k in 0..N {
+                        centroid[k] += point[k] / M as f64;
+                    }
+                }
+                simplex.radius > size(&centroid)
+            }
+            None => false,
+        }
+    }
+
+    fn bad_simplices(&self, options: &RefinementOptions) -> Vec<usize> {
+        self.real_simplices()
+            .into_par_iter()
+            .filter(|id| self.is_bad(*id, options))
+            .collect()
+    }
+
+    fn candidates(&self, options: &RefinementOptions) -> (Vec<Candidate<N>>, usize) {
+        // Points to insert this round and the number of bad simplices
+        let boundary = self.boundary();
+        let encroached: Vec<Candidate<N>> = (0..boundary.elements.len())
+            .into_par_iter()
+            .filter_map(|i| {
+                self.is_encroached(&boundary.elements[i])
+                    .map(|simplex| (self.split_point(&boundary, i), simplex))
+            })
+            .collect();
+        let bad = self.bad_simplices(options);
+        if !encroached.is_empty() {
+            return (encroached, bad.len());
+        }
+        let candidates = bad
+            .par_iter()
+            .filter_map(|&id| {
+                let center = self.simplices.get(&id).unwrap().center;
+                match boundary.encroached_by(center).first() {
+                    Some(&element) => Some((self.split_point(&boundary, element), id)),
+                    None => match self.find_containing_simplex(center) {
+                        Location::Inside { .. } => Some((center, id)),
+                        Location::OutsideHull => None,
+                    },
+                }
+            })
+            .collect();
+        (candidates, bad.len())
+    }
+
+    pub fn refine(&mut self, options: &RefinementOptions) -> RefinementReport {
+        let mut report = RefinementReport::default();
+        loop {
+            let (candidates, n_bad) = self.candidates(options);
+            report.bad_simplices = n_bad;
+            // Several simplices can ask for the same split, and once features get down to
+            // rounding errors a split can land on an existing vertex
+            let mut seen = HashSet::new();
+            let mut points: Vec<Candidate<N>> = candidates
+                .into_iter()
+                .filter(|(point, _)| seen.insert(point.map(f64::to_bits)))
+                .filter(|(point, _)| {
+                    self.kdtree.nearest_one::<SquaredEuclidean>(point).distance > 0.0
+                })
+                .collect();
+            if points.is_empty() {
+                break;
+            }
+            if report.points_inserted >= options.max_points {
+                log::warn!(
+                    "Refinement stopped after {} points with {} bad simplices left",
+                    report.points_inserted,
+                    n_bad
+                );
+                break;
+            }
+            points.truncate(options.max_points - report.points_inserted);
+            let inserted = if options.batch {
+                self.insert_batch(points)
+            } else {
+                self.insert_candidates(points)
+            };
+            report.points_inserted += inserted;
+            report.rounds += 1;
+            log::debug!(
+                "Refinement round {}: {} bad simplices, inserted {} points",
+                report.rounds,
+                n_bad,
+                inserted
+            );
+        }
+        report
+    }
+
+    fn insert_candidates(&mut self, candidates: Vec<Candidate<N>>) -> usize {
+        // One by one, skipping points whose simplex was destroyed by an earlier one
+        let mut inserted = 0;
+        for (point, simplex) in candidates {
+            if !self.simplices.contains_key(&simplex) {
+                continue;
+            }
+            let update = TreeUpdate::new(self.vertices.len(), point, self);
+            self.insert_point(&update);
+            inserted += 1;
+        }
+        inserted
+    }
+
+    fn insert_batch(&mut self, candidates: Vec<Candidate<N>>) -> usize {
+        // The first batch of the scheduler, whose cavities do not interact
+        let points: Vec<[f64; N]> = candidates.into_iter().map(|(point, _)| point).collect();
+        let queue = make_queue(points, self);
+        let Some(batch) = schedule_batches(&queue).into_iter().next() else {
+            return 0;
+        };
+        let n_points = self.vertices.len();
+        let updates: Vec<TreeUpdate<N, M>> = batch
+            .par_iter()
+            .enumerate()
+            .map(|(k, i)| TreeUpdate::new(n_points + k, queue[*i].1, self))
+            .collect();
+        self.insert_points_parallel(&updates);
+        updates.len()
+    }
+}
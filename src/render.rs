@@ -0,0 +1,232 @@
+use std::error::Error;
+use std::path::Path;
+
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::colors::colormaps::{BlackWhite, Bone, Copper, ViridisRGB};
+
+use crate::delaunay_tree::DelaunayTree;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+    Viridis,
+    BlackWhite,
+    Bone,
+    Copper,
+}
+
+impl Colormap {
+    fn color(&self, h: f64) -> RGBColor {
+        match self {
+            Colormap::Viridis => ViridisRGB::get_color(h),
+            Colormap::BlackWhite => BlackWhite::get_color(h),
+            Colormap::Bone => Bone::get_color(h),
+            Colormap::Copper => Copper::get_color(h),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub width: u32,
+    pub height: u32,
+    // Lower and upper corner of the plotted region, defaults to the input points plus a margin
+    pub viewport: Option<([f64; 2], [f64; 2])>,
+    pub triangles: bool,
+    pub circumcircles: bool,
+    pub voronoi: bool,
+    pub points: bool,
+    // Skip triangles, circles and Voronoi edges that belong to the super-triangle
+    pub hide_super_triangle: bool,
+    // One value per input point, used to fill the bounded Voronoi cells
+    pub cell_values: Option<Vec<f64>>,
+    pub colormap: Colormap,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            width: 1024,
+            height: 1024,
+            viewport: None,
+            triangles: true,
+            circumcircles: false,
+            voronoi: true,
+            points: true,
+            hide_super_triangle: true,
+            cell_values: None,
+            colormap: Colormap::Viridis,
+        }
+    }
+}
+
+pub fn render_png<P: AsRef<Path>>(
+    tree: &DelaunayTree<2, 3>,
+    path: P,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn Error>> {
+    let root =
+        BitMapBackend::new(path.as_ref(), (options.width, options.height)).into_drawing_area();
+    draw(tree, &root, options)?;
+    root.present()?;
+    Ok(())
+}
+
+pub fn render_svg<P: AsRef<Path>>(
+    tree: &DelaunayTree<2, 3>,
+    path: P,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn Error>> {
+    let root = SVGBackend::new(path.as_ref(), (options.width, options.height)).into_drawing_area();
+    draw(tree, &root, options)?;
+    root.present()?;
+    Ok(())
+}
+
+pub fn render_svg_string(
+    tree: &DelaunayTree<2, 3>,
+    options: &RenderOptions,
+) -> Result<String, Box<dyn Error>> {
+    let mut output = String::new();
+    {
+        let root = SVGBackend::with_string(&mut output, (options.width, options.height))
+            .into_drawing_area();
+        draw(tree, &root, options)?;
+        root.present()?;
+    }
+    Ok(output)
+}
+
+fn default_viewport(tree: &DelaunayTree<2, 3>) -> ([f64; 2], [f64; 2]) {
+    let mut lower = [f64::INFINITY; 2];
+    let mut upper = [f64::NEG_INFINITY; 2];
+    for id in DelaunayTree::<2, 3>::N_SUPER_VERTICES..tree.vertices.len() {
+        let coordinates = tree.vertices.get(&id).unwrap().coordinates;
+        for k in 0..2 {
+            lower[k] = lower[k].min(coordinates[k]);
+            upper[k] = upper[k].max(coordinates[k]);
+        }
+    }
+    if lower[0] > upper[0] {
+        return ([0.0, 0.0], [1.0, 1.0]);
+    }
+    for k in 0..2 {
+        let margin = 0.05 * (upper[k] - lower[k]).max(f64::EPSILON);
+        lower[k] -= margin;
+        upper[k] += margin;
+    }
+    (lower, upper)
+}
+
+fn draw<DB: DrawingBackend>(
+    tree: &DelaunayTree<2, 3>,
+    root: &DrawingArea<DB, Shift>,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let (lower, upper) = options.viewport.unwrap_or_else(|| default_viewport(tree));
+    root.fill(&WHITE)?;
+    let chart =
+        ChartBuilder::on(root).build_cartesian_2d(lower[0]..upper[0], lower[1]..upper[1])?;
+    let area = chart.plotting_area();
+
+    // Ghost simplices only pad the super-triangle and carry no geometry
+    let first = DelaunayTree::<2, 3>::N_SUPER_VERTICES;
+    let is_ghost = |vertices: &[usize; 3]| vertices.iter().any(|&id| (3..first).contains(&id));
+    let shown = |vertices: &[usize; 3]| {
+        !is_ghost(vertices)
+            && (!options.hide_super_triangle || vertices.iter().all(|&id| id >= first))
+    };
+
+    if options.voronoi || options.cell_values.is_some() {
+        let cells = tree.voronoi_cells();
+        if let Some(values) = &options.cell_values {
+            assert_eq!(
+                values.len(),
+                tree.vertices.len() - first,
+                "cell_values needs one value per input point"
+            );
+            let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            for cell in cells.iter().filter(|cell| cell.bounded) {
+                let value = values[cell.generator - first];
+                let h = if max > min {
+                    (value - min) / (max - min)
+                } else {
+                    0.5
+                };
+                let polygon: Vec<(f64, f64)> = cell.vertices.iter().map(|v| (v[0], v[1])).collect();
+                area.draw(&Polygon::new(polygon, options.colormap.color(h).filled()))?;
+            }
+        }
+        if options.voronoi {
+            for cell in &cells {
+                for face in cell
+                    .faces
+                    .iter()
+                    .filter(|face| cell.generator < face.neighbor)
+                {
+                    let ends: Vec<usize> =
+                        face.vertices.iter().map(|&k| cell.simplices[k]).collect();
+                    let visible = ends
+                        .iter()
+                        .all(|id| shown(&tree.simplices.get(id).unwrap().vertices));
+                    if visible {
+                        let line: Vec<(f64, f64)> = face
+                            .vertices
+                            .iter()
+                            .map(|&k| (cell.vertices[k][0], cell.vertices[k][1]))
+                            .collect();
+                        area.draw(&PathElement::new(line, BLUE.stroke_width(1)))?;
+                    }
+                }
+            }
+        }
+    }
+
+    for simplex in tree.simplices.iter() {
+        if !shown(&simplex.vertices) {
+            continue;
+        }
+        if options.triangles {
+            let mut line: Vec<(f64, f64)> = simplex
+                .vertices
+                .iter()
+                .map(|id| {
+                    let coordinates = tree.vertices.get(id).unwrap().coordinates;
+                    (coordinates[0], coordinates[1])
+                })
+                .collect();
+            line.push(line[0]);
+            area.draw(&PathElement::new(line, BLACK.stroke_width(1)))?;
+        }
+        if options.circumcircles {
+            // Drawn as polylines in data coordinates so they go through the triangle corners
+            // whatever the axis scales, unequal scales make them ellipses
+            let circle: Vec<(f64, f64)> = (0..=64)
+                .map(|i| {
+                    let angle = 2.0 * std::f64::consts::PI * i as f64 / 64.0;
+                    (
+                        simplex.center[0] + simplex.radius * angle.cos(),
+                        simplex.center[1] + simplex.radius * angle.sin(),
+                    )
+                })
+                .collect();
+            area.draw(&PathElement::new(circle, RED.mix(0.4).stroke_width(1)))?;
+        }
+    }
+
+    if options.points {
+        for id in first..tree.vertices.len() {
+            let coordinates = tree.vertices.get(&id).unwrap().coordinates;
+            area.draw(&Circle::new(
+                (coordinates[0], coordinates[1]),
+                2,
+                BLACK.filled(),
+            ))?;
+        }
+    }
+    Ok(())
+}
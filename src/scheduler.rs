@@ -1,8 +1,11 @@
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::delaunay_tree::DelaunayTree;
 
+// schedule_batches compacts simplex ids spread over more than this many slots per reference
+const COMPACT_RATIO: usize = 8;
+
 pub fn make_queue<const N: usize, const M: usize>(
     vertices: Vec<[f64; N]>,
     tree: &DelaunayTree<N, M>,
@@ -27,29 +30,73 @@ pub fn make_queue<const N: usize, const M: usize>(
         .collect()
 }
 
-pub fn find_placement<const N: usize>(queue: &Vec<(usize, [f64; N], Vec<usize>)>) -> Vec<usize> {
-    let mut occupancy: HashMap<usize, Vec<usize>> = HashMap::new();
-    let mut placement: Vec<usize> = vec![0; queue.len()];
-    queue.into_iter().for_each(|(id, _, neighbors)| {
-        for neighbor in neighbors {
-            if occupancy.contains_key(&neighbor) {
-                let sites = occupancy.get_mut(&neighbor).unwrap();
-                sites.push(*id);
-            } else {
-                occupancy.insert(*neighbor, vec![*id]);
+pub fn schedule_batches<const N: usize>(
+    queue: &[(usize, [f64; N], Vec<usize>)],
+) -> Vec<Vec<usize>> {
+    // Deterministic reservations (Blelloch et al. 2012). In every round each remaining
+    // entry reserves the simplices it may touch with its queue position as priority,
+    // and the entries that hold all of their reservations form the next batch.
+    // An entry is therefore scheduled right after every earlier entry it conflicts
+    // with, so the batches do not depend on the number of threads.
+    let n_sites = queue
+        .par_iter()
+        .flat_map(|(_, _, sites)| sites.par_iter())
+        .max()
+        .map_or(0, |site| site + 1);
+    let n_references: usize = queue.par_iter().map(|(_, _, sites)| sites.len()).sum();
+    // Simplex ids keep growing in a streaming triangulation, reservations then go through
+    // the sorted distinct ids rather than one slot per id ever used
+    let compact: Option<Vec<usize>> = (n_sites > COMPACT_RATIO * n_references).then(|| {
+        let mut ids: Vec<usize> = queue
+            .par_iter()
+            .flat_map(|(_, _, sites)| sites.par_iter().copied())
+            .collect();
+        ids.par_sort_unstable();
+        ids.dedup();
+        ids
+    });
+    let slot = |site: usize| match &compact {
+        Some(ids) => ids.binary_search(&site).unwrap(),
+        None => site,
+    };
+    let n_slots = compact.as_ref().map_or(n_sites, |ids| ids.len());
+    let reservations: Vec<AtomicUsize> = (0..n_slots)
+        .into_par_iter()
+        .map(|_| AtomicUsize::new(usize::MAX))
+        .collect();
+
+    let mut batches = vec![];
+    let mut remaining: Vec<usize> = (0..queue.len()).collect();
+    while !remaining.is_empty() {
+        remaining.par_iter().for_each(|&i| {
+            for site in &queue[i].2 {
+                reservations[slot(*site)].fetch_min(i, Ordering::Relaxed);
             }
+        });
+        let (batch, rest): (Vec<usize>, Vec<usize>) = remaining.par_iter().partition(|&&i| {
+            queue[i]
+                .2
+                .iter()
+                .all(|site| reservations[slot(*site)].load(Ordering::Relaxed) == i)
+        });
+        remaining.par_iter().for_each(|&i| {
+            for site in &queue[i].2 {
+                reservations[slot(*site)].store(usize::MAX, Ordering::Relaxed);
+            }
+        });
+        batches.push(batch);
+        remaining = rest;
+    }
+    batches
+}
+
+pub fn find_placement<const N: usize>(queue: &[(usize, [f64; N], Vec<usize>)]) -> Vec<usize> {
+    // Batch number, starting from 1, of every entry in the queue
+    let mut placement: Vec<usize> = vec![0; queue.len()];
+    for (level, batch) in schedule_batches(queue).iter().enumerate() {
+        for &i in batch {
+            placement[queue[i].0] = level + 1;
         }
-        placement[*id] = neighbors
-            .iter()
-            .map(|site| {
-                if occupancy[site].len() == 1 {
-                    1
-                } else {
-                    placement[occupancy[site][occupancy[site].len() - 2]] + 1
-                }
-            })
-            .max()
-            .unwrap()
-    });
+    }
     placement
 }
@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+
+use bincode::Options;
+use dashmap::DashMap;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, SerializeStruct, SerializeTuple, Serializer};
+use serde::{Deserialize, Serialize};
+
+use crate::delaunay_tree::{DelaunayTree, Parallelism, Simplex, Vertex};
+use crate::query::QueryMethod;
+
+// Every checkpoint starts with MAGIC, FORMAT_VERSION, N and M (little endian u32),
+// followed by the varint-encoded bincode payload of the tree.
+pub const MAGIC: [u8; 4] = *b"VRND";
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum SerializationError {
+    Io(std::io::Error),
+    InvalidMagic([u8; 4]),
+    VersionMismatch {
+        found: u32,
+        expected: u32,
+    },
+    DimensionMismatch {
+        found: (u32, u32),
+        expected: (u32, u32),
+    },
+    InvalidData(String),
+    Encoding(bincode::Error),
+}
+
+impl fmt::Display for SerializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerializationError::Io(err) => write!(f, "I/O error: {}", err),
+            SerializationError::InvalidMagic(magic) => {
+                write!(f, "not a voronoids checkpoint (magic bytes {:?})", magic)
+            }
+            SerializationError::VersionMismatch { found, expected } => write!(
+                f,
+                "checkpoint format version {} is not supported (expected {})",
+                found, expected
+            ),
+            SerializationError::DimensionMismatch { found, expected } => write!(
+                f,
+                "checkpoint holds a DelaunayTree<{}, {}> but a DelaunayTree<{}, {}> was requested",
+                found.0, found.1, expected.0, expected.1
+            ),
+            SerializationError::InvalidData(message) => {
+                write!(f, "invalid checkpoint data: {}", message)
+            }
+            SerializationError::Encoding(err) => write!(f, "encoding error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SerializationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SerializationError::Io(err) => Some(err),
+            SerializationError::Encoding(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SerializationError {
+    fn from(err: std::io::Error) -> Self {
+        SerializationError::Io(err)
+    }
+}
+
+impl From<bincode::Error> for SerializationError {
+    fn from(err: bincode::Error) -> Self {
+        SerializationError::Encoding(err)
+    }
+}
+
+// serde only implements its traits for arrays of a fixed literal length,
+// so the const generic arrays in Simplex and Vertex go through this module.
+pub(crate) mod array {
+    use super::*;
+
+    pub fn serialize<S, T, const K: usize>(array: &[T; K], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        let mut tuple = serializer.serialize_tuple(K)?;
+        for element in array {
+            tuple.serialize_element(element)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D, T, const K: usize>(deserializer: D) -> Result<[T; K], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        struct ArrayVisitor<T, const K: usize>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>, const K: usize> Visitor<'de> for ArrayVisitor<T, K> {
+            type Value = [T; K];
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an array of length {}", K)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut elements = Vec::with_capacity(K);
+                for i in 0..K {
+                    match seq.next_element()? {
+                        Some(element) => elements.push(element),
+                        None => return Err(de::Error::invalid_length(i, &self)),
+                    }
+                }
+                elements
+                    .try_into()
+                    .map_err(|_| de::Error::invalid_length(K, &self))
+            }
+        }
+
+        deserializer.deserialize_tuple(K, ArrayVisitor::<T, K>(PhantomData))
+    }
+}
+
+// DashMap iteration order is arbitrary, so maps are written as (id, value)
+// pairs sorted by id to make checkpoints of the same tree byte-identical.
+struct SortedMap<'a, V>(&'a DashMap<usize, V>);
+
+impl<'a, V: Serialize> Serialize for SortedMap<'a, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut keys: Vec<usize> = self.0.iter().map(|entry| *entry.key()).collect();
+        keys.sort_unstable();
+        let mut seq = serializer.serialize_seq(Some(keys.len()))?;
+        for key in keys {
+            let value = self.0.get(&key).unwrap();
+            seq.serialize_element(&(key, &*value))?;
+        }
+        seq.end()
+    }
+}
+
+impl<const N: usize, const M: usize> Serialize for DelaunayTree<N, M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // The kd-tree is not stored, it is rebuilt from the vertices on load.
+        let mut state = serializer.serialize_struct("DelaunayTree", 3)?;
+        state.serialize_field("vertices", &SortedMap(&self.vertices))?;
+        state.serialize_field("simplices", &SortedMap(&self.simplices))?;
+        state.serialize_field("max_simplex_id", &self.max_simplex_id)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "DelaunayTree")]
+struct DelaunayTreeData<const N: usize, const M: usize> {
+    vertices: Vec<(usize, Vertex<N>)>,
+    simplices: Vec<(usize, Simplex<N, M>)>,
+    max_simplex_id: usize,
+}
+
+impl<'de, const N: usize, const M: usize> Deserialize<'de> for DelaunayTree<N, M> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = DelaunayTreeData::<N, M>::deserialize(deserializer)?;
+        DelaunayTree::from_data(data).map_err(de::Error::custom)
+    }
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    fn from_data(data: DelaunayTreeData<N, M>) -> Result<Self, String> {
+        // Vertex ids are handed out as vertices.len(), so they must be contiguous.
+        for (i, (id, _)) in data.vertices.iter().enumerate() {
+            if *id != i {
+                return Err(format!(
+                    "vertex ids are not contiguous, found {} at {}",
+                    id, i
+                ));
+            }
+        }
+        if let Some((id, _)) = data
+            .simplices
+            .iter()
+            .find(|(id, _)| *id > data.max_simplex_id)
+        {
+            return Err(format!(
+                "simplex id {} exceeds max_simplex_id {}",
+                id, data.max_simplex_id
+            ));
+        }
+        // Every id a simplex or a vertex refers to must exist, or the first lookup panics.
+        let n_vertices = data.vertices.len();
+        let simplex_ids: HashSet<usize> = data.simplices.iter().map(|(id, _)| *id).collect();
+        for (id, simplex) in &data.simplices {
+            if let Some(vertex) = simplex
+                .vertices
+                .iter()
+                .find(|&&vertex| vertex >= n_vertices)
+            {
+                return Err(format!(
+                    "simplex {} refers to missing vertex {}",
+                    id, vertex
+                ));
+            }
+            if let Some(neighbor) = simplex
+                .neighbors
+                .iter()
+                .find(|neighbor| !simplex_ids.contains(neighbor))
+            {
+                return Err(format!(
+                    "simplex {} refers to missing neighbor {}",
+                    id, neighbor
+                ));
+            }
+        }
+        for (id, vertex) in &data.vertices {
+            if let Some(simplex) = vertex
+                .simplex
+                .iter()
+                .find(|simplex| !simplex_ids.contains(simplex))
+            {
+                return Err(format!(
+                    "vertex {} refers to missing simplex {}",
+                    id, simplex
+                ));
+            }
+        }
+
+        let vertices: DashMap<usize, Vertex<N>> = data.vertices.into_iter().collect();
+        let simplices: DashMap<usize, Simplex<N, M>> = data.simplices.into_iter().collect();
+        Ok(DelaunayTree {
+            kdtree: Self::build_kdtree(&vertices),
+            vertices,
+            simplices,
+            max_simplex_id: data.max_simplex_id,
+            deterministic: false,
+            parallelism: Parallelism::Global,
+            progress: None,
+            query_method: QueryMethod::KdTree,
+        })
+    }
+
+    pub fn save<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&(N as u32).to_le_bytes())?;
+        writer.write_all(&(M as u32).to_le_bytes())?;
+        bincode::DefaultOptions::new().serialize_into(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(SerializationError::InvalidMagic(magic));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != FORMAT_VERSION {
+            return Err(SerializationError::VersionMismatch {
+                found: version,
+                expected: FORMAT_VERSION,
+            });
+        }
+        let dimensions = (read_u32(&mut reader)?, read_u32(&mut reader)?);
+        if dimensions != (N as u32, M as u32) {
+            return Err(SerializationError::DimensionMismatch {
+                found: dimensions,
+                expected: (N as u32, M as u32),
+            });
+        }
+        let data: DelaunayTreeData<N, M> =
+            bincode::DefaultOptions::new().deserialize_from(&mut reader)?;
+        DelaunayTree::from_data(data).map_err(SerializationError::InvalidData)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut buffer = vec![];
+        self.save(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        Self::load(bytes)
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, SerializationError> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
@@ -0,0 +1,438 @@
+use std::collections::HashSet;
+
+use kiddo::SquaredEuclidean;
+use rand::prelude::*;
+use rayon::prelude::*;
+
+use crate::delaunay_tree::{DelaunayTree, Parallelism, TreeUpdate};
+use crate::geometry::simplex_volume;
+use crate::quality::simplex_quality;
+use crate::query::Location;
+
+// Sliver removal by vertex perturbation. Slivers are flat tetrahedra whose vertices are
+// spread out, so that their radius-edge ratio is fine and refinement leaves them alone.
+// Every pass picks one vertex per sliver, the one closest to the plane of the other
+// three, and keeps these vertices two rings apart. Every vertex tries random moves by
+// up to a fraction of its shortest edge and keeps the one that maximizes the minimum
+// dihedral angle of the tetrahedra around it without lowering that of the tetrahedra
+// around its link. Moves are scored by inserting the vertex into a triangulation of its
+// two-ring without it, built once per vertex and pass. Its tetrahedra around the link
+// are those of the whole mesh when their circumspheres are empty of every other vertex,
+// and moves for which the two-ring does not show this are dropped. The whole mesh is
+// rebuilt once per pass with the chosen moves, and the pass is kept if it lowers the
+// number of slivers, or keeps it and raises the minimum dihedral angle. Vertices on the
+// hull never move.
+
+// A vertex counts as inside a circumsphere if its squared distance to the center is
+// below (1 - RELATIVE_TOLERANCE) times the squared radius, as in validate
+const RELATIVE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone)]
+pub struct SliverOptions {
+    // Tetrahedra with a smaller dihedral angle, in degrees, are slivers
+    pub min_dihedral_angle: f64,
+    // Largest move as a fraction of the shortest edge at the vertex
+    pub max_displacement: f64,
+    // Random moves tried for every selected vertex in every pass
+    pub trials: usize,
+    pub max_iterations: usize,
+    // The first fixed_points input points never move, e.g. the input of a refinement
+    pub fixed_points: usize,
+    pub seed: u64,
+}
+
+impl Default for SliverOptions {
+    fn default() -> Self {
+        SliverOptions {
+            min_dihedral_angle: 15.0,
+            max_displacement: 0.2,
+            trials: 8,
+            max_iterations: 10,
+            fixed_points: 0,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SliverReport {
+    // Over all real tetrahedra, in degrees
+    pub min_dihedral_angle_before: f64,
+    pub min_dihedral_angle_after: f64,
+    pub slivers_before: usize,
+    pub slivers_after: usize,
+    // Distinct vertices moved by the passes that were kept
+    pub vertices_moved: usize,
+    // Passes kept
+    pub iterations: usize,
+}
+
+// A vertex to move, the vertices whose tetrahedra it affects, the sorted vertices of
+// its two-ring and its largest move
+struct Selected {
+    vertex: usize,
+    region: Vec<usize>,
+    ring: Vec<usize>,
+    step: f64,
+}
+
+// The two-ring around a selected vertex triangulated without it, the global ids of its
+// input points, and for every real tetrahedron around the link whether its circumsphere
+// is empty in the whole mesh without the vertex, with its minimum dihedral angle
+struct Cavity {
+    tree: DelaunayTree<3, 4>,
+    ids: Vec<usize>,
+    region: Vec<(usize, bool, f64)>,
+}
+
+fn distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn height(coordinates: &[[f64; 3]; 4], k: usize) -> f64 {
+    // Distance from vertex k to the plane of the opposite facet, 3 V / area
+    let facet: Vec<[f64; 3]> = (0..4).filter(|&l| l != k).map(|l| coordinates[l]).collect();
+    let u: Vec<f64> = (0..3).map(|d| facet[1][d] - facet[0][d]).collect();
+    let v: Vec<f64> = (0..3).map(|d| facet[2][d] - facet[0][d]).collect();
+    let area = ((u[1] * v[2] - u[2] * v[1]).powi(2)
+        + (u[2] * v[0] - u[0] * v[2]).powi(2)
+        + (u[0] * v[1] - u[1] * v[0]).powi(2))
+    .sqrt()
+        / 2.0;
+    3.0 * simplex_volume(*coordinates) / area
+}
+
+fn random_direction(rng: &mut StdRng) -> [f64; 3] {
+    // Uniform on the sphere by rejection from the cube
+    loop {
+        let direction: [f64; 3] = [
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        ];
+        let norm = direction.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm > 1e-3 && norm <= 1.0 {
+            return direction.map(|x| x / norm);
+        }
+    }
+}
+
+impl DelaunayTree<3, 4> {
+    fn min_angle_and_slivers(&self, min_dihedral_angle: f64) -> (f64, usize) {
+        let qualities = self.quality();
+        let min = qualities
+            .iter()
+            .map(|quality| quality.min_dihedral_angle)
+            .fold(f64::INFINITY, f64::min);
+        let slivers = qualities
+            .iter()
+            .filter(|quality| quality.min_dihedral_angle < min_dihedral_angle)
+            .count();
+        (min, slivers)
+    }
+
+    fn region_min_angle(&self, region: &[usize]) -> f64 {
+        // Minimum dihedral angle of the real tetrahedra touching the region
+        let mut simplices: Vec<usize> = region
+            .iter()
+            .flat_map(|id| self.vertices.get(id).unwrap().simplex.clone())
+            .filter(|id| Self::is_real_simplex(&self.simplices.get(id).unwrap()))
+            .collect();
+        simplices.sort_unstable();
+        simplices.dedup();
+        simplices
+            .into_iter()
+            .map(|id| self.simplex_quality(id).min_dihedral_angle)
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn is_interior(&self, vertex_id: usize) -> bool {
+        // Whether every simplex around the vertex is real
+        self.vertices
+            .get(&vertex_id)
+            .unwrap()
+            .simplex
+            .iter()
+            .all(|id| Self::is_real_simplex(&self.simplices.get(id).unwrap()))
+    }
+
+    fn select_vertices(&self, options: &SliverOptions) -> Vec<Selected> {
+        // One movable vertex per sliver, worst sliver first, two rings apart
+        let hull: HashSet<usize> = self.hull_facets().into_iter().flatten().collect();
+        let first_movable = Self::N_SUPER_VERTICES + options.fixed_points;
+        let mut slivers: Vec<(f64, usize)> = self
+            .quality()
+            .into_iter()
+            .filter(|quality| quality.min_dihedral_angle < options.min_dihedral_angle)
+            .map(|quality| (quality.min_dihedral_angle, quality.simplex))
+            .collect();
+        slivers.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut blocked: HashSet<usize> = HashSet::new();
+        let mut selected = vec![];
+        for (_, simplex_id) in slivers {
+            let simplex = self.simplices.get(&simplex_id).unwrap();
+            let coordinates = self.simplex_coordinates(&simplex);
+            let vertex = (0..4)
+                .filter(|&k| {
+                    let id = simplex.vertices[k];
+                    id >= first_movable && !hull.contains(&id) && !blocked.contains(&id)
+                })
+                .min_by(|&i, &j| height(&coordinates, i).total_cmp(&height(&coordinates, j)))
+                .map(|k| simplex.vertices[k]);
+            let Some(vertex) = vertex else {
+                continue;
+            };
+            let link = self.vertex_link(vertex, true);
+            let position = self.vertices.get(&vertex).unwrap().coordinates;
+            let step = link
+                .iter()
+                .map(|id| distance(&position, &self.vertices.get(id).unwrap().coordinates))
+                .fold(f64::INFINITY, f64::min)
+                * options.max_displacement;
+            let mut ring = vec![vertex];
+            for id in &link {
+                ring.push(*id);
+                ring.extend(self.vertex_link(*id, true));
+            }
+            ring.sort_unstable();
+            ring.dedup();
+            blocked.extend(ring.iter().copied());
+            let mut region = link;
+            region.push(vertex);
+            selected.push(Selected {
+                vertex,
+                region,
+                ring,
+                step,
+            });
+        }
+        selected
+    }
+
+    fn is_empty_circumsphere(
+        &self,
+        center: &[f64; 3],
+        radius: f64,
+        vertices: &[usize; 4],
+        moved: usize,
+    ) -> bool {
+        // No vertex but the ghosts, those of the tetrahedron and the old position of the
+        // moved vertex lies inside the circumsphere
+        let threshold = radius * radius * (1.0 - RELATIVE_TOLERANCE);
+        self.kdtree
+            .within_unsorted::<SquaredEuclidean>(center, threshold)
+            .into_iter()
+            .filter(|neighbour| neighbour.distance < threshold)
+            .map(|neighbour| neighbour.item as usize)
+            .all(|id| Self::is_ghost_vertex(id) || id == moved || vertices.contains(&id))
+    }
+
+    fn cavity(&self, selected: &Selected) -> Cavity {
+        // Triangulates the two-ring without the vertex. Ring vertex ids[i] gets local id
+        // N_SUPER_VERTICES + i, with the vertex itself last so that it gets the next id.
+        let mut ids: Vec<usize> = selected
+            .ring
+            .iter()
+            .copied()
+            .filter(|&id| id != selected.vertex)
+            .collect();
+        let points: Vec<[f64; 3]> = ids
+            .iter()
+            .map(|id| self.vertices.get(id).unwrap().coordinates)
+            .collect();
+        ids.push(selected.vertex);
+        let mut tree =
+            DelaunayTree::<3, 4>::new(points.clone()).with_parallelism(Parallelism::Serial);
+        for (i, &point) in points.iter().enumerate() {
+            let update = TreeUpdate::new(Self::N_SUPER_VERTICES + i, point, &tree);
+            tree.insert_point(&update);
+        }
+
+        let mut simplices: Vec<usize> = selected
+            .region
+            .iter()
+            .filter(|&&id| id != selected.vertex)
+            .flat_map(|id| {
+                let local =
+                    Self::N_SUPER_VERTICES + ids.iter().position(|other| other == id).unwrap();
+                tree.vertices.get(&local).unwrap().simplex.clone()
+            })
+            .filter(|id| Self::is_real_simplex(&tree.simplices.get(id).unwrap()))
+            .collect();
+        simplices.sort_unstable();
+        simplices.dedup();
+        let region = simplices
+            .into_iter()
+            .map(|id| {
+                let simplex = tree.simplices.get(&id).unwrap();
+                let vertices = simplex
+                    .vertices
+                    .map(|local| ids[local - Self::N_SUPER_VERTICES]);
+                let settled = self.is_empty_circumsphere(
+                    &simplex.center,
+                    simplex.radius,
+                    &vertices,
+                    selected.vertex,
+                );
+                (id, settled, tree.simplex_quality(id).min_dihedral_angle)
+            })
+            .collect();
+        Cavity { tree, ids, region }
+    }
+
+    fn trial_angles(
+        &self,
+        selected: &Selected,
+        cavity: &Cavity,
+        position: [f64; 3],
+    ) -> Option<(f64, f64)> {
+        // Minimum dihedral angle around the vertex and around its region with the vertex
+        // moved to position, by inserting it into the cavity. None if the vertex reaches
+        // the hull or the two-ring does not settle the tetrahedra of the region.
+        let vertex = Self::N_SUPER_VERTICES + cavity.ids.len() - 1;
+        let update = TreeUpdate::new(vertex, position, &cavity.tree);
+        let mut angle = f64::INFINITY;
+        for (vertices, (center, radius)) in update
+            .simplices
+            .iter()
+            .zip(update.centers.iter().zip(&update.radii))
+        {
+            if vertices.iter().any(|&id| Self::is_super_vertex(id)) {
+                return None;
+            }
+            let vertices = vertices.map(|local| cavity.ids[local - Self::N_SUPER_VERTICES]);
+            if !self.is_empty_circumsphere(center, *radius, &vertices, selected.vertex) {
+                return None;
+            }
+            let coordinates = vertices.map(|id| {
+                if id == selected.vertex {
+                    position
+                } else {
+                    self.vertices.get(&id).unwrap().coordinates
+                }
+            });
+            angle = angle.min(simplex_quality(coordinates, *radius).min_dihedral_angle);
+        }
+
+        let mut region = angle;
+        for &(id, settled, quality) in &cavity.region {
+            if update.killed_sites.contains(&id) {
+                continue;
+            }
+            if !settled {
+                return None;
+            }
+            region = region.min(quality);
+        }
+        Some((angle, region))
+    }
+
+    pub fn remove_slivers(&mut self, options: &SliverOptions) -> SliverReport {
+        let (min_angle, slivers) = self.min_angle_and_slivers(options.min_dihedral_angle);
+        let mut report = SliverReport {
+            min_dihedral_angle_before: min_angle,
+            min_dihedral_angle_after: min_angle,
+            slivers_before: slivers,
+            slivers_after: slivers,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(options.seed);
+        let mut moved: HashSet<usize> = HashSet::new();
+        for _ in 0..options.max_iterations {
+            if report.slivers_after == 0 {
+                break;
+            }
+            let selected = self.select_vertices(options);
+            if selected.is_empty() {
+                break;
+            }
+            let mut points: Vec<[f64; 3]> = (Self::N_SUPER_VERTICES..self.vertices.len())
+                .map(|id| self.vertices.get(&id).unwrap().coordinates)
+                .collect();
+            // Minimum angle around the region of every selected vertex, which a move must
+            // not lower, and the best minimum angle around the vertex with its move
+            let regions: Vec<f64> = selected
+                .par_iter()
+                .map(|selected| self.region_min_angle(&selected.region))
+                .collect();
+            let mut best: Vec<(f64, Option<[f64; 3]>)> = selected
+                .par_iter()
+                .map(|selected| (self.region_min_angle(&[selected.vertex]), None))
+                .collect();
+            // Draw all moves up front so that they do not depend on the thread count
+            let trials: Vec<Vec<[f64; 3]>> = selected
+                .iter()
+                .map(|selected| {
+                    (0..options.trials)
+                        .map(|_| {
+                            let direction = random_direction(&mut rng);
+                            let length = selected.step * rng.gen_range(0.0..1.0);
+                            let mut position = points[selected.vertex - Self::N_SUPER_VERTICES];
+                            for (x, d) in position.iter_mut().zip(direction.iter()) {
+                                *x += length * d;
+                            }
+                            position
+                        })
+                        .collect()
+                })
+                .collect();
+            best.par_iter_mut()
+                .zip(&selected)
+                .zip(&regions)
+                .zip(&trials)
+                .for_each(|(((best, selected), before), trials)| {
+                    let cavity = self.cavity(selected);
+                    for &position in trials {
+                        // Stay inside the hull so that the domain does not change
+                        if let Location::OutsideHull = self.find_containing_simplex(position) {
+                            continue;
+                        }
+                        if let Some((angle, region)) =
+                            self.trial_angles(selected, &cavity, position)
+                        {
+                            if angle > best.0 && region >= *before {
+                                *best = (angle, Some(position));
+                            }
+                        }
+                    }
+                });
+
+            let mut pass_moved = vec![];
+            for (selected, (_, position)) in selected.iter().zip(best) {
+                if let Some(position) = position {
+                    points[selected.vertex - Self::N_SUPER_VERTICES] = position;
+                    pass_moved.push(selected.vertex);
+                }
+            }
+            if pass_moved.is_empty() {
+                break;
+            }
+            let tree = self.retriangulate(&points);
+            let (min_angle, slivers) = tree.min_angle_and_slivers(options.min_dihedral_angle);
+            let better = slivers < report.slivers_after
+                || (slivers == report.slivers_after && min_angle > report.min_dihedral_angle_after);
+            if !better || !pass_moved.iter().all(|id| tree.is_interior(*id)) {
+                // Try other moves in the next pass
+                continue;
+            }
+            self.replace_triangulation(tree);
+            report.min_dihedral_angle_after = min_angle;
+            report.slivers_after = slivers;
+            report.iterations += 1;
+            moved.extend(pass_moved);
+            log::debug!(
+                "Sliver removal pass {}: {} slivers, minimum dihedral angle {:.2}",
+                report.iterations,
+                slivers,
+                min_angle
+            );
+        }
+        report.vertices_moved = moved.len();
+        report
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::iter;
+use std::time::Instant;
+
+use crate::delaunay_tree::{DelaunayTree, Parallelism};
+
+// Streaming triangulation of point sets too large for memory. Points come in chunks
+// sorted along the first coordinate: every chunk may be in any order, but must not start
+// before the largest first coordinate of the previous chunks, the front. No future point
+// then lies in a circumsphere that ends before the front, so these simplices are final.
+// They are written to a sink once, and leave memory together with their vertices once
+// all their neighbors are final too, because cavity searches only ever step from a live
+// simplex to its neighbors. Simplices touching the super-simplex have circumspheres
+// reaching out of the bounds, so they are final once their circumsphere misses the part
+// of the bounds past the front, and are evicted without being written. Only the few
+// simplices next to the ghosts and the super-simplex corners stay for the whole run.
+// Memory is then bounded by the simplices around the front, of the order of the points
+// in a slab one chunk thick. The real simplices left are written by finish.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FinalSimplex<const N: usize, const M: usize> {
+    // Indices of the points in the order they were pushed
+    pub vertices: [usize; M],
+    pub coordinates: [[f64; N]; M],
+    pub center: [f64; N],
+    pub radius: f64,
+}
+
+pub trait SimplexSink<const N: usize, const M: usize> {
+    fn write(&mut self, simplex: &FinalSimplex<N, M>) -> io::Result<()>;
+
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Keeps everything in memory, for tests and inputs that fit
+impl<const N: usize, const M: usize> SimplexSink<N, M> for Vec<FinalSimplex<N, M>> {
+    fn write(&mut self, simplex: &FinalSimplex<N, M>) -> io::Result<()> {
+        self.push(*simplex);
+        Ok(())
+    }
+}
+
+// Fixed size little-endian records: M u64 vertex indices, M * N f64 coordinates, N f64
+// for the center and one for the radius
+pub struct BinarySink<W: Write> {
+    writer: W,
+    pub simplices_written: usize,
+}
+
+impl<W: Write> BinarySink<W> {
+    pub fn new(writer: W) -> Self {
+        BinarySink {
+            writer,
+            simplices_written: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<const N: usize, const M: usize, W: Write> SimplexSink<N, M> for BinarySink<W> {
+    fn write(&mut self, simplex: &FinalSimplex<N, M>) -> io::Result<()> {
+        for id in simplex.vertices {
+            self.writer.write_all(&(id as u64).to_le_bytes())?;
+        }
+        for x in simplex
+            .coordinates
+            .iter()
+            .flatten()
+            .chain(simplex.center.iter())
+        {
+            self.writer.write_all(&x.to_le_bytes())?;
+        }
+        self.writer.write_all(&simplex.radius.to_le_bytes())?;
+        self.simplices_written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub fn read_simplices<const N: usize, const M: usize, R: Read>(
+    reader: R,
+) -> impl Iterator<Item = io::Result<FinalSimplex<N, M>>> {
+    // Reads the records written by BinarySink one at a time, so that the output never
+    // has to fit in memory. A truncated last record is an error and ends the iteration.
+    let record = 8 * (M + M * N + N + 1);
+    let mut reader = BufReader::new(reader);
+    let mut bytes = vec![0; record];
+    let mut done = false;
+    iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        match reader.fill_buf() {
+            Ok([]) => return None,
+            Ok(_) => {}
+            Err(error) => {
+                done = true;
+                return Some(Err(error));
+            }
+        }
+        if let Err(error) = reader.read_exact(&mut bytes) {
+            done = true;
+            if error.kind() != io::ErrorKind::UnexpectedEof {
+                return Some(Err(error));
+            }
+            return Some(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("truncated {} byte record", record),
+            )));
+        }
+        let words: Vec<[u8; 8]> = bytes
+            .chunks_exact(8)
+            .map(|word| word.try_into().unwrap())
+            .collect();
+        let mut simplex = FinalSimplex {
+            vertices: [0; M],
+            coordinates: [[0.0; N]; M],
+            center: [0.0; N],
+            radius: f64::from_le_bytes(words[M + M * N + N]),
+        };
+        for i in 0..M {
+            simplex.vertices[i] = u64::from_le_bytes(words[i]) as usize;
+            for k in 0..N {
+                simplex.coordinates[i][k] = f64::from_le_bytes(words[M + i * N + k]);
+            }
+        }
+        for k in 0..N {
+            simplex.center[k] = f64::from_le_bytes(words[M + M * N + k]);
+        }
+        Some(Ok(simplex))
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StreamingReport {
+    pub points: usize,
+    pub chunks: usize,
+    pub simplices_written: usize,
+    // Largest number of simplices and of input vertices held after a chunk
+    pub max_simplices_in_memory: usize,
+    pub max_vertices_in_memory: usize,
+}
+
+pub struct StreamingTriangulation<const N: usize, const M: usize, S: SimplexSink<N, M>> {
+    tree: DelaunayTree<N, M>,
+    sink: S,
+    lower: [f64; N],
+    upper: [f64; N],
+    // Written to the sink, kept while a neighbor is not final
+    finalized: HashSet<usize>,
+    front: f64,
+    report: StreamingReport,
+}
+
+impl<S: SimplexSink<3, 4>> StreamingTriangulation<3, 4, S> {
+    pub fn new(lower: [f64; 3], upper: [f64; 3], sink: S) -> Self {
+        // The super-simplex must hold every future point, so the bounds come first
+        let corners = (0..8)
+            .map(|bits| {
+                [0, 1, 2].map(|k| {
+                    if (bits >> k) & 1 == 1 {
+                        upper[k]
+                    } else {
+                        lower[k]
+                    }
+                })
+            })
+            .collect();
+        Self::from_tree(DelaunayTree::<3, 4>::new(corners), lower, upper, sink)
+    }
+}
+
+impl<S: SimplexSink<2, 3>> StreamingTriangulation<2, 3, S> {
+    pub fn new(lower: [f64; 2], upper: [f64; 2], sink: S) -> Self {
+        let corners = (0..4)
+            .map(|bits| {
+                [0, 1].map(|k| {
+                    if (bits >> k) & 1 == 1 {
+                        upper[k]
+                    } else {
+                        lower[k]
+                    }
+                })
+            })
+            .collect();
+        Self::from_tree(DelaunayTree::<2, 3>::new(corners), lower, upper, sink)
+    }
+}
+
+impl<const N: usize, const M: usize, S: SimplexSink<N, M>> StreamingTriangulation<N, M, S> {
+    fn from_tree(tree: DelaunayTree<N, M>, lower: [f64; N], upper: [f64; N], sink: S) -> Self {
+        StreamingTriangulation {
+            tree,
+            sink,
+            lower,
+            upper,
+            finalized: HashSet::new(),
+            front: f64::NEG_INFINITY,
+            report: StreamingReport::default(),
+        }
+    }
+
+    pub fn with_parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.tree = self.tree.with_parallelism(parallelism);
+        self
+    }
+
+    pub fn report(&self) -> &StreamingReport {
+        &self.report
+    }
+
+    pub fn push_chunk(&mut self, points: &[[f64; N]]) -> io::Result<()> {
+        if points.is_empty() {
+            return Ok(());
+        }
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+        if let Some(point) = points
+            .iter()
+            .find(|point| (0..N).any(|k| point[k] < self.lower[k] || point[k] > self.upper[k]))
+        {
+            return Err(invalid(format!("{:?} is outside the bounds", point)));
+        }
+        let (start, end) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(start, end), point| {
+                (start.min(point[0]), end.max(point[0]))
+            });
+        if start < self.front {
+            return Err(invalid(format!(
+                "chunk starts at {} before the front at {}",
+                start, self.front
+            )));
+        }
+
+        let first_id = DelaunayTree::<N, M>::N_SUPER_VERTICES + self.report.points;
+        self.tree
+            .add_points(points.to_vec(), first_id, 0, points.len(), Instant::now());
+        self.report.points += points.len();
+        self.report.chunks += 1;
+        self.front = end;
+
+        // Final simplices touching the super-simplex are not output, but can be evicted
+        let simplices = self.final_simplices();
+        let real: Vec<usize> = simplices
+            .iter()
+            .copied()
+            .filter(|id| {
+                DelaunayTree::<N, M>::is_real_simplex(&self.tree.simplices.get(id).unwrap())
+            })
+            .collect();
+        self.write(&real)?;
+        self.finalized.extend(simplices);
+        self.evict();
+
+        self.report.max_simplices_in_memory =
+            (self.report.max_simplices_in_memory).max(self.tree.simplices.len());
+        self.report.max_vertices_in_memory = (self.report.max_vertices_in_memory)
+            .max(self.tree.vertices.len() - DelaunayTree::<N, M>::N_SUPER_VERTICES);
+        log::debug!(
+            "Chunk {}: front at {}, {} simplices written, {} in memory",
+            self.report.chunks,
+            self.front,
+            self.report.simplices_written,
+            self.tree.simplices.len()
+        );
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<(S, StreamingReport)> {
+        // Write the real simplices still pending
+        let mut simplices: Vec<usize> = self
+            .tree
+            .real_simplices()
+            .into_iter()
+            .filter(|id| !self.finalized.contains(id))
+            .collect();
+        simplices.sort_unstable();
+        self.write(&simplices)?;
+        self.sink.finish()?;
+        Ok((self.sink, self.report))
+    }
+
+    fn final_simplices(&self) -> Vec<usize> {
+        // Simplices but the ghosts whose circumsphere misses the part of the bounds at or
+        // past the front, in id order so that the output does not depend on DashMap order
+        let mut simplices: Vec<usize> = self
+            .tree
+            .simplices
+            .iter()
+            .filter(|simplex| {
+                !self.finalized.contains(simplex.key())
+                    && !DelaunayTree::<N, M>::is_ghost_simplex(simplex.value())
+                    && self.misses_remaining_bounds(&simplex.center, simplex.radius)
+            })
+            .map(|simplex| *simplex.key())
+            .collect();
+        simplices.sort_unstable();
+        simplices
+    }
+
+    fn misses_remaining_bounds(&self, center: &[f64; N], radius: f64) -> bool {
+        // Squared distance from the center to the box where future points can lie
+        let squared: f64 = (0..N)
+            .map(|k| {
+                let lower = if k == 0 { self.front } else { self.lower[k] };
+                let gap = (lower - center[k]).max(center[k] - self.upper[k]).max(0.0);
+                gap * gap
+            })
+            .sum();
+        squared > radius * radius
+    }
+
+    fn write(&mut self, simplices: &[usize]) -> io::Result<()> {
+        for id in simplices {
+            let simplex = self.tree.simplices.get(id).unwrap();
+            let final_simplex = FinalSimplex {
+                vertices: simplex
+                    .vertices
+                    .map(|vertex| vertex - DelaunayTree::<N, M>::N_SUPER_VERTICES),
+                coordinates: self.tree.simplex_coordinates(&simplex),
+                center: simplex.center,
+                radius: simplex.radius,
+            };
+            self.sink.write(&final_simplex)?;
+        }
+        self.report.simplices_written += simplices.len();
+        Ok(())
+    }
+
+    fn evict(&mut self) {
+        // Drop final simplices whose neighbors are all final, a neighbor that is gone was
+        // dropped before. Then drop the vertices left without simplices.
+        let evicted: Vec<usize> = self
+            .finalized
+            .iter()
+            .filter(|id| {
+                self.tree
+                    .simplices
+                    .get(id)
+                    .unwrap()
+                    .neighbors
+                    .iter()
+                    .all(|neighbor| {
+                        self.finalized.contains(neighbor)
+                            || !self.tree.simplices.contains_key(neighbor)
+                    })
+            })
+            .copied()
+            .collect();
+        for id in evicted {
+            let (_, simplex) = self.tree.simplices.remove(&id).unwrap();
+            self.finalized.remove(&id);
+            for vertex_id in simplex.vertices {
+                let mut vertex = self.tree.vertices.get_mut(&vertex_id).unwrap();
+                vertex.simplex.retain(|&other| other != id);
+                // The super-simplex corners stay for the cavities of later points
+                if vertex.simplex.is_empty() && !DelaunayTree::<N, M>::is_super_vertex(vertex_id) {
+                    let coordinates = vertex.coordinates;
+                    drop(vertex);
+                    self.tree.vertices.remove(&vertex_id);
+                    self.tree.kdtree.remove(&coordinates, vertex_id as u64);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::delaunay_tree::DelaunayTree;
+
+// Readers and writers for the text formats of TetGen (3D) and Triangle (2D).
+// Points are numbered from first_index, which is 1 for TetGen/Triangle defaults
+// and 0 for files produced with the -z switch.
+
+#[derive(Debug, Clone)]
+pub struct NodeFile<const N: usize> {
+    pub points: Vec<[f64; N]>,
+    pub attributes: Vec<Vec<f64>>,
+    pub boundary_markers: Option<Vec<i64>>,
+    pub first_index: usize,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn data_lines<R: BufRead>(reader: R) -> impl Iterator<Item = io::Result<Vec<String>>> {
+    // Non-empty lines split into tokens, with everything after '#' dropped
+    reader.lines().filter_map(|line| match line {
+        Ok(line) => {
+            let content = line.split('#').next().unwrap_or("");
+            let tokens: Vec<String> = content.split_whitespace().map(String::from).collect();
+            if tokens.is_empty() {
+                None
+            } else {
+                Some(Ok(tokens))
+            }
+        }
+        Err(err) => Some(Err(err)),
+    })
+}
+
+fn parse<T: std::str::FromStr>(token: &str) -> io::Result<T> {
+    token
+        .parse()
+        .map_err(|_| invalid_data(format!("cannot parse {:?}", token)))
+}
+
+pub fn read_node<const N: usize, R: BufRead>(reader: R) -> io::Result<NodeFile<N>> {
+    let mut lines = data_lines(reader);
+    let header = lines
+        .next()
+        .ok_or_else(|| invalid_data("empty .node file".to_string()))??;
+    if header.len() < 2 {
+        return Err(invalid_data(format!("invalid .node header {:?}", header)));
+    }
+    let n_points: usize = parse(&header[0])?;
+    let dimension: usize = parse(&header[1])?;
+    if dimension != N {
+        return Err(invalid_data(format!(
+            ".node file is {}-dimensional, expected {} dimensions",
+            dimension, N
+        )));
+    }
+    let n_attributes: usize = match header.get(2) {
+        Some(token) => parse(token)?,
+        None => 0,
+    };
+    let has_markers = match header.get(3) {
+        Some(token) => parse::<usize>(token)? != 0,
+        None => false,
+    };
+
+    let mut output = NodeFile {
+        points: Vec::with_capacity(n_points),
+        attributes: Vec::with_capacity(n_points),
+        boundary_markers: if has_markers { Some(vec![]) } else { None },
+        first_index: 0,
+    };
+    for i in 0..n_points {
+        let tokens = lines
+            .next()
+            .ok_or_else(|| invalid_data(format!("expected {} points, found {}", n_points, i)))??;
+        let expected = 1 + N + n_attributes + has_markers as usize;
+        if tokens.len() < expected {
+            return Err(invalid_data(format!(
+                "point line {:?} has fewer than {} entries",
+                tokens, expected
+            )));
+        }
+        let index: usize = parse(&tokens[0])?;
+        if i == 0 {
+            output.first_index = index;
+        }
+        let mut point = [0.0; N];
+        for (k, coordinate) in point.iter_mut().enumerate() {
+            *coordinate = parse(&tokens[1 + k])?;
+        }
+        output.points.push(point);
+        output.attributes.push(
+            tokens[1 + N..1 + N + n_attributes]
+                .iter()
+                .map(|token| parse(token))
+                .collect::<io::Result<Vec<f64>>>()?,
+        );
+        if let Some(markers) = output.boundary_markers.as_mut() {
+            markers.push(parse(&tokens[1 + N + n_attributes])?);
+        }
+    }
+    Ok(output)
+}
+
+impl DelaunayTree<3, 4> {
+    pub fn from_node<R: BufRead>(reader: R) -> io::Result<Self> {
+        let nodes = read_node::<3, R>(reader)?;
+        let mut delaunay_tree = DelaunayTree::<3, 4>::new(nodes.points.clone());
+        delaunay_tree.insert_points(&nodes.points);
+        Ok(delaunay_tree)
+    }
+}
+
+impl DelaunayTree<2, 3> {
+    pub fn from_node<R: BufRead>(reader: R) -> io::Result<Self> {
+        let nodes = read_node::<2, R>(reader)?;
+        let mut delaunay_tree = DelaunayTree::<2, 3>::new(nodes.points.clone());
+        delaunay_tree.insert_points(&nodes.points);
+        Ok(delaunay_tree)
+    }
+}
+
+pub fn read_node_file<const N: usize, P: AsRef<Path>>(path: P) -> io::Result<NodeFile<N>> {
+    read_node(BufReader::new(File::open(path)?))
+}
+
+pub fn write_node<const N: usize, const M: usize, W: Write>(
+    tree: &DelaunayTree<N, M>,
+    mut writer: W,
+    first_index: usize,
+) -> io::Result<()> {
+    let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+    writeln!(writer, "{} {} 0 0", tree.vertices.len() - first, N)?;
+    for id in first..tree.vertices.len() {
+        let coordinates = tree.vertices.get(&id).unwrap().coordinates;
+        let line: Vec<String> = coordinates.iter().map(|x| format!("{:e}", x)).collect();
+        writeln!(writer, "{} {}", id - first + first_index, line.join(" "))?;
+    }
+    writer.flush()
+}
+
+pub fn write_ele<const N: usize, const M: usize, W: Write>(
+    tree: &DelaunayTree<N, M>,
+    mut writer: W,
+    first_index: usize,
+) -> io::Result<()> {
+    // Real simplices in increasing id order, all positively oriented
+    let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+    let simplices = tree.real_simplices();
+    writeln!(writer, "{} {} 0", simplices.len(), M)?;
+    for (i, id) in simplices.iter().enumerate() {
+        let vertices: Vec<String> = tree
+            .oriented_vertices(*id)
+            .iter()
+            .map(|vertex| (vertex - first + first_index).to_string())
+            .collect();
+        writeln!(writer, "{} {}", i + first_index, vertices.join(" "))?;
+    }
+    writer.flush()
+}
+
+pub fn write_neigh<const N: usize, const M: usize, W: Write>(
+    tree: &DelaunayTree<N, M>,
+    mut writer: W,
+    first_index: usize,
+) -> io::Result<()> {
+    // The k-th neighbor is opposite to the k-th vertex of the simplex in the .ele file,
+    // -1 marks a facet on the convex hull
+    let simplices = tree.real_simplices();
+    let index: HashMap<usize, usize> = simplices
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (*id, i))
+        .collect();
+    writeln!(writer, "{} {}", simplices.len(), M)?;
+    for (i, id) in simplices.iter().enumerate() {
+        let line: Vec<String> = tree
+            .oriented_vertices(*id)
+            .iter()
+            .map(|vertex| {
+                tree.opposite_neighbor(*id, *vertex)
+                    .and_then(|neighbor| index.get(&neighbor))
+                    .map_or("-1".to_string(), |k| (k + first_index).to_string())
+            })
+            .collect();
+        writeln!(writer, "{} {}", i + first_index, line.join(" "))?;
+    }
+    writer.flush()
+}
+
+pub fn write_face<const N: usize, const M: usize, W: Write>(
+    tree: &DelaunayTree<N, M>,
+    mut writer: W,
+    first_index: usize,
+    boundary_only: bool,
+) -> io::Result<()> {
+    // Facets of the real simplices (triangles in 3D, edges in 2D, i.e. Triangle's .edge),
+    // with boundary marker 1 on the convex hull and 0 inside
+    let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+    let mut facets: Vec<([usize; N], bool)> = vec![];
+    for id in tree.real_simplices() {
+        let vertices = tree.oriented_vertices(id);
+        for k in 0..M {
+            let neighbor = tree.opposite_neighbor(id, vertices[k]);
+            let on_hull = match neighbor {
+                Some(neighbor) => {
+                    !DelaunayTree::is_real_simplex(&tree.simplices.get(&neighbor).unwrap())
+                }
+                None => true,
+            };
+            // Interior facets are shared, write them from the simplex with the smaller id
+            if on_hull || (!boundary_only && id < neighbor.unwrap()) {
+                facets.push((DelaunayTree::<N, M>::facet(vertices, k), on_hull));
+            }
+        }
+    }
+    writeln!(writer, "{} 1", facets.len())?;
+    for (i, (facet, on_hull)) in facets.iter().enumerate() {
+        let vertices: Vec<String> = facet
+            .iter()
+            .map(|vertex| (vertex - first + first_index).to_string())
+            .collect();
+        writeln!(
+            writer,
+            "{} {} {}",
+            i + first_index,
+            vertices.join(" "),
+            *on_hull as u8
+        )?;
+    }
+    writer.flush()
+}
+
+pub fn write_files<const N: usize, const M: usize, P: AsRef<Path>>(
+    tree: &DelaunayTree<N, M>,
+    basename: P,
+    first_index: usize,
+) -> io::Result<()> {
+    // Writes basename.node, .ele, .neigh and the hull facets as .face (3D) or .edge (2D)
+    let basename = basename.as_ref();
+    let create = |extension: &str| -> io::Result<BufWriter<File>> {
+        let mut path = basename.as_os_str().to_owned();
+        path.push(".");
+        path.push(extension);
+        Ok(BufWriter::new(File::create(path)?))
+    };
+    write_node(tree, create("node")?, first_index)?;
+    write_ele(tree, create("ele")?, first_index)?;
+    write_neigh(tree, create("neigh")?, first_index)?;
+    let facet_extension = if N == 2 { "edge" } else { "face" };
+    write_face(tree, create(facet_extension)?, first_index, true)
+}
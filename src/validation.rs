@@ -0,0 +1,252 @@
+use std::fmt;
+
+use kiddo::SquaredEuclidean;
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+use crate::geometry::signed_volume;
+
+// Topological and geometric consistency checks of a DelaunayTree. Ghost simplices are
+// only checked for adjacency and vertex stars. Every other simplex, including those
+// touching the super-simplex, must have an empty circumsphere: Bowyer-Watson keeps the
+// Delaunay triangulation of the input points and the super-simplex corners, which are
+// ordinary points at a finite distance. The ghost vertices repeat the corners and are
+// not checked against.
+
+// A vertex counts as inside a circumsphere if its squared distance to the center is
+// below (1 - RELATIVE_TOLERANCE) times the squared radius
+const RELATIVE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    // Simplex id above max_simplex_id
+    SimplexIdOutOfRange {
+        simplex: usize,
+    },
+    // Vertex ids must be 0..n_vertices
+    VertexIdOutOfRange {
+        vertex: usize,
+    },
+    MissingVertex {
+        simplex: usize,
+        vertex: usize,
+    },
+    MissingNeighbor {
+        simplex: usize,
+        neighbor: usize,
+    },
+    // The neighbor does not list the simplex as its neighbor
+    AsymmetricNeighbor {
+        simplex: usize,
+        neighbor: usize,
+    },
+    // Neighbors must share exactly N vertices
+    NotAdjacent {
+        simplex: usize,
+        neighbor: usize,
+        shared: usize,
+    },
+    // Every simplex but the ghosts has N + 1 distinct neighbors
+    NeighborCount {
+        simplex: usize,
+        count: usize,
+    },
+    // The simplex contains the vertex but is not in its Vertex::simplex list
+    MissingFromStar {
+        vertex: usize,
+        simplex: usize,
+    },
+    // Vertex::simplex lists a simplex that does not exist or does not contain the vertex
+    ExtraInStar {
+        vertex: usize,
+        simplex: usize,
+    },
+    NotPositivelyOriented {
+        simplex: usize,
+        volume: f64,
+    },
+    NonEmptyCircumsphere {
+        simplex: usize,
+        vertex: usize,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub n_vertices: usize,
+    pub n_simplices: usize,
+    // Sorted by simplex id, then by vertex id
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} vertices, {} simplices, {} violations",
+            self.n_vertices,
+            self.n_simplices,
+            self.violations.len()
+        )?;
+        for violation in &self.violations {
+            writeln!(f, "  {:?}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    pub fn validate(&self) -> ValidationReport {
+        let n_vertices = self.vertices.len();
+        let mut simplex_ids: Vec<usize> = self
+            .simplices
+            .iter()
+            .map(|simplex| *simplex.key())
+            .collect();
+        simplex_ids.sort_unstable();
+        let mut vertex_ids: Vec<usize> = self.vertices.iter().map(|vertex| *vertex.key()).collect();
+        vertex_ids.sort_unstable();
+
+        let mut violations: Vec<Violation> = simplex_ids
+            .par_iter()
+            .flat_map_iter(|&id| self.check_simplex(id))
+            .collect();
+        violations.par_extend(
+            vertex_ids
+                .par_iter()
+                .flat_map_iter(|&id| self.check_vertex(id, n_vertices)),
+        );
+        ValidationReport {
+            n_vertices,
+            n_simplices: simplex_ids.len(),
+            violations,
+        }
+    }
+
+    pub fn check_delaunay(&self) -> bool {
+        self.validate().is_valid()
+    }
+
+    fn check_simplex(&self, id: usize) -> Vec<Violation> {
+        let mut violations = vec![];
+        let simplex = self.simplices.get(&id).unwrap();
+        if id > self.max_simplex_id {
+            violations.push(Violation::SimplexIdOutOfRange { simplex: id });
+        }
+
+        let mut complete = true;
+        for &vertex in &simplex.vertices {
+            match self.vertices.get(&vertex) {
+                Some(star) if !star.simplex.contains(&id) => {
+                    violations.push(Violation::MissingFromStar {
+                        vertex,
+                        simplex: id,
+                    })
+                }
+                Some(_) => {}
+                None => {
+                    complete = false;
+                    violations.push(Violation::MissingVertex {
+                        simplex: id,
+                        vertex,
+                    });
+                }
+            }
+        }
+
+        let ghost = Self::is_ghost_simplex(&simplex);
+        let mut neighbors = simplex.neighbors.clone();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        if !ghost && neighbors.len() != M {
+            violations.push(Violation::NeighborCount {
+                simplex: id,
+                count: neighbors.len(),
+            });
+        }
+        for &neighbor in &neighbors {
+            let Some(other) = self.simplices.get(&neighbor) else {
+                violations.push(Violation::MissingNeighbor {
+                    simplex: id,
+                    neighbor,
+                });
+                continue;
+            };
+            if !other.neighbors.contains(&id) {
+                violations.push(Violation::AsymmetricNeighbor {
+                    simplex: id,
+                    neighbor,
+                });
+            }
+            let shared = other
+                .vertices
+                .iter()
+                .filter(|vertex| simplex.vertices.contains(vertex))
+                .count();
+            if shared != N {
+                violations.push(Violation::NotAdjacent {
+                    simplex: id,
+                    neighbor,
+                    shared,
+                });
+            }
+        }
+
+        if ghost || !complete {
+            return violations;
+        }
+        let volume = signed_volume(self.simplex_coordinates(&simplex));
+        if volume <= 0.0 {
+            violations.push(Violation::NotPositivelyOriented {
+                simplex: id,
+                volume,
+            });
+        }
+        let threshold = simplex.radius * simplex.radius * (1.0 - RELATIVE_TOLERANCE);
+        let mut inside: Vec<usize> = self
+            .kdtree
+            .within_unsorted::<SquaredEuclidean>(&simplex.center, threshold)
+            .into_iter()
+            .filter(|neighbour| neighbour.distance < threshold)
+            .map(|neighbour| neighbour.item as usize)
+            .filter(|&vertex| !Self::is_ghost_vertex(vertex) && !simplex.vertices.contains(&vertex))
+            .collect();
+        inside.sort_unstable();
+        violations.extend(
+            inside
+                .into_iter()
+                .map(|vertex| Violation::NonEmptyCircumsphere {
+                    simplex: id,
+                    vertex,
+                }),
+        );
+        violations
+    }
+
+    fn check_vertex(&self, id: usize, n_vertices: usize) -> Vec<Violation> {
+        let mut violations = vec![];
+        if id >= n_vertices {
+            violations.push(Violation::VertexIdOutOfRange { vertex: id });
+        }
+        let vertex = self.vertices.get(&id).unwrap();
+        for &simplex in &vertex.simplex {
+            let contains = self
+                .simplices
+                .get(&simplex)
+                .is_some_and(|other| other.vertices.contains(&id));
+            if !contains {
+                violations.push(Violation::ExtraInStar {
+                    vertex: id,
+                    simplex,
+                });
+            }
+        }
+        violations
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+
+use rayon::prelude::*;
+
+use crate::delaunay_tree::DelaunayTree;
+
+#[derive(Debug, Clone)]
+pub struct VoronoiFace {
+    // Generator on the other side of the face
+    pub neighbor: usize,
+    // Indices into VoronoiCell::vertices, ordered around the face
+    pub vertices: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VoronoiCell<const N: usize> {
+    pub generator: usize,
+    pub site: [f64; N],
+    // The cell vertices are the circumcenters of these simplices
+    pub simplices: Vec<usize>,
+    pub vertices: Vec<[f64; N]>,
+    pub faces: Vec<VoronoiFace>,
+    // False if the cell touches the super-simplex, i.e. the generator is on the convex hull
+    pub bounded: bool,
+}
+
+impl<const N: usize> VoronoiCell<N> {
+    pub fn volume(&self) -> f64 {
+        // Area in 2D, volume in 3D. Only meaningful for bounded cells.
+        if N == 2 {
+            let n = self.vertices.len();
+            (0..n)
+                .map(|i| {
+                    let a = self.vertices[i];
+                    let b = self.vertices[(i + 1) % n];
+                    a[0] * b[1] - a[1] * b[0]
+                })
+                .sum::<f64>()
+                .abs()
+                / 2.0
+        } else {
+            let mut volume = 0.0;
+            for face in &self.faces {
+                let a = sub(self.vertices[face.vertices[0]], self.site);
+                for k in 1..face.vertices.len().saturating_sub(1) {
+                    let b = sub(self.vertices[face.vertices[k]], self.site);
+                    let c = sub(self.vertices[face.vertices[k + 1]], self.site);
+                    volume += (a[0] * (b[1] * c[2] - b[2] * c[1])
+                        - a[1] * (b[0] * c[2] - b[2] * c[0])
+                        + a[2] * (b[0] * c[1] - b[1] * c[0]))
+                        .abs()
+                        / 6.0;
+                }
+            }
+            volume
+        }
+    }
+}
+
+fn sub<const N: usize>(a: [f64; N], b: [f64; N]) -> [f64; N] {
+    let mut output = [0.0; N];
+    for i in 0..N {
+        output[i] = a[i] - b[i];
+    }
+    output
+}
+
+impl<const N: usize, const M: usize> DelaunayTree<N, M> {
+    fn order_ring(&self, ids: &[usize]) -> (Vec<usize>, bool) {
+        // Order simplices so that consecutive ones are neighbors.
+        // Returns the ordering and whether it closes into a cycle.
+        let adjacent: Vec<Vec<usize>> = ids
+            .iter()
+            .map(|id| {
+                self.simplices
+                    .get(id)
+                    .unwrap()
+                    .neighbors
+                    .iter()
+                    .filter(|neighbor| ids.contains(neighbor))
+                    .copied()
+                    .collect()
+            })
+            .collect();
+        let start = (0..ids.len()).find(|&i| adjacent[i].len() < 2).unwrap_or(0);
+        let mut ring = vec![ids[start]];
+        let mut current = start;
+        loop {
+            let next = adjacent[current]
+                .iter()
+                .find(|neighbor| !ring.contains(neighbor));
+            match next {
+                Some(next) => {
+                    ring.push(*next);
+                    current = ids.iter().position(|id| id == next).unwrap();
+                }
+                None => break,
+            }
+        }
+        let closed = ring.len() > 2 && adjacent[current].contains(&ring[0]);
+        (ring, closed)
+    }
+
+    pub fn voronoi_cell(&self, vertex_id: usize) -> VoronoiCell<N> {
+        let vertex = self.vertices.get(&vertex_id).unwrap();
+        let site = vertex.coordinates;
+        let mut star = vertex.simplex.clone();
+        drop(vertex);
+        star.sort_unstable();
+        let bounded = star
+            .iter()
+            .all(|id| Self::is_real_simplex(&self.simplices.get(id).unwrap()));
+
+        let mut faces = vec![];
+        if N == 2 {
+            // The cell is a polygon whose vertices follow the triangles around the generator
+            let (ring, closed) = self.order_ring(&star);
+            star = ring;
+            let n_edges = if closed { star.len() } else { star.len() - 1 };
+            for k in 0..n_edges {
+                let a = self.simplices.get(&star[k]).unwrap().vertices;
+                let b = self
+                    .simplices
+                    .get(&star[(k + 1) % star.len()])
+                    .unwrap()
+                    .vertices;
+                let neighbor = *a
+                    .iter()
+                    .find(|&&id| id != vertex_id && b.contains(&id))
+                    .unwrap();
+                faces.push(VoronoiFace {
+                    neighbor,
+                    vertices: vec![k, (k + 1) % star.len()],
+                });
+            }
+        } else {
+            // One face per Delaunay edge, its vertices follow the simplices around that edge
+            let mut edges: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+            for id in &star {
+                for &other in self.simplices.get(id).unwrap().vertices.iter() {
+                    if other != vertex_id {
+                        edges.entry(other).or_default().push(*id);
+                    }
+                }
+            }
+            for (neighbor, ids) in edges {
+                let (ring, _) = self.order_ring(&ids);
+                faces.push(VoronoiFace {
+                    neighbor,
+                    vertices: ring
+                        .iter()
+                        .map(|id| star.binary_search(id).unwrap())
+                        .collect(),
+                });
+            }
+        }
+
+        let vertices: Vec<[f64; N]> = star
+            .iter()
+            .map(|id| self.simplices.get(id).unwrap().center)
+            .collect();
+        let mut cell = VoronoiCell {
+            generator: vertex_id,
+            site,
+            simplices: star,
+            vertices,
+            faces,
+            bounded,
+        };
+        cell.orient();
+        cell
+    }
+
+    pub fn voronoi_cells(&self) -> Vec<VoronoiCell<N>> {
+        // Cells of all input points, in vertex id order
+        (Self::N_SUPER_VERTICES..self.vertices.len())
+            .into_par_iter()
+            .map(|id| self.voronoi_cell(id))
+            .collect()
+    }
+}
+
+impl<const N: usize> VoronoiCell<N> {
+    fn orient(&mut self) {
+        // Counter-clockwise polygons in 2D, outward facing faces in 3D
+        if N == 2 {
+            let n = self.vertices.len();
+            let area: f64 = (0..n)
+                .map(|i| {
+                    let a = self.vertices[i];
+                    let b = self.vertices[(i + 1) % n];
+                    a[0] * b[1] - a[1] * b[0]
+                })
+                .sum();
+            if area < 0.0 {
+                self.simplices.reverse();
+                self.vertices.reverse();
+                for face in self.faces.iter_mut() {
+                    for index in face.vertices.iter_mut() {
+                        *index = n - 1 - *index;
+                    }
+                    face.vertices.reverse();
+                }
+            }
+        } else if N == 3 {
+            for face in self.faces.iter_mut() {
+                // Newell normal of the face polygon
+                let mut normal = [0.0; 3];
+                let mut centroid = [0.0; 3];
+                let n = face.vertices.len();
+                for k in 0..n {
+                    let a = self.vertices[face.vertices[k]];
+                    let b = self.vertices[face.vertices[(k + 1) % n]];
+                    normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+                    normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+                    normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+                    for i in 0..3 {
+                        centroid[i] += a[i] / n as f64;
+                    }
+                }
+                let outward: f64 = (0..3)
+                    .map(|i| normal[i] * (centroid[i] - self.site[i]))
+                    .sum();
+                if outward < 0.0 {
+                    face.vertices.reverse();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::delaunay_tree::DelaunayTree;
+use crate::geometry::simplex_volume;
+
+pub const VTK_TRIANGLE: u8 = 5;
+pub const VTK_POLYGON: u8 = 7;
+pub const VTK_TETRA: u8 = 10;
+pub const VTK_POLYHEDRON: u8 = 42;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VtkFormat {
+    // Legacy .vtk files
+    LegacyAscii,
+    LegacyBinary,
+    // XML .vtu files, binary data goes into a raw appended section
+    XmlAscii,
+    XmlBinary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MeshKind {
+    Delaunay,
+    Voronoi,
+}
+
+#[derive(Debug, Clone)]
+pub struct VtkMesh {
+    kind: MeshKind,
+    pub points: Vec<[f64; 3]>,
+    // Vertex ids of the points for a Delaunay mesh, simplex ids for a Voronoi mesh
+    pub point_ids: Vec<usize>,
+    pub cells: Vec<Vec<usize>>,
+    pub cell_types: Vec<u8>,
+    // Face lists of polyhedral cells, empty for all other cell types
+    pub cell_faces: Vec<Vec<Vec<usize>>>,
+    // Simplex ids of the cells for a Delaunay mesh, generator vertex ids for a Voronoi mesh
+    pub cell_ids: Vec<usize>,
+    pub point_data: Vec<(String, Vec<f64>)>,
+    pub cell_data: Vec<(String, Vec<f64>)>,
+}
+
+fn pad<const N: usize>(coordinates: [f64; N]) -> [f64; 3] {
+    let mut output = [0.0; 3];
+    output[..N].copy_from_slice(&coordinates);
+    output
+}
+
+impl VtkMesh {
+    pub fn delaunay<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>) -> Self {
+        // Only input points and the simplices between them, the super-simplex is dropped
+        let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+        let point_ids: Vec<usize> = (first..tree.vertices.len()).collect();
+        let points = point_ids
+            .iter()
+            .map(|id| pad(tree.vertices.get(id).unwrap().coordinates))
+            .collect();
+        let cell_ids = tree.real_simplices();
+        let cells: Vec<Vec<usize>> = cell_ids
+            .iter()
+            .map(|id| {
+                tree.simplices
+                    .get(id)
+                    .unwrap()
+                    .vertices
+                    .iter()
+                    .map(|vertex| vertex - first)
+                    .collect()
+            })
+            .collect();
+        let cell_type = if N == 2 { VTK_TRIANGLE } else { VTK_TETRA };
+        VtkMesh {
+            kind: MeshKind::Delaunay,
+            points,
+            point_ids,
+            cell_types: vec![cell_type; cells.len()],
+            cell_faces: vec![vec![]; cells.len()],
+            cells,
+            cell_ids,
+            point_data: vec![],
+            cell_data: vec![],
+        }
+    }
+
+    pub fn voronoi<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>) -> Self {
+        // Bounded Voronoi cells as polygons (2D) or polyhedra (3D)
+        let voronoi_cells: Vec<_> = tree
+            .voronoi_cells()
+            .into_iter()
+            .filter(|cell| cell.bounded)
+            .collect();
+
+        let mut point_index: BTreeMap<usize, usize> = BTreeMap::new();
+        for cell in &voronoi_cells {
+            for id in &cell.simplices {
+                point_index.insert(*id, 0);
+            }
+        }
+        let mut point_ids = Vec::with_capacity(point_index.len());
+        let mut points = Vec::with_capacity(point_index.len());
+        for (i, (id, index)) in point_index.iter_mut().enumerate() {
+            *index = i;
+            point_ids.push(*id);
+            points.push(pad(tree.simplices.get(id).unwrap().center));
+        }
+
+        let mut mesh = VtkMesh {
+            kind: MeshKind::Voronoi,
+            points,
+            point_ids,
+            cells: vec![],
+            cell_types: vec![],
+            cell_faces: vec![],
+            cell_ids: vec![],
+            point_data: vec![],
+            cell_data: vec![],
+        };
+        for cell in &voronoi_cells {
+            let local: Vec<usize> = cell.simplices.iter().map(|id| point_index[id]).collect();
+            mesh.cells.push(local.clone());
+            mesh.cell_ids.push(cell.generator);
+            if N == 2 {
+                mesh.cell_types.push(VTK_POLYGON);
+                mesh.cell_faces.push(vec![]);
+            } else {
+                mesh.cell_types.push(VTK_POLYHEDRON);
+                mesh.cell_faces.push(
+                    cell.faces
+                        .iter()
+                        .map(|face| face.vertices.iter().map(|&k| local[k]).collect())
+                        .collect(),
+                );
+            }
+        }
+        mesh
+    }
+
+    pub fn with_point_data(mut self, name: &str, values: Vec<f64>) -> Self {
+        assert_eq!(
+            values.len(),
+            self.points.len(),
+            "point data {:?} has the wrong length",
+            name
+        );
+        self.point_data.push((name.to_string(), values));
+        self
+    }
+
+    pub fn with_cell_data(mut self, name: &str, values: Vec<f64>) -> Self {
+        assert_eq!(
+            values.len(),
+            self.cells.len(),
+            "cell data {:?} has the wrong length",
+            name
+        );
+        self.cell_data.push((name.to_string(), values));
+        self
+    }
+
+    pub fn with_radius<const N: usize, const M: usize>(self, tree: &DelaunayTree<N, M>) -> Self {
+        // Circumradius of the simplices, or of the Delaunay simplices at the Voronoi vertices
+        let radius = match self.kind {
+            MeshKind::Delaunay => self
+                .cell_ids
+                .iter()
+                .map(|id| tree.simplices.get(id).unwrap().radius)
+                .collect(),
+            MeshKind::Voronoi => self
+                .point_ids
+                .iter()
+                .map(|id| tree.simplices.get(id).unwrap().radius)
+                .collect(),
+        };
+        match self.kind {
+            MeshKind::Delaunay => self.with_cell_data("radius", radius),
+            MeshKind::Voronoi => self.with_point_data("radius", radius),
+        }
+    }
+
+    pub fn with_volume<const N: usize, const M: usize>(self, tree: &DelaunayTree<N, M>) -> Self {
+        let volume = match self.kind {
+            MeshKind::Delaunay => self
+                .cell_ids
+                .iter()
+                .map(|id| {
+                    let simplex = tree.simplices.get(id).unwrap();
+                    simplex_volume(tree.simplex_coordinates(&simplex))
+                })
+                .collect(),
+            MeshKind::Voronoi => self
+                .cell_ids
+                .iter()
+                .map(|id| tree.voronoi_cell(*id).volume())
+                .collect(),
+        };
+        self.with_cell_data("volume", volume)
+    }
+
+    fn face_stream(&self, cell: usize) -> Vec<usize> {
+        // nFaces, then nPoints followed by the point indices of every face
+        let faces = &self.cell_faces[cell];
+        let mut stream = vec![faces.len()];
+        for face in faces {
+            stream.push(face.len());
+            stream.extend(face);
+        }
+        stream
+    }
+
+    pub fn write<W: Write>(&self, writer: W, format: VtkFormat) -> io::Result<()> {
+        match format {
+            VtkFormat::LegacyAscii => self.write_legacy(writer, false),
+            VtkFormat::LegacyBinary => self.write_legacy(writer, true),
+            VtkFormat::XmlAscii => self.write_xml(writer, false),
+            VtkFormat::XmlBinary => self.write_xml(writer, true),
+        }
+    }
+
+    fn write_legacy<W: Write>(&self, mut writer: W, binary: bool) -> io::Result<()> {
+        // Version 4.2 stores polyhedra inline as face streams in the CELLS section
+        writeln!(writer, "# vtk DataFile Version 4.2")?;
+        writeln!(writer, "voronoids")?;
+        writeln!(writer, "{}", if binary { "BINARY" } else { "ASCII" })?;
+        writeln!(writer, "DATASET UNSTRUCTURED_GRID")?;
+
+        writeln!(writer, "POINTS {} double", self.points.len())?;
+        let coordinates: Vec<f64> = self.points.iter().flatten().copied().collect();
+        write_legacy_values(&mut writer, &coordinates, 3, binary)?;
+
+        let cells: Vec<Vec<usize>> = (0..self.cells.len())
+            .map(|i| {
+                let entries = if self.cell_types[i] == VTK_POLYHEDRON {
+                    self.face_stream(i)
+                } else {
+                    self.cells[i].clone()
+                };
+                let mut cell = vec![entries.len()];
+                cell.extend(entries);
+                cell
+            })
+            .collect();
+        let size: usize = cells.iter().map(|cell| cell.len()).sum();
+        writeln!(writer, "CELLS {} {}", cells.len(), size)?;
+        if binary {
+            for value in cells.iter().flatten() {
+                writer.write_all(&(*value as i32).to_be_bytes())?;
+            }
+            writeln!(writer)?;
+        } else {
+            for cell in &cells {
+                let line: Vec<String> = cell.iter().map(|value| value.to_string()).collect();
+                writeln!(writer, "{}", line.join(" "))?;
+            }
+        }
+
+        writeln!(writer, "CELL_TYPES {}", self.cell_types.len())?;
+        if binary {
+            for cell_type in &self.cell_types {
+                writer.write_all(&(*cell_type as i32).to_be_bytes())?;
+            }
+            writeln!(writer)?;
+        } else {
+            for cell_type in &self.cell_types {
+                writeln!(writer, "{}", cell_type)?;
+            }
+        }
+
+        if !self.cell_data.is_empty() {
+            writeln!(writer, "CELL_DATA {}", self.cells.len())?;
+            for (name, values) in &self.cell_data {
+                writeln!(writer, "SCALARS {} double 1", sanitize(name))?;
+                writeln!(writer, "LOOKUP_TABLE default")?;
+                write_legacy_values(&mut writer, values, 1, binary)?;
+            }
+        }
+        if !self.point_data.is_empty() {
+            writeln!(writer, "POINT_DATA {}", self.points.len())?;
+            for (name, values) in &self.point_data {
+                writeln!(writer, "SCALARS {} double 1", sanitize(name))?;
+                writeln!(writer, "LOOKUP_TABLE default")?;
+                write_legacy_values(&mut writer, values, 1, binary)?;
+            }
+        }
+        writer.flush()
+    }
+
+    fn write_xml<W: Write>(&self, mut writer: W, binary: bool) -> io::Result<()> {
+        let mut xml = XmlArrays {
+            binary,
+            body: String::new(),
+            appended: vec![],
+        };
+
+        xml.body.push_str(&format!(
+            "    <Piece NumberOfPoints=\"{}\" NumberOfCells=\"{}\">\n",
+            self.points.len(),
+            self.cells.len()
+        ));
+        if !self.point_data.is_empty() {
+            xml.body.push_str("      <PointData>\n");
+            for (name, values) in &self.point_data {
+                xml.add(Some(name), 1, Array::F64(values));
+            }
+            xml.body.push_str("      </PointData>\n");
+        }
+        if !self.cell_data.is_empty() {
+            xml.body.push_str("      <CellData>\n");
+            for (name, values) in &self.cell_data {
+                xml.add(Some(name), 1, Array::F64(values));
+            }
+            xml.body.push_str("      </CellData>\n");
+        }
+
+        xml.body.push_str("      <Points>\n");
+        let coordinates: Vec<f64> = self.points.iter().flatten().copied().collect();
+        xml.add(None, 3, Array::F64(&coordinates));
+        xml.body.push_str("      </Points>\n");
+
+        xml.body.push_str("      <Cells>\n");
+        let connectivity: Vec<i64> = self.cells.iter().flatten().map(|&i| i as i64).collect();
+        let offsets: Vec<i64> = self
+            .cells
+            .iter()
+            .scan(0, |end, cell| {
+                *end += cell.len() as i64;
+                Some(*end)
+            })
+            .collect();
+        xml.add(Some("connectivity"), 1, Array::I64(&connectivity));
+        xml.add(Some("offsets"), 1, Array::I64(&offsets));
+        xml.add(Some("types"), 1, Array::U8(&self.cell_types));
+        if self.cell_types.contains(&VTK_POLYHEDRON) {
+            let mut faces: Vec<i64> = vec![];
+            let mut face_offsets: Vec<i64> = vec![];
+            for i in 0..self.cells.len() {
+                if self.cell_types[i] == VTK_POLYHEDRON {
+                    faces.extend(self.face_stream(i).iter().map(|&value| value as i64));
+                    face_offsets.push(faces.len() as i64);
+                } else {
+                    face_offsets.push(-1);
+                }
+            }
+            xml.add(Some("faces"), 1, Array::I64(&faces));
+            xml.add(Some("faceoffsets"), 1, Array::I64(&face_offsets));
+        }
+        xml.body.push_str("      </Cells>\n");
+        xml.body.push_str("    </Piece>\n");
+
+        writeln!(writer, "<?xml version=\"1.0\"?>")?;
+        writeln!(
+            writer,
+            "<VTKFile type=\"UnstructuredGrid\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\">"
+        )?;
+        writeln!(writer, "  <UnstructuredGrid>")?;
+        writer.write_all(xml.body.as_bytes())?;
+        writeln!(writer, "  </UnstructuredGrid>")?;
+        if binary {
+            write!(writer, "  <AppendedData encoding=\"raw\">\n   _")?;
+            writer.write_all(&xml.appended)?;
+            writeln!(writer, "\n  </AppendedData>")?;
+        }
+        writeln!(writer, "</VTKFile>")?;
+        writer.flush()
+    }
+}
+
+fn sanitize(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<&str>>().join("_")
+}
+
+fn write_legacy_values<W: Write>(
+    writer: &mut W,
+    values: &[f64],
+    per_line: usize,
+    binary: bool,
+) -> io::Result<()> {
+    // Legacy binary data is big endian
+    if binary {
+        for value in values {
+            writer.write_all(&value.to_be_bytes())?;
+        }
+        writeln!(writer)?;
+    } else {
+        for chunk in values.chunks(per_line) {
+            let line: Vec<String> = chunk.iter().map(|value| value.to_string()).collect();
+            writeln!(writer, "{}", line.join(" "))?;
+        }
+    }
+    Ok(())
+}
+
+enum Array<'a> {
+    F64(&'a [f64]),
+    I64(&'a [i64]),
+    U8(&'a [u8]),
+}
+
+impl<'a> Array<'a> {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Array::F64(_) => "Float64",
+            Array::I64(_) => "Int64",
+            Array::U8(_) => "UInt8",
+        }
+    }
+
+    fn to_ascii(&self) -> String {
+        let values: Vec<String> = match self {
+            Array::F64(values) => values.iter().map(|value| value.to_string()).collect(),
+            Array::I64(values) => values.iter().map(|value| value.to_string()).collect(),
+            Array::U8(values) => values.iter().map(|value| value.to_string()).collect(),
+        };
+        values.join(" ")
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Array::F64(values) => values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+            Array::I64(values) => values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+            Array::U8(values) => values.to_vec(),
+        }
+    }
+}
+
+struct XmlArrays {
+    binary: bool,
+    body: String,
+    appended: Vec<u8>,
+}
+
+impl XmlArrays {
+    fn add(&mut self, name: Option<&str>, components: usize, array: Array) {
+        let mut tag = format!("        <DataArray type=\"{}\"", array.type_name());
+        if let Some(name) = name {
+            tag.push_str(&format!(" Name=\"{}\"", sanitize(name)));
+        }
+        if components > 1 {
+            tag.push_str(&format!(" NumberOfComponents=\"{}\"", components));
+        }
+        if self.binary {
+            // Each appended block is prefixed with its size in bytes
+            tag.push_str(&format!(
+                " format=\"appended\" offset=\"{}\"/>\n",
+                self.appended.len()
+            ));
+            let bytes = array.to_bytes();
+            self.appended
+                .extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            self.appended.extend(bytes);
+            self.body.push_str(&tag);
+        } else {
+            self.body.push_str(&tag);
+            self.body.push_str(" format=\"ascii\">\n          ");
+            self.body.push_str(&array.to_ascii());
+            self.body.push_str("\n        </DataArray>\n");
+        }
+    }
+}
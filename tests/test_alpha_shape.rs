@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::geometry::simplex_volume;
+use voronoids::points::uniform_box;
+
+fn check_filtration<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>) {
+    // Every face comes after its own facets, with an alpha value at least theirs
+    let filtration = tree.alpha_filtration();
+    let position: HashMap<Vec<usize>, usize> = filtration
+        .iter()
+        .enumerate()
+        .map(|(i, face)| (face.vertices.clone(), i))
+        .collect();
+    assert_eq!(position.len(), filtration.len());
+    for (i, face) in filtration.iter().enumerate() {
+        if face.dimension() == 0 {
+            assert_eq!(face.alpha, 0.0);
+            continue;
+        }
+        for skip in 0..face.vertices.len() {
+            let mut facet = face.vertices.clone();
+            facet.remove(skip);
+            let j = position[&facet];
+            assert!(j < i);
+            assert!(filtration[j].alpha <= face.alpha);
+        }
+    }
+    let counts: Vec<usize> = (0..M)
+        .map(|d| filtration.iter().filter(|f| f.dimension() == d).count())
+        .collect();
+    assert_eq!(
+        counts[0],
+        tree.vertices.len() - DelaunayTree::<N, M>::N_SUPER_VERTICES
+    );
+    assert_eq!(counts[N], tree.real_simplices().len());
+    // Euler characteristic of a triangulated ball
+    let euler: i64 = counts
+        .iter()
+        .enumerate()
+        .map(|(d, count)| {
+            if d % 2 == 0 {
+                *count as i64
+            } else {
+                -(*count as i64)
+            }
+        })
+        .sum();
+    assert_eq!(euler, 1);
+}
+
+#[test]
+fn test_filtration() {
+    let points = uniform_box(500, [0.0; 3], [1.0; 3], 0);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    check_filtration(&tree);
+
+    let points = uniform_box(300, [0.0; 2], [1.0; 2], 1);
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    check_filtration(&tree);
+}
+
+#[test]
+fn test_attachment() {
+    // In an obtuse triangle the longest edge is attached and enters with the triangle
+    let points = vec![[0.1, 0.2], [4.3, 1.1], [2.0, 1.3]];
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    let first = DelaunayTree::<2, 3>::N_SUPER_VERTICES;
+    let triangle = tree.real_simplices()[0];
+    let radius = tree.simplices.get(&triangle).unwrap().radius;
+    let filtration = tree.alpha_filtration();
+    assert_eq!(filtration.len(), 7);
+    let length = |a: usize, b: usize| {
+        ((points[a][0] - points[b][0]).powi(2) + (points[a][1] - points[b][1]).powi(2)).sqrt()
+    };
+    for face in filtration.iter().filter(|face| face.dimension() == 1) {
+        let (a, b) = (face.vertices[0] - first, face.vertices[1] - first);
+        if (a, b) == (0, 1) {
+            assert!(face.attached);
+            assert_eq!(face.alpha, radius);
+        } else {
+            assert!(!face.attached);
+            assert!((face.alpha - length(a, b) / 2.0).abs() < 1e-12);
+        }
+    }
+    let complex = tree.alpha_complex(length(0, 2) / 2.0 + 1e-9);
+    assert_eq!(complex.faces.len(), 4);
+    assert!(complex.simplices.is_empty());
+    let complex = tree.alpha_complex(radius);
+    assert_eq!(complex.faces.len(), 7);
+    assert_eq!(complex.simplices, vec![triangle]);
+
+    // Cutting the filtration at the alpha values of its own faces and in between
+    for alpha in [0.0, length(0, 2) / 2.0, 1.5, radius, 10.0] {
+        let complex = tree.alpha_complex(alpha);
+        let from = tree.alpha_complex_from(&filtration, alpha);
+        assert_eq!(from.faces, complex.faces);
+        assert_eq!(from.simplices, complex.simplices);
+    }
+}
+
+#[test]
+fn test_alpha_shape_surface() {
+    let points = uniform_box(800, [0.0; 3], [1.0; 3], 2);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+
+    // For a large alpha the boundary is the convex hull
+    let mut boundary = tree.alpha_shape_boundary(f64::INFINITY);
+    let mut hull = tree.hull_facets();
+    boundary.sort_unstable();
+    hull.sort_unstable();
+    assert_eq!(boundary, hull);
+    assert!(tree.alpha_shape_surface(0.0).facets.is_empty());
+
+    for alpha in [0.08, 0.1, 0.15] {
+        let mesh = tree.alpha_shape_surface(alpha);
+        // The outward surface encloses the volume of the simplices of the complex
+        let enclosed: f64 = mesh
+            .facets
+            .iter()
+            .map(|facet| {
+                let [a, b, c] = facet.map(|i| mesh.points[i]);
+                (a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+                    + a[2] * (b[0] * c[1] - b[1] * c[0]))
+                    / 6.0
+            })
+            .sum();
+        let volume: f64 = tree
+            .alpha_simplices(alpha)
+            .iter()
+            .map(|id| simplex_volume(tree.simplex_coordinates(&tree.simplices.get(id).unwrap())))
+            .sum();
+        assert!(volume > 0.0);
+        assert!((enclosed - volume).abs() < 1e-9, "{} {}", enclosed, volume);
+        for (point, index) in mesh.points.iter().zip(mesh.point_indices.iter()) {
+            assert_eq!(*point, points[*index]);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn voronoids(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_voronoids"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_text_input() {
+    // The corners of a square around its center give 4 triangles
+    let input = b"0 0\n1 0\n0 1\n1 1\n0.5 0.5\n";
+    let output = voronoids(&["-", "--quiet"], input);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let simplices: Vec<Vec<usize>> = stdout
+        .lines()
+        .map(|line| line.split(' ').map(|x| x.parse().unwrap()).collect())
+        .collect();
+    assert_eq!(simplices.len(), 4);
+    for simplex in &simplices {
+        assert_eq!(simplex.len(), 3);
+        assert!(simplex.contains(&4));
+        assert!(simplex.iter().all(|&vertex| vertex < 5));
+    }
+
+    let output = voronoids(&["-", "--quiet", "--format", "neighbors"], input);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 5);
+
+    // A thread count only changes how the points are inserted
+    let output = voronoids(&["-", "--quiet", "--threads", "2"], input);
+    assert_eq!(output.status.code(), Some(0));
+    let mut lines: Vec<Vec<usize>> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let mut simplex: Vec<usize> = line.split(' ').map(|x| x.parse().unwrap()).collect();
+            simplex.sort();
+            simplex
+        })
+        .collect();
+    lines.sort();
+    let mut expected: Vec<Vec<usize>> = simplices
+        .into_iter()
+        .map(|mut simplex| {
+            simplex.sort();
+            simplex
+        })
+        .collect();
+    expected.sort();
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn test_invalid_input() {
+    let path = std::env::temp_dir().join("voronoids_test_cli.bin");
+    // Two 2D points and a stray byte
+    let mut bytes: Vec<u8> = [0.0f64, 0.0, 1.0, 0.0]
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect();
+    bytes.push(0);
+    std::fs::write(&path, &bytes).unwrap();
+    let output = voronoids(&[path.to_str().unwrap(), "--dim", "2"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("float64"));
+
+    // Three coordinates do not make 2D points
+    bytes.pop();
+    bytes.extend(2.0f64.to_le_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+    let output = voronoids(&[path.to_str().unwrap(), "--dim", "2"], b"");
+    assert_eq!(output.status.code(), Some(1));
+    std::fs::remove_file(&path).unwrap();
+
+    let output = voronoids(&["-"], b"0 0\n1 0 2\n");
+    assert_eq!(output.status.code(), Some(1));
+
+    // One header line is skipped, a second unparsable line is reported
+    let output = voronoids(&["-", "--quiet"], b"# points\nx y\n0 0\n1 0\n0 1\n");
+    assert_eq!(output.status.code(), Some(0));
+    let output = voronoids(&["-"], b"x y\nfoo bar\n0 0\n1 0\n0 1\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("line 2: cannot parse \"foo bar\""));
+}
@@ -1,21 +1,15 @@
-use rand::distributions::{Distribution, Uniform};
-use rand::prelude::*;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
-use voronoids::delaunay_tree::{DelaunayTree, TreeUpdate};
+use voronoids::delaunay_tree::{DelaunayTree, Parallelism, Progress, TreeUpdate};
+use voronoids::geometry::signed_volume;
+use voronoids::points::{
+    gaussian_mixture, lattice, perturb, uniform_ball, uniform_box, GaussianCluster, Lattice,
+};
 
 #[test]
 fn test_delaunay_tree_3d() {
-    let mut vertices = vec![];
-    let mut rng = StdRng::seed_from_u64(0);
-    let dist = Uniform::from(0.0..1.0);
-    for _ in 0..1000 {
-        let point = [
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-        ];
-        vertices.push(point);
-    }
+    let vertices = uniform_box(1000, [0.0; 3], [1.0; 3], 0);
     let mut delaunay_tree = DelaunayTree::<3, 4>::new(vertices.clone());
     assert_eq!(delaunay_tree.max_simplex_id, 4);
     let n_points = delaunay_tree.vertices.len();
@@ -24,17 +18,9 @@ fn test_delaunay_tree_3d() {
         let update = TreeUpdate::new(n_points+i, vertices[i], &delaunay_tree);
         delaunay_tree.insert_point(&update);
     }
-    let mut vertices2: Vec<[f64; 3]> = vec![];
-    for _ in 0..1000 {
-        let point = [
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-            dist.sample(&mut rng),
-        ];
-        vertices2.push(point);
-    }
+    let vertices2 = uniform_box(1000, [0.0; 3], [1.0; 3], 1);
     delaunay_tree.add_points_to_tree(vertices2);
-    delaunay_tree.check_delaunay();
+    assert!(delaunay_tree.check_delaunay());
 }
 
 #[test]
@@ -42,18 +28,248 @@ fn test_delaunay_tree_2d() {
     let vertices = vec![[0.3, 0.1], [1.0, 0.2], [0.1, 1.0], [0.5, 0.5]];
     let mut delaunay_tree = DelaunayTree::<2, 3>::new(vertices.clone());
     assert_eq!(delaunay_tree.max_simplex_id, 3);
-    let mut rng = StdRng::seed_from_u64(0);
-    let dist = Uniform::from(0.0..1.0);
     let n_points = delaunay_tree.vertices.len();
 
-
-    for i in 0..1000 {
+    let points = uniform_box(1000, [0.0; 2], [1.0; 2], 0);
+    for (i, &point) in points.iter().enumerate() {
         let start = Instant::now();
-        let point = [dist.sample(&mut rng), dist.sample(&mut rng)];
         let update = TreeUpdate::new(n_points+i, point, &delaunay_tree);
         delaunay_tree.insert_point(&update);
         let duration = start.elapsed();
         println!("Point {:?} inserted in {:?}", point, duration);
     }
-    delaunay_tree.check_delaunay();
+    assert!(delaunay_tree.check_delaunay());
+}
+
+fn deterministic_tree(n_threads: usize, points: &[[f64; 3]]) -> DelaunayTree<3, 4> {
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.to_vec())
+        .with_parallelism(Parallelism::threads(n_threads).unwrap());
+    delaunay_tree.deterministic = true;
+    let n_points = delaunay_tree.vertices.len();
+    for (i, &vertex) in points.iter().take(200).enumerate() {
+        let update = TreeUpdate::new(n_points + i, vertex, &delaunay_tree);
+        delaunay_tree.insert_point(&update);
+    }
+    delaunay_tree.add_points_to_tree(points[200..].to_vec());
+    delaunay_tree
+}
+
+#[test]
+fn test_deterministic_insertion() {
+    let points = uniform_box(2000, [0.0; 3], [1.0; 3], 1);
+    let serial = deterministic_tree(1, &points);
+    let parallel = deterministic_tree(4, &points);
+    assert_eq!(serial.max_simplex_id, parallel.max_simplex_id);
+    assert_eq!(serial.simplices.len(), parallel.simplices.len());
+    for simplex in serial.simplices.iter() {
+        let other = parallel.simplices.get(simplex.key()).unwrap();
+        assert_eq!(simplex.vertices, other.vertices);
+        assert_eq!(simplex.neighbors, other.neighbors);
+    }
+    for vertex in serial.vertices.iter() {
+        let other = parallel.vertices.get(vertex.key()).unwrap();
+        assert_eq!(vertex.simplex, other.simplex);
+    }
+}
+
+fn simplex_coordinates(delaunay_tree: &DelaunayTree<3, 4>) -> HashSet<Vec<[u64; 3]>> {
+    // Simplices as sorted vertex coordinates, independent of the insertion order
+    delaunay_tree
+        .real_simplices()
+        .iter()
+        .map(|id| {
+            let simplex = delaunay_tree.simplices.get(id).unwrap();
+            let mut vertices: Vec<[u64; 3]> = simplex
+                .vertices
+                .iter()
+                .map(|vertex| {
+                    delaunay_tree
+                        .vertices
+                        .get(vertex)
+                        .unwrap()
+                        .coordinates
+                        .map(f64::to_bits)
+                })
+                .collect();
+            vertices.sort();
+            vertices
+        })
+        .collect()
+}
+
+#[test]
+fn test_parallelism() {
+    let points = uniform_box(1000, [0.0; 3], [1.0; 3], 2);
+    let pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap(),
+    );
+    match Parallelism::threads(3).unwrap() {
+        Parallelism::Pool(pool) => assert_eq!(pool.current_num_threads(), 3),
+        parallelism => panic!("expected a pool, found {:?}", parallelism),
+    }
+    let mut expected = None;
+    for parallelism in [
+        Parallelism::Serial,
+        Parallelism::threads(3).unwrap(),
+        Parallelism::Pool(pool),
+        Parallelism::Global,
+    ] {
+        let mut delaunay_tree =
+            DelaunayTree::<3, 4>::new(points.clone()).with_parallelism(parallelism);
+        let n_points = delaunay_tree.vertices.len();
+        for (i, &vertex) in points.iter().take(100).enumerate() {
+            let update = TreeUpdate::new(n_points + i, vertex, &delaunay_tree);
+            delaunay_tree.insert_point(&update);
+        }
+        delaunay_tree.add_points_to_tree(points[100..].to_vec());
+        let simplices = simplex_coordinates(&delaunay_tree);
+        match &expected {
+            None => expected = Some(simplices),
+            Some(expected) => assert_eq!(&simplices, expected),
+        }
+    }
+}
+
+#[test]
+fn test_progress() {
+    let points = uniform_box(1500, [0.0; 3], [1.0; 3], 3);
+    let reports: Arc<Mutex<Vec<Progress>>> = Arc::new(Mutex::new(vec![]));
+    let sink = reports.clone();
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone())
+        .with_progress(move |progress| sink.lock().unwrap().push(*progress));
+
+    delaunay_tree.insert_points(&points[..500]);
+    let serial = reports.lock().unwrap().clone();
+    assert_eq!(serial.len(), 1);
+    assert_eq!(serial[0].points_inserted, 500);
+    assert_eq!(serial[0].n_batches, 0);
+
+    reports.lock().unwrap().clear();
+    delaunay_tree.add_points_to_tree(points[500..].to_vec());
+    let batches = reports.lock().unwrap().clone();
+    assert!(!batches.is_empty());
+    for (i, progress) in batches.iter().enumerate() {
+        assert_eq!(progress.batch, i + 1);
+        assert_eq!(progress.n_batches, batches.len());
+        assert_eq!(progress.total_points, 1000);
+    }
+    assert_eq!(batches.last().unwrap().points_inserted, 1000);
+}
+
+fn check_orientation<const N: usize, const M: usize>(delaunay_tree: &DelaunayTree<N, M>) {
+    // Every simplex but the flat ghost ones, including those touching the super-simplex
+    for simplex in delaunay_tree.simplices.iter() {
+        if simplex
+            .vertices
+            .iter()
+            .any(|&id| (M..DelaunayTree::<N, M>::N_SUPER_VERTICES).contains(&id))
+        {
+            continue;
+        }
+        let volume = signed_volume(delaunay_tree.simplex_coordinates(&simplex));
+        assert!(
+            volume > 0.0,
+            "simplex {} has volume {}",
+            simplex.key(),
+            volume
+        );
+    }
+}
+
+#[test]
+fn test_orientation() {
+    let points = uniform_box(2000, [0.0; 3], [1.0; 3], 4);
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
+    check_orientation(&delaunay_tree);
+    delaunay_tree.insert_points(&points[..500]);
+    delaunay_tree.add_points_to_tree(points[500..].to_vec());
+    check_orientation(&delaunay_tree);
+
+    let points = uniform_box(1000, [0.0; 2], [1.0; 2], 6);
+    let mut delaunay_tree = DelaunayTree::<2, 3>::new(points.clone());
+    check_orientation(&delaunay_tree);
+    delaunay_tree.insert_points(&points);
+    check_orientation(&delaunay_tree);
+}
+
+#[test]
+fn test_insertion_point_sets() {
+    // Serial and batched insertion of point sets harder than a uniform box: a ball with
+    // its curved hull, a nearly cospherical lattice and tight clusters next to a sparse one
+    let clusters = [
+        GaussianCluster {
+            center: [0.3, 0.3, 0.3],
+            sigma: 0.005,
+            weight: 2.0,
+        },
+        GaussianCluster {
+            center: [0.7, 0.7, 0.7],
+            sigma: 0.005,
+            weight: 2.0,
+        },
+        GaussianCluster {
+            center: [0.5, 0.5, 0.5],
+            sigma: 0.2,
+            weight: 1.0,
+        },
+    ];
+    let point_sets = [
+        uniform_ball(1500, [0.5; 3], 0.5, 7),
+        perturb(
+            &lattice::<3>(Lattice::BodyCentered, [8, 8, 8], 0.1, [0.0; 3]),
+            1e-7,
+            8,
+        ),
+        gaussian_mixture(1500, &clusters, 9),
+    ];
+    for points in point_sets {
+        for parallelism in [Parallelism::Serial, Parallelism::threads(4).unwrap()] {
+            let mut delaunay_tree =
+                DelaunayTree::<3, 4>::new(points.clone()).with_parallelism(parallelism);
+            delaunay_tree.insert_points(&points[..200]);
+            delaunay_tree.add_points_to_tree(points[200..].to_vec());
+            check_orientation(&delaunay_tree);
+            let report = delaunay_tree.validate();
+            assert!(report.is_valid(), "{}", report);
+            assert_eq!(report.n_vertices, points.len() + 8);
+        }
+    }
+
+    // Exactly cocircular points in 2D
+    let points = lattice::<2>(Lattice::Cubic, [20, 20], 0.05, [0.0; 2]);
+    let mut delaunay_tree = DelaunayTree::<2, 3>::new(points.clone());
+    delaunay_tree.insert_points(&points[..100]);
+    delaunay_tree.add_points_to_tree(points[100..].to_vec());
+    check_orientation(&delaunay_tree);
+    let report = delaunay_tree.validate();
+    assert!(report.is_valid(), "{}", report);
+}
+
+#[test]
+fn test_vertex_ids_follow_input() {
+    // The point at input index i gets vertex id N_SUPER_VERTICES + i, whether it was
+    // inserted serially or in a parallel batch
+    let points = uniform_box(2000, [0.0; 3], [1.0; 3], 5);
+    let first = DelaunayTree::<3, 4>::N_SUPER_VERTICES;
+    for parallelism in [Parallelism::Serial, Parallelism::threads(4).unwrap()] {
+        let mut delaunay_tree =
+            DelaunayTree::<3, 4>::new(points.clone()).with_parallelism(parallelism);
+        delaunay_tree.insert_points(&points[..500]);
+        delaunay_tree.add_points_to_tree(points[500..].to_vec());
+        assert_eq!(delaunay_tree.vertices.len(), first + points.len());
+        for (i, point) in points.iter().enumerate() {
+            assert_eq!(
+                delaunay_tree
+                    .vertices
+                    .get(&(first + i))
+                    .unwrap()
+                    .coordinates,
+                *point
+            );
+            assert_eq!(delaunay_tree.nearest_vertex(*point), Some((i, 0.0)));
+        }
+    }
 }
@@ -0,0 +1,99 @@
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::dtfe::Dtfe;
+use voronoids::geometry::simplex_volume;
+use voronoids::interpolation::Grid;
+use voronoids::points::uniform_box;
+
+fn check_mass<const N: usize, const M: usize>(
+    tree: &DelaunayTree<N, M>,
+    dtfe: &Dtfe<N, M>,
+    total_mass: f64,
+) {
+    // The linear density field integrates to the total mass over the hull
+    let first = DelaunayTree::<N, M>::N_SUPER_VERTICES;
+    let mass: f64 = tree
+        .real_simplices()
+        .iter()
+        .map(|id| {
+            let simplex = tree.simplices.get(id).unwrap();
+            let mean = simplex
+                .vertices
+                .iter()
+                .map(|vertex| dtfe.densities()[vertex - first])
+                .sum::<f64>()
+                / M as f64;
+            mean * simplex_volume(tree.simplex_coordinates(&simplex))
+        })
+        .sum();
+    assert!((mass - total_mass).abs() < 1e-8 * total_mass);
+}
+
+#[test]
+fn test_dtfe_3d() {
+    let points = uniform_box(2000, [0.0; 3], [1.0; 3], 0);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+
+    let dtfe = Dtfe::new(&tree, None);
+    assert_eq!(dtfe.densities().len(), points.len());
+    assert!(dtfe.densities().iter().all(|density| *density > 0.0));
+    check_mass(&tree, &dtfe, points.len() as f64);
+    // The mean volume per point of uniform points in the unit cube is one over their number
+    let interior: Vec<f64> = points
+        .iter()
+        .zip(dtfe.densities())
+        .filter(|(point, _)| point.iter().all(|x| (0.2..0.8).contains(x)))
+        .map(|(_, density)| 1.0 / density)
+        .collect();
+    let mean = interior.iter().sum::<f64>() / interior.len() as f64;
+    assert!((mean * 2000.0 - 1.0).abs() < 0.1, "{}", mean);
+
+    let masses: Vec<f64> = (0..points.len()).map(|i| 1.0 + (i % 3) as f64).collect();
+    let weighted = Dtfe::new(&tree, Some(&masses));
+    check_mass(&tree, &weighted, masses.iter().sum());
+
+    // Gradients are consistent with the vertex densities of their simplex
+    let first = DelaunayTree::<3, 4>::N_SUPER_VERTICES;
+    let gradients = weighted.simplex_gradients();
+    assert_eq!(gradients.len(), tree.real_simplices().len());
+    for (id, gradient) in gradients.iter().take(200) {
+        let vertices = tree.simplices.get(id).unwrap().vertices;
+        let base = tree.vertices.get(&vertices[0]).unwrap().coordinates;
+        for vertex in &vertices[1..] {
+            let coordinates = tree.vertices.get(vertex).unwrap().coordinates;
+            let change: f64 = (0..3)
+                .map(|k| gradient[k] * (coordinates[k] - base[k]))
+                .sum();
+            let expected =
+                weighted.densities()[vertex - first] - weighted.densities()[vertices[0] - first];
+            assert!((change - expected).abs() < 1e-6 * expected.abs().max(1.0));
+        }
+    }
+}
+
+#[test]
+fn test_dtfe_grid_2d() {
+    let points = uniform_box(500, [0.0; 2], [1.0; 2], 1);
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    let dtfe = Dtfe::new(&tree, None);
+    check_mass(&tree, &dtfe, points.len() as f64);
+
+    let grid = Grid::new([-0.5, -0.5], [1.5, 1.5], [21, 21]);
+    let sampled = dtfe.sample_grid(&grid);
+    assert_eq!(sampled, dtfe.densities_at(&grid.points()));
+    // Zero outside the hull, positive in the middle
+    assert_eq!(sampled[0], 0.0);
+    assert!(sampled[10 * 21 + 10] > 0.0);
+    // Input points get their own density
+    for (point, density) in points.iter().zip(dtfe.densities()).take(50) {
+        assert!(
+            (dtfe.density(*point) - density).abs() < 1e-6 * density,
+            "{:?} {} {} {:?}",
+            point,
+            dtfe.density(*point),
+            density,
+            tree.find_containing_simplex(*point)
+        );
+    }
+}
@@ -31,3 +31,14 @@ fn test_boundsphere() {
     println!("Center: {:?}", center);
     println!("Radius: {:?}", radius);
 }
+
+#[test]
+fn test_circumsphere_2d() {
+    // Edges parallel to the axes
+    let (center, radius) = circumsphere([[0.0, 0.0], [2.0, 0.0], [0.0, 2.0]]);
+    assert!((center[0] - 1.0).abs() < 1e-12 && (center[1] - 1.0).abs() < 1e-12);
+    assert!((radius - 2f64.sqrt()).abs() < 1e-12);
+    let (center, radius) = circumsphere([[1.0, 1.0], [3.0, 1.0], [2.0, 4.0]]);
+    assert!((center[0] - 2.0).abs() < 1e-12);
+    assert!((radius - (center[1] - 4.0).abs()).abs() < 1e-12);
+}
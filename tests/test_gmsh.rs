@@ -0,0 +1,107 @@
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::gmsh::{write_msh, MshFormat};
+
+fn section<'a>(msh: &'a str, name: &str) -> Vec<Vec<&'a str>> {
+    let start = msh.find(&format!("${}\n", name)).unwrap() + name.len() + 2;
+    let end = msh.find(&format!("$End{}", name)).unwrap();
+    msh[start..end]
+        .lines()
+        .map(|line| line.split_whitespace().collect())
+        .collect()
+}
+
+#[test]
+fn test_msh_3d() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    let points: Vec<[f64; 3]> = (0..300)
+        .map(|_| {
+            [
+                dist.sample(&mut rng),
+                dist.sample(&mut rng),
+                dist.sample(&mut rng),
+            ]
+        })
+        .collect();
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
+    delaunay_tree.insert_points(&points);
+    let n_simplices = delaunay_tree.real_simplices().len();
+    let n_facets = delaunay_tree.hull_facets().len();
+
+    let mut msh = vec![];
+    write_msh(&delaunay_tree, &mut msh, MshFormat::Ascii).unwrap();
+    let msh = String::from_utf8(msh).unwrap();
+    assert!(msh.starts_with("$MeshFormat\n4.1 0 8\n$EndMeshFormat\n"));
+    assert!(msh.contains("2 1 \"hull boundary\"\n3 2 \"interior\""));
+
+    let nodes = section(&msh, "Nodes");
+    assert_eq!(nodes[0], vec!["1", "300", "1", "300"]);
+    let elements = section(&msh, "Elements");
+    let total = (n_facets + n_simplices).to_string();
+    assert_eq!(elements[0], vec!["2", total.as_str(), "1", total.as_str()]);
+    assert_eq!(elements[1], vec!["2", "1", "2", &n_facets.to_string()]);
+    let simplex_block = &elements[2 + n_facets];
+    assert_eq!(
+        simplex_block,
+        &vec!["3", "1", "4", &n_simplices.to_string()]
+    );
+
+    // Hull triangles point away from the centroid of the (convex) point cloud
+    let mut centroid = [0.0; 3];
+    for point in &points {
+        for k in 0..3 {
+            centroid[k] += point[k] / points.len() as f64;
+        }
+    }
+    for line in &elements[2..2 + n_facets] {
+        let p: Vec<[f64; 3]> = line[1..]
+            .iter()
+            .map(|tag| points[tag.parse::<usize>().unwrap() - 1])
+            .collect();
+        let u = [p[1][0] - p[0][0], p[1][1] - p[0][1], p[1][2] - p[0][2]];
+        let v = [p[2][0] - p[0][0], p[2][1] - p[0][1], p[2][2] - p[0][2]];
+        let normal = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        let outward: f64 = (0..3).map(|k| normal[k] * (p[0][k] - centroid[k])).sum();
+        assert!(outward > 0.0);
+    }
+}
+
+#[test]
+fn test_msh_2d_binary() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    let points: Vec<[f64; 2]> = (0..100)
+        .map(|_| [dist.sample(&mut rng), dist.sample(&mut rng)])
+        .collect();
+    let mut delaunay_tree = DelaunayTree::<2, 3>::new(points.clone());
+    delaunay_tree.insert_points(&points);
+    let n_simplices = delaunay_tree.real_simplices().len();
+    let n_facets = delaunay_tree.hull_facets().len();
+    // Euler's formula for a triangulated disk
+    assert_eq!(n_simplices, 2 * 100 - n_facets - 2);
+
+    let mut msh = vec![];
+    write_msh(&delaunay_tree, &mut msh, MshFormat::Binary).unwrap();
+    assert!(msh.starts_with(b"$MeshFormat\n4.1 1 8\n\x01\x00\x00\x00\n$EndMeshFormat\n"));
+    let nodes = msh.windows(7).position(|w| w == b"$Nodes\n").unwrap() + 7;
+    let end_nodes = msh.windows(10).position(|w| w == b"$EndNodes\n").unwrap();
+    assert_eq!(
+        end_nodes - nodes,
+        4 * 8 + 3 * 4 + 8 + 100 * 8 + 100 * 3 * 8 + 1
+    );
+    let elements = msh.windows(10).position(|w| w == b"$Elements\n").unwrap() + 10;
+    let end_elements = msh
+        .windows(13)
+        .position(|w| w == b"$EndElements\n")
+        .unwrap();
+    assert_eq!(
+        end_elements - elements,
+        4 * 8 + 2 * (3 * 4 + 8) + n_facets * 3 * 8 + n_simplices * 4 * 8 + 1
+    );
+}
@@ -0,0 +1,107 @@
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::graph::Graph;
+use voronoids::points::uniform_box;
+
+fn distance<const N: usize>(a: &[f64; N], b: &[f64; N]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn brute_force<const N: usize>(points: &[[f64; N]], gabriel: bool) -> Vec<(usize, usize)> {
+    let mut edges = vec![];
+    for a in 0..points.len() {
+        for b in a + 1..points.len() {
+            let length = distance(&points[a], &points[b]);
+            let mut midpoint = [0.0; N];
+            for k in 0..N {
+                midpoint[k] = (points[a][k] + points[b][k]) / 2.0;
+            }
+            let empty = (0..points.len()).filter(|&c| c != a && c != b).all(|c| {
+                if gabriel {
+                    distance(&points[c], &midpoint) >= length / 2.0
+                } else {
+                    distance(&points[c], &points[a]).max(distance(&points[c], &points[b])) >= length
+                }
+            });
+            if empty {
+                edges.push((a, b));
+            }
+        }
+    }
+    edges
+}
+
+fn minimum_spanning_weight<const N: usize>(points: &[[f64; N]]) -> f64 {
+    // Prim on the complete graph
+    let mut best = vec![f64::INFINITY; points.len()];
+    let mut done = vec![false; points.len()];
+    best[0] = 0.0;
+    let mut total = 0.0;
+    for _ in 0..points.len() {
+        let next = (0..points.len())
+            .filter(|&i| !done[i])
+            .min_by(|&i, &j| best[i].total_cmp(&best[j]))
+            .unwrap();
+        done[next] = true;
+        total += best[next];
+        for i in 0..points.len() {
+            best[i] = best[i].min(distance(&points[next], &points[i]));
+        }
+    }
+    total
+}
+
+fn pairs(graph: &Graph) -> Vec<(usize, usize)> {
+    graph.edges().iter().map(|(a, b, _)| (*a, *b)).collect()
+}
+
+fn check_graphs<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>, points: &[[f64; N]]) {
+    let delaunay = tree.delaunay_graph();
+    let gabriel = tree.gabriel_graph();
+    let rng = tree.relative_neighborhood_graph();
+    let emst = tree.euclidean_minimum_spanning_tree();
+
+    for graph in [&delaunay, &gabriel, &rng, &emst] {
+        assert_eq!(graph.n_vertices(), points.len());
+        // Symmetric rows with the edge lengths as weights
+        for a in 0..points.len() {
+            for (k, &b) in graph.neighbors_of(a).iter().enumerate() {
+                assert!(graph.neighbors_of(b).contains(&a));
+                let weight = graph.weights[graph.offsets[a] + k];
+                assert_eq!(weight, distance(&points[a], &points[b]));
+            }
+        }
+    }
+    assert_eq!(pairs(&gabriel), brute_force(points, true));
+    assert_eq!(pairs(&rng), brute_force(points, false));
+    assert!(pairs(&rng)
+        .iter()
+        .all(|edge| pairs(&gabriel).contains(edge)));
+    assert!(pairs(&gabriel)
+        .iter()
+        .all(|edge| pairs(&delaunay).contains(edge)));
+    assert!(pairs(&emst).iter().all(|edge| pairs(&rng).contains(edge)));
+
+    assert_eq!(emst.n_edges(), points.len() - 1);
+    let weight: f64 = emst.edges().iter().map(|(_, _, length)| length).sum();
+    assert!((weight - minimum_spanning_weight(points)).abs() < 1e-9);
+}
+
+#[test]
+fn test_graphs_2d() {
+    let points = uniform_box(200, [0.0; 2], [1.0; 2], 0);
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    check_graphs(&tree, &points);
+}
+
+#[test]
+fn test_graphs_3d() {
+    let points = uniform_box(150, [0.0; 3], [1.0; 3], 1);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    check_graphs(&tree, &points);
+}
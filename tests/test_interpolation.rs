@@ -0,0 +1,146 @@
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::interpolation::{Grid, InterpolationMethod, Interpolator};
+use voronoids::points::uniform_box;
+
+const METHODS: [InterpolationMethod; 3] = [
+    InterpolationMethod::Linear,
+    InterpolationMethod::NaturalNeighbor,
+    InterpolationMethod::Nearest,
+];
+
+fn linear<const N: usize>(point: &[f64; N]) -> [f64; 2] {
+    let mut value = [1.0, -0.5];
+    for (k, x) in point.iter().enumerate() {
+        value[0] += (k + 1) as f64 * x;
+        value[1] += 0.25 * x;
+    }
+    value
+}
+
+fn check_natural_neighbors<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>) {
+    // Sibson coordinates are positive, sum to 1 and reproduce the query point
+    let points: Vec<[f64; N]> = (0..tree.vertices.len() - DelaunayTree::<N, M>::N_SUPER_VERTICES)
+        .map(|index| {
+            tree.vertices
+                .get(&(index + DelaunayTree::<N, M>::N_SUPER_VERTICES))
+                .unwrap()
+                .coordinates
+        })
+        .collect();
+    for query in uniform_box(100, [0.0; N], [1.0; N], 7) {
+        // Stay away from the hull, where super-simplex vertices take part of the weight
+        let query = query.map(|x| 0.3 + 0.4 * x);
+        let weights = tree.natural_neighbor_coordinates(query).unwrap();
+        assert!(weights.len() >= M);
+        assert!(weights.iter().all(|(_, weight)| *weight > 0.0));
+        let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        for k in 0..N {
+            let reproduced: f64 = weights
+                .iter()
+                .map(|(index, weight)| weight * points[*index][k])
+                .sum();
+            assert!(
+                (reproduced - query[k]).abs() < 1e-9,
+                "{} {}",
+                reproduced,
+                query[k]
+            );
+        }
+    }
+}
+
+#[test]
+fn test_natural_neighbor_coordinates() {
+    let points = uniform_box(1000, [0.0; 3], [1.0; 3], 0);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    check_natural_neighbors(&tree);
+    assert!(tree.natural_neighbor_coordinates([2.0, 0.5, 0.5]).is_none());
+
+    // Right inside a hull facet the Voronoi cell of the query reaches a super-simplex
+    // vertex, so there are no Sibson coordinates while linear interpolation still works
+    let facet = tree.hull_facets()[0];
+    let mut query = [0.0; 3];
+    for id in facet {
+        let coordinates = tree.vertices.get(&id).unwrap().coordinates;
+        for k in 0..3 {
+            query[k] += coordinates[k] / 3.0;
+        }
+    }
+    let query = query.map(|x| 0.999 * x + 0.001 * 0.5);
+    assert!(tree.natural_neighbor_coordinates(query).is_none());
+    let values: Vec<[f64; 2]> = points.iter().map(linear).collect();
+    let linear_interpolator = Interpolator::new(&tree, values.clone());
+    assert!(linear_interpolator.interpolate(query).is_some());
+    let natural =
+        Interpolator::new(&tree, values).with_method(InterpolationMethod::NaturalNeighbor);
+    assert!(natural.interpolate(query).is_none());
+    assert!(natural.gradient(query).is_none());
+
+    let points = uniform_box(500, [0.0; 2], [1.0; 2], 1);
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    check_natural_neighbors(&tree);
+}
+
+#[test]
+fn test_linear_fields() {
+    // All methods reproduce the gradient of a linear field, linear and natural neighbor
+    // interpolation also reproduce its values
+    let points = uniform_box(800, [0.0; 3], [1.0; 3], 2);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    let values: Vec<[f64; 2]> = points.iter().map(linear).collect();
+    let queries: Vec<[f64; 3]> = uniform_box(50, [0.0; 3], [1.0; 3], 3)
+        .into_iter()
+        .map(|query| query.map(|x| 0.2 + 0.6 * x))
+        .collect();
+    for method in METHODS {
+        let interpolator = Interpolator::new(&tree, values.clone()).with_method(method);
+        let interpolated = interpolator.interpolate_points(&queries);
+        let gradients = interpolator.gradient_points(&queries);
+        for (i, query) in queries.iter().enumerate() {
+            let value = interpolated[i].unwrap();
+            let gradient = gradients[i].unwrap();
+            if method == InterpolationMethod::Nearest {
+                let (index, _) = tree.nearest_vertex(*query).unwrap();
+                assert_eq!(value, values[index]);
+            } else {
+                let expected = linear(query);
+                for c in 0..2 {
+                    assert!((value[c] - expected[c]).abs() < 1e-9);
+                }
+            }
+            for (k, (first, second)) in gradient[0].iter().zip(gradient[1].iter()).enumerate() {
+                assert!((first - (k + 1) as f64).abs() < 1e-6);
+                assert!((second - 0.25).abs() < 1e-6);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_grid() {
+    let points = uniform_box(400, [0.0; 2], [1.0; 2], 4);
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    let values: Vec<f64> = points.iter().map(|point| point[0] * point[1]).collect();
+    // The grid sticks out of the unit square, where only nearest-neighbor is defined
+    let grid = Grid::new([-0.1, -0.1], [1.1, 1.1], [13, 7]);
+    assert_eq!(grid.len(), 91);
+    // Row-major, the last axis varies fastest
+    let point = grid.point(2 * 7 + 1);
+    assert!((point[0] - 0.1).abs() < 1e-12 && (point[1] - 0.1).abs() < 1e-12);
+    for method in METHODS {
+        let interpolator = Interpolator::from_scalars(&tree, &values).with_method(method);
+        let on_grid = interpolator.interpolate_grid(&grid);
+        assert_eq!(on_grid.len(), grid.len());
+        for (value, point) in on_grid.iter().zip(grid.points()) {
+            assert_eq!(*value, interpolator.interpolate(point));
+        }
+        assert_eq!(on_grid[0].is_some(), method == InterpolationMethod::Nearest);
+        let gradients = interpolator.gradient_grid(&grid);
+        assert_eq!(gradients[0].is_some(), on_grid[0].is_some());
+    }
+}
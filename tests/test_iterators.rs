@@ -0,0 +1,95 @@
+use std::collections::BTreeSet;
+
+use rayon::prelude::*;
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::points::uniform_box;
+
+fn check_iterators<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>) {
+    for exclude_super in [true, false] {
+        // Reference: de-duplicated faces of the considered simplices
+        let simplices: Vec<[usize; M]> = tree
+            .simplices
+            .iter()
+            .filter(|simplex| {
+                if exclude_super {
+                    DelaunayTree::is_real_simplex(simplex.value())
+                } else {
+                    !DelaunayTree::is_ghost_simplex(simplex.value())
+                }
+            })
+            .map(|simplex| simplex.vertices)
+            .collect();
+        let mut edges = BTreeSet::new();
+        let mut facets = BTreeSet::new();
+        for vertices in &simplices {
+            for i in 0..M {
+                for j in i + 1..M {
+                    edges.insert((vertices[i].min(vertices[j]), vertices[i].max(vertices[j])));
+                }
+                let mut facet: Vec<usize> = vertices
+                    .iter()
+                    .copied()
+                    .filter(|&v| v != vertices[i])
+                    .collect();
+                facet.sort_unstable();
+                facets.insert(facet);
+            }
+        }
+
+        let found: Vec<(usize, usize)> = tree.unique_edges(exclude_super).collect();
+        assert_eq!(found, edges.iter().copied().collect::<Vec<_>>());
+
+        let found: Vec<_> = tree.unique_facets(exclude_super).collect();
+        assert_eq!(found.len(), facets.len());
+        let mut n_boundary = 0;
+        for facet in &found {
+            let mut sorted = facet.vertices.to_vec();
+            sorted.sort_unstable();
+            assert!(facets.contains(&sorted));
+            let simplex = tree.simplices.get(&facet.simplex).unwrap().vertices;
+            assert!(facet.vertices.iter().all(|v| simplex.contains(v)));
+            match facet.neighbor {
+                Some(neighbor) => {
+                    assert!(facet.simplex < neighbor);
+                    let neighbor = tree.simplices.get(&neighbor).unwrap().vertices;
+                    assert!(facet.vertices.iter().all(|v| neighbor.contains(v)));
+                }
+                None => n_boundary += 1,
+            }
+        }
+        if exclude_super {
+            assert_eq!(n_boundary, tree.hull_facets().len());
+        }
+
+        let links: Vec<(usize, Vec<usize>)> = tree.vertex_links(exclude_super).collect();
+        let total: usize = links.iter().map(|(_, link)| link.len()).sum();
+        assert_eq!(total, 2 * edges.len());
+        for (id, link) in links.iter().take(100) {
+            for other in link {
+                assert!(edges.contains(&(*id.min(other), *id.max(other))));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_iterators_3d() {
+    let points = uniform_box(400, [0.0; 3], [1.0; 3], 0);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    check_iterators(&tree);
+}
+
+#[test]
+fn test_iterators_2d() {
+    let points = uniform_box(300, [0.0; 2], [1.0; 2], 1);
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    check_iterators(&tree);
+    // Euler characteristic of the triangulated convex hull
+    let n_edges = tree.unique_edges(true).count();
+    assert_eq!(
+        points.len() as i64 - n_edges as i64 + tree.real_simplices().len() as i64,
+        1
+    );
+}
@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::lloyd::{Domain, LloydOptions};
+use voronoids::points::uniform_box;
+
+fn points<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>) -> Vec<[f64; N]> {
+    (DelaunayTree::<N, M>::N_SUPER_VERTICES..tree.vertices.len())
+        .map(|id| tree.vertices.get(&id).unwrap().coordinates)
+        .collect()
+}
+
+#[test]
+fn test_lloyd_2d() {
+    let points_2d = uniform_box(100, [0.0; 2], [1.0; 2], 0);
+    let mut tree = DelaunayTree::<2, 3>::new(points_2d.clone());
+    tree.insert_points(&points_2d);
+    let domain = Domain::new([0.0, 0.0], [1.0, 1.0]);
+
+    let moments = tree.cell_moments(&domain, None);
+    let area: f64 = moments.iter().map(|moments| moments.mass).sum();
+    assert!((area - 1.0).abs() < 1e-9);
+
+    let options = LloydOptions {
+        max_iterations: 30,
+        ..Default::default()
+    };
+    let report = tree.lloyd(&domain, &options);
+    assert_eq!(report.iterations, 30);
+    assert_eq!(report.energy.len(), report.iterations + 1);
+    assert_eq!(report.max_displacement.len(), report.iterations);
+    // Lloyd never raises the energy with exact integration
+    for pair in report.energy.windows(2) {
+        assert!(pair[1] <= pair[0] * (1.0 + 1e-12));
+    }
+    assert!(report.energy[30] < 0.9 * report.energy[0]);
+    assert!((tree.cvt_energy(&domain, None) - report.energy[30]).abs() < 1e-12);
+    assert!(tree.validate().is_valid());
+    for point in points(&tree) {
+        assert!(domain.contains(point));
+    }
+    let area: f64 = tree
+        .cell_moments(&domain, None)
+        .iter()
+        .map(|moments| moments.mass)
+        .sum();
+    assert!((area - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_lloyd_converges() {
+    // Four points converge to the centers of the quadrants
+    let points_2d = vec![[0.1, 0.2], [0.7, 0.1], [0.2, 0.6], [0.9, 0.8]];
+    let mut tree = DelaunayTree::<2, 3>::new(points_2d.clone());
+    tree.insert_points(&points_2d);
+    let domain = Domain::new([0.0, 0.0], [1.0, 1.0]);
+    let report = tree.lloyd(&domain, &LloydOptions::default());
+    assert!(report.converged);
+    assert!(report.iterations < 100);
+    let expected = [[0.25, 0.25], [0.75, 0.25], [0.25, 0.75], [0.75, 0.75]];
+    for (point, expected) in points(&tree).iter().zip(expected) {
+        assert!((point[0] - expected[0]).abs() < 1e-4);
+        assert!((point[1] - expected[1]).abs() < 1e-4);
+    }
+    // 4 squares of side 1/2, each with energy 2 * (1/2)^4 / 12
+    assert!((report.energy.last().unwrap() - 4.0 * 2.0 / 16.0 / 12.0).abs() < 1e-6);
+
+    // Stopping on the energy
+    let mut tree = DelaunayTree::<2, 3>::new(points_2d.clone());
+    tree.insert_points(&points_2d);
+    let report = tree.lloyd(
+        &domain,
+        &LloydOptions {
+            tolerance: 0.0,
+            energy_tolerance: 1e-3,
+            ..Default::default()
+        },
+    );
+    assert!(report.converged);
+    assert_eq!(report.energy.len(), report.iterations + 1);
+    let n = report.energy.len();
+    assert!(report.energy[n - 2] - report.energy[n - 1] <= 1e-3 * report.energy[n - 2]);
+}
+
+#[test]
+fn test_lloyd_3d() {
+    let points_3d = uniform_box(100, [0.0; 3], [1.0; 3], 1);
+    let mut tree = DelaunayTree::<3, 4>::new(points_3d.clone());
+    tree.insert_points(&points_3d);
+    let domain = Domain::new([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+
+    let volume: f64 = tree
+        .cell_moments(&domain, None)
+        .iter()
+        .map(|moments| moments.mass)
+        .sum();
+    assert!((volume - 1.0).abs() < 1e-9);
+
+    let report = tree.lloyd(
+        &domain,
+        &LloydOptions {
+            max_iterations: 10,
+            ..Default::default()
+        },
+    );
+    for pair in report.energy.windows(2) {
+        assert!(pair[1] <= pair[0] * (1.0 + 1e-12));
+    }
+    assert!(report.energy[10] < report.energy[0]);
+    assert!(tree.validate().is_valid());
+    for point in points(&tree) {
+        assert!(domain.contains(point));
+    }
+}
+
+#[test]
+fn test_lloyd_density() {
+    // Points gather where the density is high
+    let points_2d = uniform_box(100, [0.0; 2], [1.0; 2], 2);
+    let mut tree = DelaunayTree::<2, 3>::new(points_2d.clone());
+    tree.insert_points(&points_2d);
+    let domain = Domain::new([0.0, 0.0], [1.0, 1.0]);
+    let right = |points: &[[f64; 2]]| points.iter().filter(|point| point[0] > 0.5).count();
+    let before = right(&points_2d);
+    let report = tree.lloyd(
+        &domain,
+        &LloydOptions {
+            max_iterations: 300,
+            density: Some(Arc::new(|x: &[f64]| (4.0 * x[0]).exp())),
+            ..Default::default()
+        },
+    );
+    assert!(report.energy[report.iterations] < report.energy[0]);
+    // The density of a 2D CVT goes as the square root of the density, 73% on the right
+    let after = right(&points(&tree));
+    assert!(after > before + 10, "{} {}", before, after);
+    assert!(after > 65);
+}
+
+#[test]
+fn test_lloyd_halfspaces() {
+    // The triangle x + y <= 1 in the unit square
+    let points_2d: Vec<[f64; 2]> = uniform_box(200, [0.0; 2], [1.0; 2], 3)
+        .into_iter()
+        .filter(|point| point[0] + point[1] <= 1.0)
+        .collect();
+    let mut tree = DelaunayTree::<2, 3>::new(points_2d.clone());
+    tree.insert_points(&points_2d);
+    let domain = Domain::new([0.0, 0.0], [1.0, 1.0]).with_halfspace([1.0, 1.0], 1.0);
+    let area: f64 = tree
+        .cell_moments(&domain, None)
+        .iter()
+        .map(|moments| moments.mass)
+        .sum();
+    assert!((area - 0.5).abs() < 1e-9);
+
+    tree.lloyd(
+        &domain,
+        &LloydOptions {
+            max_iterations: 10,
+            ..Default::default()
+        },
+    );
+    for point in points(&tree) {
+        assert!(domain.contains(point));
+    }
+
+    // Same in 3D with the corner x + y + z <= 1 of the cube
+    let points_3d: Vec<[f64; 3]> = uniform_box(300, [0.0; 3], [1.0; 3], 4)
+        .into_iter()
+        .filter(|point| point[0] + point[1] + point[2] <= 1.0)
+        .collect();
+    let mut tree = DelaunayTree::<3, 4>::new(points_3d.clone());
+    tree.insert_points(&points_3d);
+    let domain = Domain::new([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]).with_halfspace([1.0, 1.0, 1.0], 1.0);
+    let volume: f64 = tree
+        .cell_moments(&domain, None)
+        .iter()
+        .map(|moments| moments.mass)
+        .sum();
+    assert!((volume - 1.0 / 6.0).abs() < 1e-9);
+}
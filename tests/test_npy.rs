@@ -0,0 +1,102 @@
+use std::io::Cursor;
+
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::npy::{
+    read_array, read_npz_array, read_npz_points, read_points, write_array, SimplexArrays,
+};
+
+fn npy_bytes(descr: &str, fortran_order: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}",
+        descr,
+        if fortran_order { "True" } else { "False" },
+        shape
+    );
+    // Pad with spaces so the data starts on a 64 byte boundary
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.as_bytes());
+    bytes.extend(data);
+    bytes
+}
+
+#[test]
+fn test_read_npy() {
+    let values = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+    let data: Vec<u8> = values.iter().flat_map(|x: &f64| x.to_le_bytes()).collect();
+    let bytes = npy_bytes("<f8", false, "(2, 3)", &data);
+    let points = read_points::<3, _>(bytes.as_slice()).unwrap();
+    assert_eq!(points, vec![[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]]);
+    assert!(read_points::<2, _>(bytes.as_slice()).is_err());
+
+    // Big endian float32 in Fortran order holds the same points column by column
+    let data: Vec<u8> = [0.0f32, 3.0, 1.0, 4.0, 2.0, 5.0]
+        .iter()
+        .flat_map(|x| x.to_be_bytes())
+        .collect();
+    let bytes = npy_bytes(">f4", true, "(2, 3)", &data);
+    let (shape, array) = read_array(bytes.as_slice()).unwrap();
+    assert_eq!(shape, vec![2, 3]);
+    assert_eq!(array, values);
+
+    let bytes = npy_bytes("<i8", false, "(2, 3)", &data);
+    assert!(read_array(bytes.as_slice()).is_err());
+}
+
+#[test]
+fn test_read_corrupt_npy() {
+    // A shape whose size overflows, alone or times the item size
+    let data: Vec<u8> = [0.0f64, 1.0].iter().flat_map(|x| x.to_le_bytes()).collect();
+    for shape in [
+        format!("({}, {})", usize::MAX, 2),
+        format!("({},)", usize::MAX / 4),
+    ] {
+        let bytes = npy_bytes("<f8", false, &shape, &data);
+        let error = read_array(bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{}", shape);
+    }
+    // A payload shorter than the shape, also for a huge shape
+    for shape in ["(2, 3)", "(1000000000000, 3)"] {
+        let bytes = npy_bytes("<f8", false, shape, &data);
+        let error = read_array(bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{}", shape);
+    }
+}
+
+#[test]
+fn test_write_npz() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    let points: Vec<f64> = (0..600).map(|_| dist.sample(&mut rng)).collect();
+    let mut output = vec![];
+    write_array(&mut output, &[200, 3], &points).unwrap();
+    let delaunay_tree = DelaunayTree::<3, 4>::from_npy(output.as_slice()).unwrap();
+
+    let arrays = SimplexArrays::new(&delaunay_tree);
+    assert_eq!(arrays.n_simplices, delaunay_tree.real_simplices().len());
+    for (i, neighbors) in arrays.neighbors.chunks_exact(4).enumerate() {
+        for &neighbor in neighbors.iter().filter(|&&neighbor| neighbor >= 0) {
+            let neighbor = neighbor as usize;
+            assert!(arrays.neighbors[4 * neighbor..4 * neighbor + 4].contains(&(i as i64)));
+        }
+    }
+
+    for compressed in [false, true] {
+        let archive = arrays
+            .write_npz(Cursor::new(vec![]), compressed)
+            .unwrap()
+            .into_inner();
+        let (shape, radii) = read_npz_array(Cursor::new(&archive), Some("radii")).unwrap();
+        assert_eq!(shape, vec![arrays.n_simplices]);
+        assert_eq!(radii, arrays.radii);
+        let centers = read_npz_points::<3, _>(Cursor::new(&archive), Some("centers")).unwrap();
+        assert_eq!(centers.len(), arrays.n_simplices);
+        assert!(read_npz_array(Cursor::new(&archive), Some("points")).is_err());
+    }
+}
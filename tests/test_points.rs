@@ -0,0 +1,215 @@
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::points::{
+    gaussian_mixture, jittered_grid, lattice, perturb, poisson_disk, uniform_ball, uniform_box,
+    GaussianCluster, Lattice,
+};
+
+fn distance<const N: usize>(a: &[f64; N], b: &[f64; N]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn min_distance<const N: usize>(points: &[[f64; N]]) -> f64 {
+    let mut min = f64::INFINITY;
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            min = min.min(distance(a, b));
+        }
+    }
+    min
+}
+
+#[test]
+fn test_uniform() {
+    let points = uniform_box::<3>(1000, [-1.0, 0.0, 2.0], [1.0, 0.5, 3.0], 0);
+    assert_eq!(points.len(), 1000);
+    for point in &points {
+        assert!((-1.0..1.0).contains(&point[0]));
+        assert!((0.0..0.5).contains(&point[1]));
+        assert!((2.0..3.0).contains(&point[2]));
+    }
+    assert_eq!(
+        points,
+        uniform_box::<3>(1000, [-1.0, 0.0, 2.0], [1.0, 0.5, 3.0], 0)
+    );
+    assert_ne!(
+        points,
+        uniform_box::<3>(1000, [-1.0, 0.0, 2.0], [1.0, 0.5, 3.0], 1)
+    );
+
+    let points = uniform_ball::<3>(10000, [1.0, 2.0, 3.0], 2.0, 0);
+    assert!(points
+        .iter()
+        .all(|point| distance(point, &[1.0, 2.0, 3.0]) <= 2.0));
+    // An eighth of the volume lies within half the radius
+    let inner = points
+        .iter()
+        .filter(|point| distance(point, &[1.0, 2.0, 3.0]) < 1.0)
+        .count();
+    assert!((inner as f64 / 10000.0 - 0.125).abs() < 0.02);
+    let points = uniform_ball::<2>(10000, [0.0, 0.0], 1.0, 0);
+    let inner = points
+        .iter()
+        .filter(|point| distance(point, &[0.0, 0.0]) < 0.5)
+        .count();
+    assert!((inner as f64 / 10000.0 - 0.25).abs() < 0.02);
+}
+
+#[test]
+fn test_poisson_disk() {
+    let points = poisson_disk::<2>([0.0, 0.0], [1.0, 1.0], 0.05, 0);
+    assert!(min_distance(&points) >= 0.05);
+    // Maximal up to the sampling: every point of the box is close to a sample
+    for probe in uniform_box::<2>(1000, [0.0, 0.0], [1.0, 1.0], 1) {
+        assert!(points.iter().any(|point| distance(point, &probe) < 0.1));
+    }
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    assert!(tree.validate().is_valid());
+
+    let points = poisson_disk::<3>([0.0, 0.0, 0.0], [1.0, 1.0, 1.0], 0.1, 2);
+    assert!(points.len() > 300);
+    assert!(min_distance(&points) >= 0.1);
+    assert!(points
+        .iter()
+        .all(|point| point.iter().all(|x| (0.0..1.0).contains(x))));
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    assert!(tree.validate().is_valid());
+}
+
+#[test]
+fn test_gaussian_mixture() {
+    let clusters = [
+        GaussianCluster {
+            center: [0.0, 0.0, 0.0],
+            sigma: 0.1,
+            weight: 3.0,
+        },
+        GaussianCluster {
+            center: [5.0, 5.0, 5.0],
+            sigma: 0.2,
+            weight: 1.0,
+        },
+    ];
+    let points = gaussian_mixture::<3>(10000, &clusters, 0);
+    let (first, second): (Vec<[f64; 3]>, Vec<[f64; 3]>) =
+        points.iter().partition(|point| point[0] < 2.5);
+    assert!((first.len() as f64 / 10000.0 - 0.75).abs() < 0.02);
+    for (cluster, points) in clusters.iter().zip([first, second]) {
+        let mut mean = [0.0; 3];
+        for point in &points {
+            for k in 0..3 {
+                mean[k] += point[k] / points.len() as f64;
+            }
+        }
+        assert!(distance(&mean, &cluster.center) < 0.02);
+        let variance = points
+            .iter()
+            .map(|point| distance(point, &mean).powi(2))
+            .sum::<f64>()
+            / (3 * points.len()) as f64;
+        assert!((variance.sqrt() / cluster.sigma - 1.0).abs() < 0.05);
+    }
+
+    let mut tree = DelaunayTree::<3, 4>::new(points[..2000].to_vec());
+    tree.insert_points(&points[..2000]);
+    assert!(tree.validate().is_valid());
+}
+
+#[test]
+#[should_panic(expected = "at least one cluster")]
+fn test_gaussian_mixture_without_clusters() {
+    gaussian_mixture::<2>(10, &[], 0);
+}
+
+#[test]
+#[should_panic(expected = "positive total weight")]
+fn test_gaussian_mixture_without_weight() {
+    let clusters = [GaussianCluster {
+        center: [0.0, 0.0],
+        sigma: 0.1,
+        weight: 0.0,
+    }];
+    gaussian_mixture::<2>(10, &clusters, 0);
+}
+
+#[test]
+#[should_panic(expected = "positive radius")]
+fn test_poisson_disk_without_radius() {
+    poisson_disk::<2>([0.0; 2], [1.0; 2], 0.0, 0);
+}
+
+#[test]
+fn test_jittered_grid() {
+    let points = jittered_grid::<2>([4, 5], [0.0, 0.0], [2.0, 1.0], 1.0, 0);
+    assert_eq!(points.len(), 20);
+    // One point in every cell
+    let mut cells: Vec<(usize, usize)> = points
+        .iter()
+        .map(|point| ((point[0] / 0.5) as usize, (point[1] / 0.2) as usize))
+        .collect();
+    cells.sort();
+    cells.dedup();
+    assert_eq!(cells.len(), 20);
+
+    let centers = jittered_grid::<3>([2, 2, 2], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0], 0.0, 0);
+    assert_eq!(centers[0], [0.25, 0.25, 0.25]);
+    assert_eq!(centers[1], [0.25, 0.25, 0.75]);
+    assert_eq!(centers[7], [0.75, 0.75, 0.75]);
+
+    let points = jittered_grid::<3>([8, 8, 8], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0], 0.8, 1);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    assert!(tree.validate().is_valid());
+}
+
+#[test]
+fn test_lattice() {
+    // Number of points and nearest neighbor distance
+    let cases = [
+        (Lattice::Cubic, 1, 1.0),
+        (Lattice::FaceCentered, 4, 0.5f64.sqrt()),
+        (Lattice::BodyCentered, 2, 0.75f64.sqrt()),
+        (Lattice::Hexagonal, 2, 1.0),
+    ];
+    for (kind, basis, nearest) in cases {
+        let points = lattice::<3>(kind, [3, 4, 5], 0.5, [1.0, 0.0, 0.0]);
+        assert_eq!(points.len(), 60 * basis);
+        assert!((min_distance(&points) - 0.5 * nearest).abs() < 1e-12);
+        assert_eq!(points[0], [1.0, 0.0, 0.0]);
+
+        // Exactly cocircular points triangulate in 2D, 3D needs a perturbation
+        let points_2d = lattice::<2>(kind, [6, 6], 0.1, [0.0, 0.0]);
+        let mut tree = DelaunayTree::<2, 3>::new(points_2d.clone());
+        tree.insert_points(&points_2d);
+        assert!(tree.validate().is_valid());
+        let points = perturb(&points, 1e-7, 0);
+        let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+        tree.insert_points(&points);
+        assert!(tree.validate().is_valid());
+    }
+
+    // Hexagonal points all have six neighbors at the same distance in their layer
+    let points = lattice::<2>(Lattice::Hexagonal, [4, 4], 1.0, [0.0, 0.0]);
+    let center = points[2 * 2 * 4 + 2 * 2 + 1];
+    let neighbors = points
+        .iter()
+        .filter(|point| (distance(point, &center) - 1.0).abs() < 1e-12)
+        .count();
+    assert_eq!(neighbors, 6);
+}
+
+#[test]
+fn test_perturb() {
+    let points = lattice::<3>(Lattice::Cubic, [3, 3, 3], 1.0, [0.0, 0.0, 0.0]);
+    let perturbed = perturb(&points, 0.01, 0);
+    assert_eq!(perturbed.len(), points.len());
+    for (a, b) in points.iter().zip(perturbed.iter()) {
+        assert_ne!(a, b);
+        assert!(a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() <= 0.01));
+    }
+}
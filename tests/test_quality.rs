@@ -0,0 +1,127 @@
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::geometry::circumsphere;
+use voronoids::quality::{histogram, simplex_quality, QualityMeasure, QualityReport};
+
+fn quality_of<const N: usize, const M: usize>(
+    coordinates: [[f64; N]; M],
+) -> voronoids::quality::SimplexQuality {
+    let (_, radius) = circumsphere(coordinates);
+    simplex_quality(coordinates, radius)
+}
+
+#[test]
+fn test_regular_simplices() {
+    let tetrahedron = [
+        [1.0, 1.0, 1.0],
+        [1.0, -1.0, -1.0],
+        [-1.0, 1.0, -1.0],
+        [-1.0, -1.0, 1.0],
+    ];
+    let quality = quality_of(tetrahedron);
+    assert!((quality.volume - 8.0 / 3.0).abs() < 1e-12);
+    assert!((quality.radius_edge_ratio - 6f64.sqrt() / 4.0).abs() < 1e-12);
+    assert!((quality.radius_ratio - 1.0).abs() < 1e-12);
+    assert!((quality.min_dihedral_angle - (1.0f64 / 3.0).acos().to_degrees()).abs() < 1e-9);
+    assert!((quality.aspect_ratio - 1.0).abs() < 1e-12);
+
+    // Equilateral triangle inscribed in the unit circle, no edge parallel to an axis
+    let triangle = [10.0f64, 130.0, 250.0].map(|angle| {
+        let angle = angle.to_radians();
+        [angle.cos(), angle.sin()]
+    });
+    let quality = quality_of(triangle);
+    assert!((quality.volume - 3.0 * 3f64.sqrt() / 4.0).abs() < 1e-12);
+    assert!((quality.radius_edge_ratio - 1.0 / 3f64.sqrt()).abs() < 1e-9);
+    assert!((quality.radius_ratio - 1.0).abs() < 1e-9);
+    assert!((quality.min_dihedral_angle - 60.0).abs() < 1e-9);
+    assert!((quality.aspect_ratio - 1.0).abs() < 1e-9);
+
+    // A right isosceles triangle has a 45 degree angle
+    let quality = quality_of([[0.0, 0.0], [1.0, 1.0], [-1.0, 1.0]]);
+    assert!((quality.min_dihedral_angle - 45.0).abs() < 1e-9);
+
+    // A sliver: four nearly coplanar points on a circle, with every edge long
+    let sliver = [
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.01],
+        [-1.0, 0.0, 0.0],
+        [0.0, -1.0, 0.01],
+    ];
+    let quality = quality_of(sliver);
+    assert!(quality.radius_edge_ratio < 1.0);
+    assert!(quality.min_dihedral_angle < 2.0);
+    assert!(quality.radius_ratio < 0.05);
+    assert!(quality.aspect_ratio > 10.0);
+}
+
+#[test]
+fn test_tree_quality() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    let points: Vec<[f64; 3]> = (0..500)
+        .map(|_| {
+            [
+                dist.sample(&mut rng),
+                dist.sample(&mut rng),
+                dist.sample(&mut rng),
+            ]
+        })
+        .collect();
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+
+    let qualities = tree.quality();
+    assert_eq!(qualities.len(), tree.real_simplices().len());
+    let total: f64 = qualities.iter().map(|quality| quality.volume).sum();
+    assert!(total > 0.8 && total <= 1.0);
+    for quality in &qualities {
+        assert!(quality.radius_ratio > 0.0 && quality.radius_ratio <= 1.0 + 1e-9);
+        assert!(quality.aspect_ratio >= 1.0 - 1e-9);
+        assert!(quality.min_dihedral_angle <= 70.53 + 1e-2);
+    }
+
+    let worst = tree.worst_simplices(QualityMeasure::MinDihedralAngle, 10);
+    assert_eq!(worst.len(), 10);
+    assert!(worst
+        .windows(2)
+        .all(|pair| pair[0].min_dihedral_angle <= pair[1].min_dihedral_angle));
+    let minimum = qualities
+        .iter()
+        .map(|quality| quality.min_dihedral_angle)
+        .fold(f64::INFINITY, f64::min);
+    assert_eq!(worst[0].min_dihedral_angle, minimum);
+    let worst = tree.worst_simplices(QualityMeasure::RadiusEdgeRatio, 5);
+    assert!(worst
+        .windows(2)
+        .all(|pair| pair[0].radius_edge_ratio >= pair[1].radius_edge_ratio));
+
+    let slivers = tree.slivers(10.0);
+    assert_eq!(
+        slivers.len(),
+        qualities
+            .iter()
+            .filter(|quality| quality.min_dihedral_angle < 10.0)
+            .count()
+    );
+
+    let counts = histogram(
+        &qualities,
+        QualityMeasure::MinDihedralAngle,
+        18,
+        Some((0.0, 90.0)),
+    );
+    assert_eq!(counts.edges.len(), 19);
+    assert_eq!(counts.counts.iter().sum::<usize>(), qualities.len());
+    let counts = histogram(&qualities, QualityMeasure::Volume, 7, None);
+    assert_eq!(counts.counts.iter().sum::<usize>(), qualities.len());
+
+    let report = QualityReport::new(&qualities);
+    assert_eq!(report.summaries.len(), 5);
+    let (_, summary) = report.summaries[3];
+    assert_eq!(summary.count, qualities.len());
+    assert_eq!(summary.min, minimum);
+    assert!(summary.min <= summary.median && summary.median <= summary.max);
+    assert!(report.to_string().contains("minimum dihedral angle"));
+}
@@ -0,0 +1,187 @@
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::query::{Location, QueryMethod};
+
+fn brute_force<const N: usize>(points: &[[f64; N]], query: [f64; N]) -> Vec<(usize, f64)> {
+    let mut output: Vec<(usize, f64)> = points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            let distance = point
+                .iter()
+                .zip(query.iter())
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f64>()
+                .sqrt();
+            (i, distance)
+        })
+        .collect();
+    output.sort_by(|a, b| a.1.total_cmp(&b.1));
+    output
+}
+
+fn indices(results: &[(usize, f64)]) -> Vec<usize> {
+    results.iter().map(|(i, _)| *i).collect()
+}
+
+#[test]
+fn test_queries_3d() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    let points: Vec<[f64; 3]> = (0..1500)
+        .map(|_| {
+            [
+                dist.sample(&mut rng),
+                dist.sample(&mut rng),
+                dist.sample(&mut rng),
+            ]
+        })
+        .collect();
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
+    delaunay_tree.insert_points(&points[..300]);
+    // Vertex ids follow the input order also for batched insertion
+    delaunay_tree.add_points_to_tree(points[300..].to_vec());
+
+    let query_dist = Uniform::from(-0.5..1.5);
+    for method in [QueryMethod::KdTree, QueryMethod::Walk] {
+        delaunay_tree.query_method = method;
+        for _ in 0..50 {
+            let query = [
+                query_dist.sample(&mut rng),
+                query_dist.sample(&mut rng),
+                query_dist.sample(&mut rng),
+            ];
+            let expected = brute_force(&points, query);
+            let (nearest, distance) = delaunay_tree.nearest_vertex(query).unwrap();
+            assert_eq!(nearest, expected[0].0, "{:?}", method);
+            assert!((distance - expected[0].1).abs() < 1e-12);
+            assert_eq!(
+                indices(&delaunay_tree.k_nearest(query, 10)),
+                indices(&expected[..10])
+            );
+            let within = delaunay_tree.within_radius(query, 0.2);
+            let n_within = expected.iter().filter(|(_, d)| *d <= 0.2).count();
+            assert_eq!(indices(&within), indices(&expected[..n_within]));
+        }
+    }
+}
+
+#[test]
+fn test_walk_2d() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let dist = Uniform::from(0.0..1.0);
+    let points: Vec<[f64; 2]> = (0..500)
+        .map(|_| [dist.sample(&mut rng), dist.sample(&mut rng)])
+        .collect();
+    let mut delaunay_tree =
+        DelaunayTree::<2, 3>::new(points.clone()).with_query_method(QueryMethod::Walk);
+    delaunay_tree.insert_points(&points);
+    for _ in 0..50 {
+        let query = [dist.sample(&mut rng), dist.sample(&mut rng)];
+        let expected = brute_force(&points, query);
+        assert_eq!(
+            indices(&delaunay_tree.k_nearest(query, 5)),
+            indices(&expected[..5])
+        );
+    }
+}
+
+#[test]
+fn test_k_nearest_all_points() {
+    // A k beyond the number of points returns every point, whatever the method
+    let mut rng = StdRng::seed_from_u64(2);
+    let dist = Uniform::from(0.0..1.0);
+    let points: Vec<[f64; 2]> = (0..200)
+        .map(|_| [dist.sample(&mut rng), dist.sample(&mut rng)])
+        .collect();
+    let mut delaunay_tree = DelaunayTree::<2, 3>::new(points.clone());
+    delaunay_tree.insert_points(&points);
+    let query = [0.3, 0.6];
+    let expected = brute_force(&points, query);
+    for method in [QueryMethod::KdTree, QueryMethod::Walk] {
+        delaunay_tree.query_method = method;
+        for k in [points.len() + 1, 1_000_000_000, usize::MAX] {
+            assert_eq!(
+                indices(&delaunay_tree.k_nearest(query, k)),
+                indices(&expected),
+                "{:?}",
+                method
+            );
+        }
+    }
+}
+
+#[test]
+fn test_find_containing_simplex() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let dist = Uniform::from(0.0..1.0);
+    let points: Vec<[f64; 3]> = (0..800)
+        .map(|_| {
+            [
+                dist.sample(&mut rng),
+                dist.sample(&mut rng),
+                dist.sample(&mut rng),
+            ]
+        })
+        .collect();
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
+    delaunay_tree.insert_points(&points);
+    let real_simplices = delaunay_tree.real_simplices();
+
+    let query_dist = Uniform::from(-0.2..1.2);
+    let queries: Vec<[f64; 3]> = (0..200)
+        .map(|_| {
+            [
+                query_dist.sample(&mut rng),
+                query_dist.sample(&mut rng),
+                query_dist.sample(&mut rng),
+            ]
+        })
+        .collect();
+    let locations = delaunay_tree.find_containing_simplices(&queries);
+    let mut n_inside = 0;
+    for (query, location) in queries.iter().zip(locations.iter()) {
+        assert_eq!(*location, delaunay_tree.find_containing_simplex(*query));
+        let covered = real_simplices.iter().any(|id| {
+            delaunay_tree
+                .barycentric_coordinates(*id, *query)
+                .iter()
+                .all(|&value| value >= -1e-12)
+        });
+        match location {
+            Location::Inside {
+                simplex,
+                barycentric,
+            } => {
+                n_inside += 1;
+                assert!(covered);
+                assert!((barycentric.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+                let vertices = delaunay_tree.simplices.get(simplex).unwrap().vertices;
+                for (k, coordinate) in query.iter().enumerate() {
+                    let interpolated: f64 = vertices
+                        .iter()
+                        .zip(barycentric.iter())
+                        .map(|(vertex, weight)| {
+                            weight * delaunay_tree.vertices.get(vertex).unwrap().coordinates[k]
+                        })
+                        .sum();
+                    assert!((interpolated - coordinate).abs() < 1e-9);
+                }
+            }
+            Location::OutsideHull => assert!(!covered),
+        }
+    }
+    assert!(n_inside > 50);
+    // Input points are inside, including those on the hull
+    for point in &points {
+        assert!(matches!(
+            delaunay_tree.find_containing_simplex(*point),
+            Location::Inside { .. }
+        ));
+    }
+    assert_eq!(
+        delaunay_tree.find_containing_simplex([10.0, 10.0, 10.0]),
+        Location::OutsideHull
+    );
+}
@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::refinement::RefinementOptions;
+
+fn domain_points<const N: usize>(n: usize, seed: u64) -> Vec<[f64; N]> {
+    // Random points well inside the cross-polytope |x|_1 <= 1, then its jittered corners.
+    // Nearly coplanar hull faces or random points right next to the hull would force
+    // refinement down to the rounding error.
+    let mut rng = StdRng::seed_from_u64(seed);
+    let dist = Uniform::from(-0.3..0.3);
+    let jitter = Uniform::from(-0.02..0.02);
+    let mut points: Vec<[f64; N]> = (0..n)
+        .map(|_| {
+            let mut point = [0.0; N];
+            for x in point.iter_mut() {
+                *x = dist.sample(&mut rng);
+            }
+            point
+        })
+        .collect();
+    for k in 0..2 * N {
+        let mut point = [0.0; N];
+        for x in point.iter_mut() {
+            *x = jitter.sample(&mut rng);
+        }
+        point[k / 2] += if k % 2 == 0 { 1.0 } else { -1.0 };
+        points.push(point);
+    }
+    points
+}
+
+fn max_radius_edge_ratio<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>) -> f64 {
+    tree.quality()
+        .iter()
+        .map(|quality| quality.radius_edge_ratio)
+        .fold(0.0, f64::max)
+}
+
+fn check_refined<const N: usize, const M: usize>(
+    tree: &DelaunayTree<N, M>,
+    points: &[[f64; N]],
+    bound: f64,
+) {
+    let validation = tree.validate();
+    assert!(validation.is_valid(), "{}", validation);
+    assert!(max_radius_edge_ratio(tree) <= bound * (1.0 + 1e-9));
+    // Input points keep their ids, Steiner points follow
+    for (i, point) in points.iter().enumerate() {
+        let id = i + DelaunayTree::<N, M>::N_SUPER_VERTICES;
+        assert_eq!(tree.vertices.get(&id).unwrap().coordinates, *point);
+    }
+    assert!(tree.vertices.len() > points.len() + DelaunayTree::<N, M>::N_SUPER_VERTICES);
+}
+
+#[test]
+fn test_refine_2d() {
+    let points = domain_points::<2>(200, 0);
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    let area: f64 = tree.quality().iter().map(|quality| quality.volume).sum();
+    assert!(max_radius_edge_ratio(&tree) > 2.0);
+
+    let report = tree.refine(&RefinementOptions::default());
+    assert_eq!(report.bad_simplices, 0);
+    assert_eq!(
+        tree.vertices.len(),
+        points.len() + DelaunayTree::<2, 3>::N_SUPER_VERTICES + report.points_inserted
+    );
+    check_refined(&tree, &points, 2.0);
+    let refined: f64 = tree.quality().iter().map(|quality| quality.volume).sum();
+    assert!((refined - area).abs() < 1e-9);
+
+    // A tighter bound, still above sqrt(2)
+    let options = RefinementOptions {
+        radius_edge_bound: 1.5,
+        ..Default::default()
+    };
+    let report = tree.refine(&options);
+    assert_eq!(report.bad_simplices, 0);
+    check_refined(&tree, &points, 1.5);
+}
+
+#[test]
+fn test_refine_3d() {
+    let points = domain_points::<3>(200, 1);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    let volume: f64 = tree.quality().iter().map(|quality| quality.volume).sum();
+
+    let report = tree.refine(&RefinementOptions::default());
+    assert_eq!(report.bad_simplices, 0);
+    check_refined(&tree, &points, 2.0);
+    let refined: f64 = tree.quality().iter().map(|quality| quality.volume).sum();
+    assert!((refined - volume).abs() < 1e-9);
+}
+
+#[test]
+fn test_refine_size() {
+    let points = domain_points::<2>(50, 2);
+    let mut tree = DelaunayTree::<2, 3>::new(points.clone());
+    tree.insert_points(&points);
+    // Finer towards x = -1
+    let options = RefinementOptions {
+        size: Some(Arc::new(|x: &[f64]| 0.06 + 0.05 * x[0])),
+        ..Default::default()
+    };
+    let report = tree.refine(&options);
+    assert_eq!(report.bad_simplices, 0);
+    check_refined(&tree, &points, 2.0);
+    for id in tree.real_simplices() {
+        let simplex = tree.simplices.get(&id).unwrap();
+        let coordinates = tree.simplex_coordinates(&simplex);
+        let x = coordinates.iter().map(|point| point[0]).sum::<f64>() / 3.0;
+        assert!(simplex.radius <= 0.06 + 0.05 * x + 1e-12);
+    }
+}
+
+#[test]
+fn test_refine_batch() {
+    let points = domain_points::<2>(200, 3);
+    let mut serial = DelaunayTree::<2, 3>::new(points.clone());
+    serial.insert_points(&points);
+    let mut batch = DelaunayTree::<2, 3>::new(points.clone());
+    batch.insert_points(&points);
+
+    let options = RefinementOptions {
+        size: Some(Arc::new(|_: &[f64]| 0.05)),
+        ..Default::default()
+    };
+    serial.refine(&options);
+    let report = batch.refine(&RefinementOptions {
+        batch: true,
+        ..options.clone()
+    });
+    assert_eq!(report.bad_simplices, 0);
+    check_refined(&batch, &points, 2.0);
+    // Same guarantees, the meshes themselves differ
+    assert!(batch.real_simplices().len() > serial.real_simplices().len() / 2);
+
+    let points = domain_points::<3>(100, 4);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    let report = tree.refine(&RefinementOptions {
+        batch: true,
+        ..Default::default()
+    });
+    assert_eq!(report.bad_simplices, 0);
+    check_refined(&tree, &points, 2.0);
+}
+
+#[test]
+fn test_refine_max_points() {
+    let points = domain_points::<3>(100, 5);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    let report = tree.refine(&RefinementOptions {
+        size: Some(Arc::new(|_: &[f64]| 0.01)),
+        max_points: 50,
+        ..Default::default()
+    });
+    assert_eq!(report.points_inserted, 50);
+    assert!(report.bad_simplices > 0);
+    assert!(tree.validate().is_valid());
+}
@@ -0,0 +1,66 @@
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::render::{render_png, render_svg_string, Colormap, RenderOptions};
+
+fn make_tree() -> DelaunayTree<2, 3> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    let points: Vec<[f64; 2]> = (0..200)
+        .map(|_| [dist.sample(&mut rng), dist.sample(&mut rng)])
+        .collect();
+    let mut delaunay_tree = DelaunayTree::<2, 3>::new(points.clone());
+    delaunay_tree.insert_points(&points);
+    delaunay_tree
+}
+
+#[test]
+fn test_render_svg() {
+    let delaunay_tree = make_tree();
+    let options = RenderOptions {
+        width: 400,
+        height: 400,
+        circumcircles: true,
+        cell_values: Some((0..200).map(|i| i as f64).collect()),
+        colormap: Colormap::Copper,
+        ..Default::default()
+    };
+    let svg = render_svg_string(&delaunay_tree, &options).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("<polygon"));
+    assert!(svg.contains("<circle"));
+
+    let plain = render_svg_string(
+        &delaunay_tree,
+        &RenderOptions {
+            voronoi: false,
+            points: false,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(!plain.contains("<polygon"));
+    assert!(!plain.contains("<circle"));
+
+    // The super-triangle reaches far beyond the default viewport
+    let full = render_svg_string(
+        &delaunay_tree,
+        &RenderOptions {
+            hide_super_triangle: false,
+            viewport: Some(([-20.0, -20.0], [20.0, 20.0])),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(full.matches("<polyline").count() > plain.matches("<polyline").count());
+}
+
+#[test]
+fn test_render_png() {
+    let delaunay_tree = make_tree();
+    let path = std::env::temp_dir().join("voronoids_test_render.png");
+    render_png(&delaunay_tree, &path, &RenderOptions::default()).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    assert!(bytes.starts_with(b"\x89PNG"));
+    std::fs::remove_file(&path).unwrap();
+}
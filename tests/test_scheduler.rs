@@ -1,7 +1,25 @@
 use rand::distributions::{Distribution, Uniform};
 use rand::prelude::*;
+use std::collections::{HashMap, HashSet};
 use voronoids::delaunay_tree::{DelaunayTree, TreeUpdate};
-use voronoids::scheduler::find_placement;
+use voronoids::scheduler::{find_placement, schedule_batches};
+
+fn serial_placement<const N: usize>(queue: &[(usize, [f64; N], Vec<usize>)]) -> Vec<usize> {
+    // Each entry goes one level above the last earlier entry sharing a site with it
+    let mut last: HashMap<usize, usize> = HashMap::new();
+    let mut placement = vec![0; queue.len()];
+    for (id, _, sites) in queue {
+        placement[*id] = sites
+            .iter()
+            .map(|site| last.get(site).map_or(1, |&other| placement[other] + 1))
+            .max()
+            .unwrap();
+        for site in sites {
+            last.insert(*site, *id);
+        }
+    }
+    placement
+}
 
 #[test]
 fn test_queuing() {
@@ -33,5 +51,45 @@ fn test_queuing() {
 
     let queue = voronoids::scheduler::make_queue(vertices2, &delaunay_tree);
     let placement = find_placement(&queue);
-    println!("{:?}", placement);
-}
\ No newline at end of file
+    assert_eq!(placement, serial_placement(&queue));
+
+    let batches = schedule_batches(&queue);
+    assert_eq!(batches.iter().map(Vec::len).sum::<usize>(), N_POINTS);
+    for (level, batch) in batches.iter().enumerate() {
+        let mut sites = HashSet::new();
+        for &i in batch {
+            assert_eq!(placement[i], level + 1);
+            for site in &queue[i].2 {
+                assert!(
+                    sites.insert(*site),
+                    "site {} is shared within a batch",
+                    site
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sparse_sites() {
+    // Large simplex ids, as in a streaming triangulation, give the same batches
+    let mut rng = StdRng::seed_from_u64(1);
+    let dist = Uniform::from(0..50);
+    let queue: Vec<(usize, [f64; 3], Vec<usize>)> = (0..200)
+        .map(|id| {
+            let mut sites: Vec<usize> = (0..6).map(|_| dist.sample(&mut rng)).collect();
+            sites.sort();
+            sites.dedup();
+            (id, [0.0; 3], sites)
+        })
+        .collect();
+    let sparse: Vec<(usize, [f64; 3], Vec<usize>)> = queue
+        .iter()
+        .map(|(id, point, sites)| {
+            let sites = sites.iter().map(|site| site * 1_000_000_000).collect();
+            (*id, *point, sites)
+        })
+        .collect();
+    assert_eq!(schedule_batches(&sparse), schedule_batches(&queue));
+    assert_eq!(find_placement(&sparse), serial_placement(&queue));
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "serde")]
+
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+use voronoids::delaunay_tree::{DelaunayTree, TreeUpdate};
+use voronoids::serialization::SerializationError;
+
+fn make_tree() -> DelaunayTree<3, 4> {
+    let mut vertices = vec![];
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    for _ in 0..200 {
+        let point = [
+            dist.sample(&mut rng),
+            dist.sample(&mut rng),
+            dist.sample(&mut rng),
+        ];
+        vertices.push(point);
+    }
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(vertices.clone());
+    let n_points = delaunay_tree.vertices.len();
+    for (i, &vertex) in vertices.iter().enumerate() {
+        let update = TreeUpdate::new(n_points + i, vertex, &delaunay_tree);
+        delaunay_tree.insert_point(&update);
+    }
+    delaunay_tree
+}
+
+#[test]
+fn test_round_trip() {
+    let delaunay_tree = make_tree();
+    let bytes = delaunay_tree.to_bytes().unwrap();
+    let mut loaded = DelaunayTree::<3, 4>::from_bytes(&bytes).unwrap();
+
+    assert_eq!(loaded.max_simplex_id, delaunay_tree.max_simplex_id);
+    assert_eq!(loaded.vertices.len(), delaunay_tree.vertices.len());
+    assert_eq!(loaded.simplices.len(), delaunay_tree.simplices.len());
+    for simplex in delaunay_tree.simplices.iter() {
+        let other = loaded.simplices.get(simplex.key()).unwrap();
+        assert_eq!(other.vertices, simplex.vertices);
+        assert_eq!(other.center, simplex.center);
+        assert_eq!(other.radius, simplex.radius);
+        assert_eq!(other.neighbors, simplex.neighbors);
+    }
+    for vertex in delaunay_tree.vertices.iter() {
+        let other = loaded.vertices.get(vertex.key()).unwrap();
+        assert_eq!(other.coordinates, vertex.coordinates);
+        assert_eq!(other.simplex, vertex.simplex);
+    }
+    assert_eq!(loaded.to_bytes().unwrap(), bytes);
+
+    // The rebuilt kd-tree has to support further insertion.
+    let n_points = loaded.vertices.len();
+    let update = TreeUpdate::new(n_points, [0.25, 0.5, 0.75], &loaded);
+    loaded.insert_point(&update);
+    assert!(loaded.check_delaunay());
+}
+
+#[test]
+fn test_version_mismatch() {
+    let mut bytes = make_tree().to_bytes().unwrap();
+    bytes[4] = 99;
+    match DelaunayTree::<3, 4>::from_bytes(&bytes) {
+        Err(SerializationError::VersionMismatch { found, .. }) => assert_eq!(found, 99),
+        _ => panic!("expected a version mismatch"),
+    }
+}
+
+#[test]
+fn test_dimension_mismatch() {
+    let bytes = make_tree().to_bytes().unwrap();
+    match DelaunayTree::<2, 3>::from_bytes(&bytes) {
+        Err(SerializationError::DimensionMismatch { found, expected }) => {
+            assert_eq!(found, (3, 4));
+            assert_eq!(expected, (2, 3));
+        }
+        _ => panic!("expected a dimension mismatch"),
+    }
+    assert!(matches!(
+        DelaunayTree::<3, 4>::from_bytes(b"not a checkpoint"),
+        Err(SerializationError::InvalidMagic(_))
+    ));
+}
+
+#[test]
+fn test_dangling_references() {
+    let delaunay_tree = make_tree();
+    let id = delaunay_tree.real_simplices()[0];
+    let missing = delaunay_tree.max_simplex_id + 1;
+    delaunay_tree.simplices.get_mut(&id).unwrap().neighbors[0] = missing;
+    let bytes = delaunay_tree.to_bytes().unwrap();
+    match DelaunayTree::<3, 4>::from_bytes(&bytes) {
+        Err(SerializationError::InvalidData(message)) => {
+            assert!(message.contains(&missing.to_string()), "{}", message)
+        }
+        _ => panic!("expected invalid data"),
+    }
+
+    let delaunay_tree = make_tree();
+    let n_vertices = delaunay_tree.vertices.len();
+    delaunay_tree.simplices.get_mut(&id).unwrap().vertices[0] = n_vertices;
+    let bytes = delaunay_tree.to_bytes().unwrap();
+    assert!(matches!(
+        DelaunayTree::<3, 4>::from_bytes(&bytes),
+        Err(SerializationError::InvalidData(_))
+    ));
+
+    let delaunay_tree = make_tree();
+    delaunay_tree
+        .vertices
+        .get_mut(&8)
+        .unwrap()
+        .simplex
+        .push(missing);
+    let bytes = delaunay_tree.to_bytes().unwrap();
+    assert!(matches!(
+        DelaunayTree::<3, 4>::from_bytes(&bytes),
+        Err(SerializationError::InvalidData(_))
+    ));
+}
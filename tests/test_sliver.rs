@@ -0,0 +1,85 @@
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::points::{perturb, uniform_box};
+use voronoids::refinement::RefinementOptions;
+use voronoids::sliver::SliverOptions;
+
+fn total_volume(tree: &DelaunayTree<3, 4>) -> f64 {
+    tree.quality().iter().map(|quality| quality.volume).sum()
+}
+
+#[test]
+fn test_remove_slivers() {
+    let points = uniform_box(300, [0.0; 3], [1.0; 3], 0);
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    let volume = total_volume(&tree);
+    let hull: Vec<usize> = tree.hull_facets().into_iter().flatten().collect();
+    let before = tree.slivers(10.0).len();
+
+    let options = SliverOptions {
+        min_dihedral_angle: 10.0,
+        ..Default::default()
+    };
+    let report = tree.remove_slivers(&options);
+    assert_eq!(report.slivers_before, before);
+    assert_eq!(report.slivers_after, tree.slivers(10.0).len());
+    assert!(report.iterations > 0 && report.vertices_moved > 0);
+    assert!(report.slivers_after < report.slivers_before);
+    assert!(report.min_dihedral_angle_after >= report.min_dihedral_angle_before);
+    let minimum = tree
+        .quality()
+        .iter()
+        .map(|quality| quality.min_dihedral_angle)
+        .fold(f64::INFINITY, f64::min);
+    assert_eq!(minimum, report.min_dihedral_angle_after);
+
+    // Hull vertices stay in place, so the domain does not change
+    let validation = tree.validate();
+    assert!(validation.is_valid(), "{}", validation);
+    assert_eq!(tree.vertices.len(), points.len() + 8);
+    for id in hull {
+        assert_eq!(tree.vertices.get(&id).unwrap().coordinates, points[id - 8]);
+    }
+    assert!((total_volume(&tree) - volume).abs() < 1e-9);
+}
+
+#[test]
+fn test_remove_slivers_after_refinement() {
+    // Well inside the octahedron |x|_1 <= 1, with jittered corners
+    let mut corners = Vec::new();
+    for k in 0..6 {
+        let mut point = [0.0; 3];
+        point[k / 2] = if k % 2 == 0 { 1.0 } else { -1.0 };
+        corners.push(point);
+    }
+    let mut points = uniform_box(100, [-0.3; 3], [0.3; 3], 1);
+    points.extend(perturb(&corners, 0.02, 1));
+    let mut tree = DelaunayTree::<3, 4>::new(points.clone());
+    tree.insert_points(&points);
+    tree.refine(&RefinementOptions::default());
+    let n_vertices = tree.vertices.len();
+
+    let report = tree.remove_slivers(&SliverOptions {
+        fixed_points: points.len(),
+        ..Default::default()
+    });
+    assert!(report.min_dihedral_angle_after > report.min_dihedral_angle_before);
+    assert!(report.slivers_after < report.slivers_before);
+    assert_eq!(tree.vertices.len(), n_vertices);
+    for (i, point) in points.iter().enumerate() {
+        assert_eq!(tree.vertices.get(&(i + 8)).unwrap().coordinates, *point);
+    }
+    assert!(tree.validate().is_valid());
+
+    // Nothing to do
+    let report = tree.remove_slivers(&SliverOptions {
+        min_dihedral_angle: 0.0,
+        ..Default::default()
+    });
+    assert_eq!(report.iterations, 0);
+    assert_eq!(report.slivers_before, 0);
+    assert_eq!(
+        report.min_dihedral_angle_after,
+        report.min_dihedral_angle_before
+    );
+}
@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+use std::io;
+
+use voronoids::delaunay_tree::{DelaunayTree, Parallelism};
+use voronoids::points::uniform_box;
+use voronoids::streaming::{read_simplices, BinarySink, FinalSimplex, StreamingTriangulation};
+
+fn sorted_points<const N: usize>(n: usize, upper: [f64; N], seed: u64) -> Vec<[f64; N]> {
+    let mut points = uniform_box::<N>(n, [0.0; N], upper, seed);
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    points
+}
+
+fn simplex_set<const N: usize, const M: usize>(
+    simplices: &[FinalSimplex<N, M>],
+) -> HashSet<[usize; M]> {
+    simplices
+        .iter()
+        .map(|simplex| {
+            let mut vertices = simplex.vertices;
+            vertices.sort();
+            vertices
+        })
+        .collect()
+}
+
+fn tree_set<const N: usize, const M: usize>(tree: &DelaunayTree<N, M>) -> HashSet<[usize; M]> {
+    tree.real_simplices()
+        .into_iter()
+        .map(|id| {
+            let mut vertices = tree
+                .simplices
+                .get(&id)
+                .unwrap()
+                .vertices
+                .map(|vertex| vertex - DelaunayTree::<N, M>::N_SUPER_VERTICES);
+            vertices.sort();
+            vertices
+        })
+        .collect()
+}
+
+#[test]
+fn test_streaming_2d() {
+    let upper = [10.0, 1.0];
+    let points = sorted_points::<2>(5000, upper, 0);
+    let mut stream = StreamingTriangulation::<2, 3, _>::new([0.0, 0.0], upper, vec![]);
+    for chunk in points.chunks(250) {
+        stream.push_chunk(chunk).unwrap();
+    }
+    assert_eq!(stream.report().points, 5000);
+    let (simplices, report) = stream.finish().unwrap();
+    assert_eq!(report.chunks, 20);
+    assert_eq!(report.simplices_written, simplices.len());
+
+    // Same triangulation as in memory, with the same super-simplex
+    let corners = vec![[0.0, 0.0], [10.0, 0.0], [0.0, 1.0], [10.0, 1.0]];
+    let mut tree = DelaunayTree::<2, 3>::new(corners);
+    tree.insert_points(&points);
+    let expected = tree_set(&tree);
+    assert_eq!(simplices.len(), expected.len());
+    assert_eq!(simplex_set(&simplices), expected);
+    for simplex in &simplices {
+        for (vertex, coordinates) in simplex.vertices.iter().zip(simplex.coordinates) {
+            assert_eq!(points[*vertex], coordinates);
+        }
+    }
+    // Only the simplices around the front stay in memory, including along the hull
+    assert!(report.max_simplices_in_memory < simplices.len() / 20);
+    assert!(report.max_vertices_in_memory < 5000 / 20);
+}
+
+#[test]
+fn test_streaming_memory_does_not_grow() {
+    // Hull simplices are evicted too, so a strip four times longer needs no more memory
+    let mut max_simplices = vec![];
+    for length in [10.0, 40.0] {
+        let points = sorted_points::<2>(500 * length as usize, [length, 1.0], 3);
+        let mut stream = StreamingTriangulation::<2, 3, _>::new([0.0, 0.0], [length, 1.0], vec![]);
+        for chunk in points.chunks(250) {
+            stream.push_chunk(chunk).unwrap();
+        }
+        let (_, report) = stream.finish().unwrap();
+        max_simplices.push(report.max_simplices_in_memory);
+    }
+    assert!(
+        max_simplices[1] < max_simplices[0] * 3 / 2,
+        "{:?}",
+        max_simplices
+    );
+}
+
+#[test]
+fn test_streaming_3d() {
+    let upper = [4.0, 1.0, 1.0];
+    let points = sorted_points::<3>(4000, upper, 1);
+    let mut outputs = vec![];
+    for parallelism in [Parallelism::Serial, Parallelism::Global] {
+        let mut stream = StreamingTriangulation::<3, 4, _>::new([0.0; 3], upper, vec![])
+            .with_parallelism(parallelism);
+        for chunk in points.chunks(200) {
+            stream.push_chunk(chunk).unwrap();
+        }
+        let (simplices, report) = stream.finish().unwrap();
+        assert!(report.max_simplices_in_memory < simplices.len() / 2);
+        outputs.push(simplex_set(&simplices));
+    }
+    assert_eq!(outputs[0], outputs[1]);
+
+    let corners = (0..8)
+        .map(|bits| [0, 1, 2].map(|k| if (bits >> k) & 1 == 1 { upper[k] } else { 0.0 }))
+        .collect();
+    let mut tree = DelaunayTree::<3, 4>::new(corners);
+    tree.insert_points(&points);
+    assert_eq!(outputs[0], tree_set(&tree));
+}
+
+#[test]
+fn test_binary_sink() {
+    let points = sorted_points::<3>(500, [1.0; 3], 2);
+    let mut in_memory = StreamingTriangulation::<3, 4, _>::new([0.0; 3], [1.0; 3], vec![]);
+    let mut on_disk =
+        StreamingTriangulation::<3, 4, _>::new([0.0; 3], [1.0; 3], BinarySink::new(vec![]));
+    for chunk in points.chunks(100) {
+        in_memory.push_chunk(chunk).unwrap();
+        on_disk.push_chunk(chunk).unwrap();
+    }
+    let (expected, _) = in_memory.finish().unwrap();
+    let (sink, report) = on_disk.finish().unwrap();
+    assert_eq!(sink.simplices_written, report.simplices_written);
+    let bytes = sink.into_inner();
+    assert_eq!(bytes.len(), expected.len() * 8 * (4 + 12 + 3 + 1));
+    let simplices = read_simplices::<3, 4, _>(&bytes[..])
+        .collect::<io::Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(simplices, expected);
+    // A truncated record ends the iteration with an error
+    let record = 8 * (4 + 12 + 3 + 1);
+    let truncated: Vec<_> = read_simplices::<3, 4, _>(&bytes[..2 * record + 100]).collect();
+    assert_eq!(truncated.len(), 3);
+    assert!(truncated[..2].iter().all(|simplex| simplex.is_ok()));
+    assert_eq!(
+        truncated[2].as_ref().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn test_streaming_errors() {
+    let mut stream = StreamingTriangulation::<2, 3, _>::new([0.0, 0.0], [1.0, 1.0], vec![]);
+    stream.push_chunk(&[[0.5, 0.5], [0.6, 0.2]]).unwrap();
+    // Behind the front
+    let error = stream.push_chunk(&[[0.55, 0.9]]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    // Outside the bounds
+    let error = stream.push_chunk(&[[0.7, 1.5]]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    // Rejected chunks change nothing
+    stream.push_chunk(&[[0.6, 0.9], [0.9, 0.1]]).unwrap();
+    assert_eq!(stream.report().points, 4);
+    let (simplices, _) = stream.finish().unwrap();
+    assert_eq!(simplices.len(), 2);
+}
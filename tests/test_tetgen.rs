@@ -0,0 +1,100 @@
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::tetgen::{read_node, write_ele, write_face, write_neigh, write_node};
+
+fn entries(text: &str) -> Vec<Vec<i64>> {
+    text.lines()
+        .skip(1)
+        .map(|line| {
+            line.split_whitespace()
+                .map(|x| x.parse().unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_tetgen_round_trip() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    let mut node = String::from("# random points\n300 3 0 0\n");
+    for i in 0..300 {
+        node.push_str(&format!(
+            "{} {} {} {} # point\n",
+            i + 1,
+            dist.sample(&mut rng),
+            dist.sample(&mut rng),
+            dist.sample(&mut rng)
+        ));
+    }
+    let nodes = read_node::<3, _>(node.as_bytes()).unwrap();
+    assert_eq!(nodes.first_index, 1);
+    let delaunay_tree = DelaunayTree::<3, 4>::from_node(node.as_bytes()).unwrap();
+
+    let mut output = vec![];
+    write_node(&delaunay_tree, &mut output, 1).unwrap();
+    let written = read_node::<3, _>(output.as_slice()).unwrap();
+    assert_eq!(written.points.len(), 300);
+    for (a, b) in written.points.iter().zip(nodes.points.iter()) {
+        for k in 0..3 {
+            assert!((a[k] - b[k]).abs() < 1e-12);
+        }
+    }
+
+    let mut ele = vec![];
+    write_ele(&delaunay_tree, &mut ele, 0).unwrap();
+    let ele = entries(&String::from_utf8(ele).unwrap());
+    let mut neigh = vec![];
+    write_neigh(&delaunay_tree, &mut neigh, 0).unwrap();
+    let neigh = entries(&String::from_utf8(neigh).unwrap());
+    assert_eq!(ele.len(), neigh.len());
+
+    let mut n_hull = 0;
+    for (i, line) in neigh.iter().enumerate() {
+        for (k, &neighbor) in line[1..].iter().enumerate() {
+            if neighbor < 0 {
+                n_hull += 1;
+                continue;
+            }
+            // Symmetric, and the facet opposite to vertex k is shared
+            let other = &neigh[neighbor as usize];
+            assert!(other[1..].contains(&(i as i64)));
+            let opposite = ele[i][1 + k];
+            assert!(!ele[neighbor as usize][1..].contains(&opposite));
+        }
+    }
+
+    let mut face = vec![];
+    write_face(&delaunay_tree, &mut face, 0, true).unwrap();
+    let face = entries(&String::from_utf8(face).unwrap());
+    assert_eq!(face.len(), n_hull);
+    assert!(face.iter().all(|line| line.len() == 5 && line[4] == 1));
+
+    // A triangulated closed surface has F = 2V - 4
+    let mut hull_vertices: Vec<i64> = face.iter().flat_map(|line| line[1..4].to_vec()).collect();
+    hull_vertices.sort();
+    hull_vertices.dedup();
+    assert_eq!(face.len(), 2 * hull_vertices.len() - 4);
+}
+
+#[test]
+fn test_triangle_node() {
+    let node = "4 2 1 1\n0 0.1 0.0 5.0 1\n1 1.0 0.2 5.0 1\n2 0.8 1.0 5.0 1\n3 0.5 0.4 5.0 0\n";
+    let nodes = read_node::<2, _>(node.as_bytes()).unwrap();
+    assert_eq!(nodes.first_index, 0);
+    assert_eq!(nodes.attributes[3], vec![5.0]);
+    assert_eq!(nodes.boundary_markers, Some(vec![1, 1, 1, 0]));
+    assert!(read_node::<3, _>(node.as_bytes()).is_err());
+
+    let delaunay_tree = DelaunayTree::<2, 3>::from_node(node.as_bytes()).unwrap();
+    let mut ele = vec![];
+    write_ele(&delaunay_tree, &mut ele, 0).unwrap();
+    let ele = entries(&String::from_utf8(ele).unwrap());
+    assert_eq!(ele.len(), 3);
+    let mut edge = vec![];
+    write_face(&delaunay_tree, &mut edge, 0, false).unwrap();
+    let edge = entries(&String::from_utf8(edge).unwrap());
+    assert_eq!(edge.len(), 6);
+    assert_eq!(edge.iter().filter(|line| line[3] == 1).count(), 3);
+}
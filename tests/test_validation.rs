@@ -0,0 +1,133 @@
+use voronoids::delaunay_tree::DelaunayTree;
+use voronoids::points::{
+    gaussian_mixture, lattice, perturb, uniform_box, GaussianCluster, Lattice,
+};
+use voronoids::validation::Violation;
+
+fn random_tree() -> DelaunayTree<3, 4> {
+    let points = uniform_box(2000, [0.0; 3], [1.0; 3], 0);
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
+    delaunay_tree.insert_points(&points[..500]);
+    delaunay_tree.add_points_to_tree(points[500..].to_vec());
+    delaunay_tree
+}
+
+#[test]
+fn test_valid_trees() {
+    let report = random_tree().validate();
+    assert!(report.is_valid(), "{}", report);
+    assert_eq!(report.n_vertices, 2008);
+
+    let points = uniform_box(1000, [0.0; 2], [1.0; 2], 1);
+    let mut delaunay_tree = DelaunayTree::<2, 3>::new(points.clone());
+    delaunay_tree.insert_points(&points);
+    let report = delaunay_tree.validate();
+    assert!(report.is_valid(), "{}", report);
+}
+
+#[test]
+fn test_degenerate_and_clustered_trees() {
+    // Lattices put many points on the same circle or sphere, and clusters mix dense and
+    // empty regions with a spread of simplex sizes. Exactly cospherical points need a
+    // perturbation in 3D, see test_lattice.
+    let kinds = [
+        Lattice::Cubic,
+        Lattice::FaceCentered,
+        Lattice::BodyCentered,
+        Lattice::Hexagonal,
+    ];
+    for kind in kinds {
+        let points = perturb(&lattice::<3>(kind, [5, 5, 5], 0.2, [0.0; 3]), 1e-7, 0);
+        let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
+        delaunay_tree.insert_points(&points[..50]);
+        delaunay_tree.add_points_to_tree(points[50..].to_vec());
+        let report = delaunay_tree.validate();
+        assert!(report.is_valid(), "{:?}: {}", kind, report);
+        assert_eq!(report.n_vertices, points.len() + 8);
+    }
+    for kind in kinds {
+        let points = lattice::<2>(kind, [12, 12], 0.1, [0.0; 2]);
+        let mut delaunay_tree = DelaunayTree::<2, 3>::new(points.clone());
+        delaunay_tree.insert_points(&points);
+        let report = delaunay_tree.validate();
+        assert!(report.is_valid(), "{:?}: {}", kind, report);
+    }
+
+    let clusters = [
+        GaussianCluster {
+            center: [0.2, 0.3, 0.4],
+            sigma: 0.01,
+            weight: 3.0,
+        },
+        GaussianCluster {
+            center: [0.7, 0.6, 0.5],
+            sigma: 0.1,
+            weight: 1.0,
+        },
+    ];
+    let points = gaussian_mixture(2000, &clusters, 2);
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(points.clone());
+    delaunay_tree.insert_points(&points[..500]);
+    delaunay_tree.add_points_to_tree(points[500..].to_vec());
+    let report = delaunay_tree.validate();
+    assert!(report.is_valid(), "{}", report);
+}
+
+#[test]
+fn test_violations() {
+    let delaunay_tree = random_tree();
+    let ids = delaunay_tree.real_simplices();
+
+    // Flip the orientation of one simplex
+    delaunay_tree
+        .simplices
+        .get_mut(&ids[0])
+        .unwrap()
+        .vertices
+        .swap(0, 1);
+    // Drop one side of an adjacency
+    let neighbor = delaunay_tree.simplices.get(&ids[1]).unwrap().neighbors[0];
+    delaunay_tree
+        .simplices
+        .get_mut(&neighbor)
+        .unwrap()
+        .neighbors
+        .retain(|&id| id != ids[1]);
+    // Grow a circumsphere so that it swallows other vertices
+    delaunay_tree.simplices.get_mut(&ids[2]).unwrap().radius *= 3.0;
+    // Also for a simplex with a super-simplex corner
+    let outer = delaunay_tree
+        .simplices
+        .iter()
+        .map(|simplex| *simplex.key())
+        .find(|&id| {
+            let simplex = delaunay_tree.simplices.get(&id).unwrap();
+            !DelaunayTree::<3, 4>::is_ghost_simplex(&simplex)
+                && !DelaunayTree::<3, 4>::is_real_simplex(&simplex)
+        })
+        .unwrap();
+    delaunay_tree.simplices.get_mut(&outer).unwrap().radius *= 3.0;
+
+    let report = delaunay_tree.validate();
+    assert!(!report.is_valid());
+    assert!(report.violations.iter().any(|violation| matches!(
+        violation,
+        Violation::NotPositivelyOriented { simplex, .. } if *simplex == ids[0]
+    )));
+    assert!(report.violations.contains(&Violation::AsymmetricNeighbor {
+        simplex: ids[1],
+        neighbor
+    }));
+    assert!(report.violations.iter().any(|violation| matches!(
+        violation,
+        Violation::NeighborCount { simplex, count: 3 } if *simplex == neighbor
+    )));
+    assert!(report.violations.iter().any(|violation| matches!(
+        violation,
+        Violation::NonEmptyCircumsphere { simplex, .. } if *simplex == ids[2]
+    )));
+    assert!(report.violations.iter().any(|violation| matches!(
+        violation,
+        Violation::NonEmptyCircumsphere { simplex, .. } if *simplex == outer
+    )));
+}
@@ -0,0 +1,149 @@
+use rand::distributions::{Distribution, Uniform};
+use rand::prelude::*;
+use voronoids::delaunay_tree::{DelaunayTree, TreeUpdate};
+use voronoids::vtk::{VtkFormat, VtkMesh, VTK_POLYGON, VTK_POLYHEDRON, VTK_TETRA};
+
+fn make_tree_3d(n_points: usize) -> DelaunayTree<3, 4> {
+    let mut vertices = vec![];
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    for _ in 0..n_points {
+        let point = [
+            dist.sample(&mut rng),
+            dist.sample(&mut rng),
+            dist.sample(&mut rng),
+        ];
+        vertices.push(point);
+    }
+    let mut delaunay_tree = DelaunayTree::<3, 4>::new(vertices.clone());
+    let n_points = delaunay_tree.vertices.len();
+    for (i, &vertex) in vertices.iter().enumerate() {
+        let update = TreeUpdate::new(n_points + i, vertex, &delaunay_tree);
+        delaunay_tree.insert_point(&update);
+    }
+    delaunay_tree
+}
+
+fn central_mean<const N: usize, const M: usize>(
+    delaunay_tree: &DelaunayTree<N, M>,
+    generators: &[usize],
+    values: &[f64],
+) -> f64 {
+    let central: Vec<f64> = generators
+        .iter()
+        .zip(values.iter())
+        .filter(|(id, _)| {
+            let vertex = delaunay_tree.vertices.get(id).unwrap();
+            vertex.coordinates.iter().all(|&x| x > 0.25 && x < 0.75)
+        })
+        .map(|(_, &value)| value)
+        .collect();
+    central.iter().sum::<f64>() / central.len() as f64
+}
+
+#[test]
+fn test_delaunay_mesh() {
+    let delaunay_tree = make_tree_3d(500);
+    let mesh = VtkMesh::delaunay(&delaunay_tree)
+        .with_radius(&delaunay_tree)
+        .with_volume(&delaunay_tree);
+    assert_eq!(mesh.points.len(), 500);
+    assert!(mesh
+        .cell_types
+        .iter()
+        .all(|&cell_type| cell_type == VTK_TETRA));
+    assert!(mesh.cells.iter().flatten().all(|&i| i < 500));
+
+    // The real simplices tile the convex hull of points in the unit cube
+    let (_, volume) = &mesh.cell_data[1];
+    let total: f64 = volume.iter().sum();
+    assert!(total > 0.8 && total < 1.0, "hull volume {}", total);
+
+    let mut legacy = vec![];
+    mesh.write(&mut legacy, VtkFormat::LegacyAscii).unwrap();
+    let legacy = String::from_utf8(legacy).unwrap();
+    assert!(legacy.starts_with("# vtk DataFile Version"));
+    assert!(legacy.contains(&format!(
+        "CELLS {} {}",
+        mesh.cells.len(),
+        5 * mesh.cells.len()
+    )));
+    assert!(legacy.contains("SCALARS radius double 1"));
+
+    let mut vtu = vec![];
+    mesh.write(&mut vtu, VtkFormat::XmlBinary).unwrap();
+    let header = String::from_utf8_lossy(&vtu[..200]);
+    assert!(header.contains("UnstructuredGrid"));
+    // Points, connectivity, offsets, types and two cell fields, each with an 8 byte size header
+    let n_cells = mesh.cells.len();
+    let appended = 8 * 6 + 8 * 3 * 500 + 8 * 4 * n_cells + 8 * n_cells + n_cells + 2 * 8 * n_cells;
+    let marker = b"<AppendedData encoding=\"raw\">\n   _";
+    let start = vtu
+        .windows(marker.len())
+        .position(|window| window == marker)
+        .unwrap()
+        + marker.len();
+    let end = vtu.len() - "\n  </AppendedData>\n</VTKFile>\n".len();
+    assert_eq!(end - start, appended);
+}
+
+#[test]
+fn test_voronoi_mesh() {
+    let delaunay_tree = make_tree_3d(500);
+    let mesh = VtkMesh::voronoi(&delaunay_tree).with_volume(&delaunay_tree);
+    assert!(!mesh.cells.is_empty());
+    assert!(mesh
+        .cell_types
+        .iter()
+        .all(|&cell_type| cell_type == VTK_POLYHEDRON));
+    for (cell, faces) in mesh.cells.iter().zip(mesh.cell_faces.iter()) {
+        // Closed convex polyhedra satisfy Euler's formula V - E + F = 2
+        let n_edges: usize = faces.iter().map(|face| face.len()).sum::<usize>() / 2;
+        assert_eq!(cell.len() + faces.len(), n_edges + 2);
+    }
+    let (_, volume) = &mesh.cell_data[0];
+    assert!(volume.iter().all(|&v| v > 0.0));
+    // Cells away from the hull take up 1 / 500 of the cube on average
+    let mean = central_mean(&delaunay_tree, &mesh.cell_ids, volume);
+    assert!(
+        mean > 0.5 / 500.0 && mean < 2.0 / 500.0,
+        "mean volume {}",
+        mean
+    );
+
+    let mut vtu = vec![];
+    mesh.write(&mut vtu, VtkFormat::XmlAscii).unwrap();
+    let vtu = String::from_utf8(vtu).unwrap();
+    assert!(vtu.contains("Name=\"faces\""));
+    assert!(vtu.contains("Name=\"faceoffsets\""));
+
+    let mut legacy = vec![];
+    mesh.write(&mut legacy, VtkFormat::LegacyBinary).unwrap();
+    assert!(legacy.starts_with(b"# vtk DataFile Version"));
+}
+
+#[test]
+fn test_voronoi_mesh_2d() {
+    let mut delaunay_tree = DelaunayTree::<2, 3>::new(vec![[0.0, 0.0], [1.0, 1.0]]);
+    let mut rng = StdRng::seed_from_u64(0);
+    let dist = Uniform::from(0.0..1.0);
+    let n_points = delaunay_tree.vertices.len();
+    for i in 0..300 {
+        let point = [dist.sample(&mut rng), dist.sample(&mut rng)];
+        let update = TreeUpdate::new(n_points + i, point, &delaunay_tree);
+        delaunay_tree.insert_point(&update);
+    }
+    let mesh = VtkMesh::voronoi(&delaunay_tree).with_volume(&delaunay_tree);
+    assert!(mesh
+        .cell_types
+        .iter()
+        .all(|&cell_type| cell_type == VTK_POLYGON));
+    let (_, area) = &mesh.cell_data[0];
+    assert!(area.iter().all(|&a| a > 0.0));
+    let mean = central_mean(&delaunay_tree, &mesh.cell_ids, area);
+    assert!(
+        mean > 0.5 / 300.0 && mean < 2.0 / 300.0,
+        "mean area {}",
+        mean
+    );
+}